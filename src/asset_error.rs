@@ -0,0 +1,41 @@
+//! Asset errors - What can go wrong when loading meshes, materials and textures
+//!
+//! Line and column numbers are 1-based and point at the offending token, so
+//! `path:line:column` can be pasted into an editor.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssetError {
+    /// The file could not be read
+    Io { path: String, message: String },
+    /// The file is not valid for its format
+    Parse { path: String, line: usize, column: usize, message: String },
+    /// A face references a vertex, texture coordinate or normal that does not exist
+    IndexOutOfRange { path: String, line: usize, column: usize, kind: &'static str, index: i64, count: usize },
+    /// The mesh has no faces to draw
+    EmptyMesh { path: String },
+    /// The image could not be decoded or has invalid dimensions
+    Image { path: String, message: String },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Io { path, message } => write!(f, "cannot read '{}': {}", path, message),
+            AssetError::Parse { path, line, column, message } => write!(f, "{}:{}:{}: {}", path, line, column, message),
+            AssetError::IndexOutOfRange { path, line, column, kind, index, count } => {
+                write!(f, "{}:{}:{}: {} index {} is out of range ", path, line, column, kind, index)?;
+                if *count == 0 {
+                    write!(f, "(no {}s defined before this line)", kind)
+                } else {
+                    write!(f, "(valid: 1 to {} or -1 to -{})", count, count)
+                }
+            }
+            AssetError::EmptyMesh { path } => write!(f, "'{}' has no faces", path),
+            AssetError::Image { path, message } => write!(f, "invalid image '{}': {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
@@ -0,0 +1,140 @@
+//! Benchmark - Mide el rasterizer sobre la escena actual
+//!
+//! Graba los triángulos del cuadro de `--start` (los mismos que dibujaría el
+//! renderer) y los rasteriza de dos formas, con el mismo depth test y el
+//! color del vértice como sombreado:
+//!
+//! - Referencia: `rasterize_triangle_reference`, que evalúa `edge_function`
+//!   en cada píxel y junta los fragmentos de cada triángulo en un `Vec`.
+//! - Streaming: `rasterize_triangle`, con edge functions incrementales que
+//!   entregan cada fragmento directamente al framebuffer.
+//!
+//! Al final mide también el cuadro completo con el renderer. Cada medición
+//! se repite `--frames` veces después de una pasada de calentamiento.
+//!
+//! Uso:
+//! ```text
+//! cargo run --release -- --headless --benchmark --frames 20 --start 3
+//! ```
+
+use std::time::{Duration, Instant};
+
+use crate::framebuffer::Framebuffer;
+use crate::headless::{build_scene, HeadlessConfig};
+use crate::primitive_assembly::Triangle;
+use crate::rasterizer::{rasterize_triangle, rasterize_triangle_reference};
+use crate::tiles::{PixelRect, Primitive};
+
+/// Tiempo medio de una pasada y fragmentos que generó
+struct Measurement {
+    time: Duration,
+    fragments: usize,
+}
+
+impl Measurement {
+    fn fragments_per_second(&self) -> f64 {
+        self.fragments as f64 / self.time.as_secs_f64()
+    }
+}
+
+/// Ejecuta el benchmark e imprime los resultados
+pub fn run(config: &HeadlessConfig) -> Result<(), String> {
+    let (mut solar_system, camera, mut renderer) = build_scene(config)?;
+    solar_system.update(config.start_time);
+
+    let view_matrix = camera.get_view_matrix();
+    let lighting = renderer.scene_lighting(&solar_system);
+    let triangles: Vec<Triangle> = renderer
+        .record_scene(&solar_system, &camera, &view_matrix, &lighting)
+        .triangles()
+        .copied()
+        .collect();
+
+    let mut framebuffer = Framebuffer::new(config.width, config.height);
+    let screen = PixelRect::screen(config.width, config.height);
+    let iterations = config.frames;
+
+    println!(
+        "Escena: {} triángulos, {}x{} píxeles, t = {:.3}, {} repeticiones",
+        triangles.len(), config.width, config.height, config.start_time, iterations
+    );
+
+    let reference = measure(iterations, || draw_reference(&mut framebuffer, &screen, &triangles));
+    print_measurement("Referencia (Vec<Fragment>, edge_function por píxel)", &reference);
+
+    let streaming = measure(iterations, || draw_streaming(&mut framebuffer, &screen, &triangles));
+    print_measurement("Streaming (edge functions incrementales)", &streaming);
+
+    println!(
+        "Aceleración: {:.2}x",
+        streaming.fragments_per_second() / reference.fragments_per_second()
+    );
+
+    let frame = measure(iterations, || {
+        framebuffer.clear();
+        renderer.render_solar_system_to_framebuffer(&mut framebuffer, &solar_system, &camera, config.show_orbits);
+        0
+    });
+    println!(
+        "Cuadro completo ({} hilos): {:.2} ms ({:.1} fps)",
+        renderer.threads,
+        frame.time.as_secs_f64() * 1000.0,
+        1.0 / frame.time.as_secs_f64()
+    );
+
+    Ok(())
+}
+
+/// Repite `pass` y devuelve el tiempo medio. `pass` devuelve la cantidad de
+/// fragmentos, que es la misma en todas las pasadas.
+fn measure(iterations: u32, mut pass: impl FnMut() -> usize) -> Measurement {
+    let fragments = pass(); // Calentamiento
+    let start = Instant::now();
+    for _ in 0..iterations {
+        std::hint::black_box(pass());
+    }
+    Measurement { time: start.elapsed() / iterations, fragments }
+}
+
+fn print_measurement(label: &str, measurement: &Measurement) {
+    println!(
+        "{}: {:.2} ms, {} fragmentos, {:.1} Mfragmentos/s",
+        label,
+        measurement.time.as_secs_f64() * 1000.0,
+        measurement.fragments,
+        measurement.fragments_per_second() / 1e6
+    );
+}
+
+fn draw_reference(framebuffer: &mut Framebuffer, screen: &PixelRect, triangles: &[Triangle]) -> usize {
+    framebuffer.clear_depth();
+    let mut count = 0;
+    for triangle in triangles {
+        let Some(bounds) = Primitive::Triangle(*triangle).bounds(screen) else {
+            continue;
+        };
+        let fragments = rasterize_triangle_reference(triangle, bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y);
+        count += fragments.len();
+        for fragment in &fragments {
+            let (x, y) = (fragment.screen_x as u32, fragment.screen_y as u32);
+            framebuffer.set_pixel_with_depth(x, y, fragment.depth, fragment.varyings.color);
+        }
+    }
+    count
+}
+
+fn draw_streaming(framebuffer: &mut Framebuffer, screen: &PixelRect, triangles: &[Triangle]) -> usize {
+    framebuffer.clear_depth();
+    let mut count = 0;
+    for triangle in triangles {
+        let Some(bounds) = Primitive::Triangle(*triangle).bounds(screen) else {
+            continue;
+        };
+        rasterize_triangle(triangle, bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y, |fragment| {
+            count += 1;
+            let (x, y) = (fragment.screen_x as u32, fragment.screen_y as u32);
+            framebuffer.set_pixel_with_depth(x, y, fragment.depth, fragment.varyings.color);
+        });
+    }
+    count
+}
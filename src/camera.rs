@@ -19,6 +19,19 @@ fn normalize_vector(v: Vector3) -> Vector3 {
     }
 }
 
+/// Altura mínima del ojo sobre la superficie. El renderer recorta los
+/// triángulos contra el near plane, así que basta con no atravesar la
+/// superficie; con el doble del near plane la superficie no se corta
+const MIN_ALTITUDE: f32 = 1.0;
+
+/// Distancia mínima entre el ojo y el centro de un cuerpo. `mesh_radius`
+/// es el radio de la malla de esfera con que se dibujan los cuerpos
+/// (`LodChain::bounding_radius`): la superficie dibujada está a
+/// `radius * mesh_radius` del centro.
+fn min_eye_distance(body: &CelestialBody, mesh_radius: f32) -> f32 {
+    body.radius * mesh_radius + MIN_ALTITUDE
+}
+
 pub struct Camera {
     // Camera position/orientation
     pub eye: Vector3,        // Camera position
@@ -123,10 +136,8 @@ impl Camera {
     pub fn zoom(&mut self, delta: f32) {
         if !self.is_warping {
             self.distance -= delta * self.zoom_speed;
-            // OPTIMIZACIÓN: Límite mínimo de distancia más restrictivo
-            // Evita que la cámara se acerque tanto que genere millones de fragmentos
-            // El límite de 10.0 evita problemas de rendering en planetas grandes
-            self.distance = self.distance.clamp(10.0, 1000.0);
+            // La distancia a la superficie la limita enforce_minimum_distance
+            self.distance = self.distance.clamp(MIN_ALTITUDE, 1000.0);
         }
     }
 
@@ -202,13 +213,13 @@ impl Camera {
         self.warp_progress = 0.0;
     }
 
-    // Warp al sol (estrella central)
-    pub fn warp_to_sun(&mut self) {
+    // Warp a una estrella (donde esté: puede orbitar un baricentro)
+    pub fn warp_to_star(&mut self, star: &CelestialBody) {
         if !self.is_warping {
             self.warp_start_pos = self.eye;
             
-            let sun_position = Vector3::new(0.0, 0.0, 0.0); // Asumiendo que el sol esta en el centro
-            let warp_distance = 80.0; // Distancia fija para el sol
+            let sun_position = star.position;
+            let warp_distance = (star.radius * 4.0 + 20.0).max(80.0); // Al menos la distancia de siempre
             
             let offset = Vector3::new(warp_distance, warp_distance * 0.4, warp_distance * 0.6);
             self.warp_target_pos = sun_position + offset;
@@ -281,19 +292,13 @@ impl Camera {
         }
     }
 
-    /// Aplica límites de proximidad inteligentes a la cámara
-    /// 
-    /// Esta función previene que la cámara se acerque demasiado a objetos grandes,
-    /// lo cual causaría:
-    /// 1. Generación de millones de fragmentos (bounding boxes gigantes)
-    /// 2. Caídas severas de FPS (de 60 a <10 FPS)
-    /// 3. Overflow de memoria en el vector de fragmentos
-    /// 
-    /// Límites aplicados:
-    /// - Distancia mínima absoluta: 10.0 unidades
-    /// - Distancia mínima relativa: 2.5x el radio del cuerpo más cercano
-    /// - Esto garantiza que la bounding box del planeta nunca exceda ~3x el tamaño de pantalla
-    pub fn enforce_minimum_distance(&mut self, bodies: &[CelestialBody]) {
+    /// Mantiene la cámara fuera del cuerpo celeste más cercano
+    ///
+    /// Los triángulos que cruzan el near plane se recortan en clip space, así
+    /// que la cámara puede acercarse hasta `MIN_ALTITUDE` sobre la superficie.
+    /// El rasterizer limita cada triángulo a la pantalla, por lo que un
+    /// planeta que la llena no genera más fragmentos que píxeles.
+    pub fn enforce_minimum_distance(&mut self, bodies: &[CelestialBody], mesh_radius: f32) {
         if self.is_warping {
             return; // No interferir durante warp
         }
@@ -312,12 +317,8 @@ impl Camera {
         
         // Si hay un cuerpo cercano, aplicar límite dinámico
         if let Some(body) = closest_body {
-            // Límite mínimo: 2.5x el radio del planeta
-            // Esto asegura que incluso planetas grandes (radio 15) tengan
-            // una distancia mínima de 37.5, evitando triangulos gigantes en pantalla
-            let dynamic_min_distance = body.radius * 2.5;
-            let absolute_min_distance = 10.0f32.max(dynamic_min_distance);
-            
+            let absolute_min_distance = min_eye_distance(body, mesh_radius);
+
             // Si estamos demasiado cerca, empujar la cámara hacia atrás
             if min_distance < absolute_min_distance {
                 let direction = normalize_vector(self.eye - body.position);
@@ -330,15 +331,15 @@ impl Camera {
     }
 
     // Check if camera would collide with a celestial body
-    pub fn check_collision(&self, body: &CelestialBody) -> bool {
+    pub fn check_collision(&self, body: &CelestialBody, mesh_radius: f32) -> bool {
         let distance_to_body = vector_length(self.eye - body.position);
-        distance_to_body < body.radius * 1.5 // Add some buffer
+        distance_to_body < min_eye_distance(body, mesh_radius)
     }
 
     // Check if camera is currently near any celestial body (for UI display)
-    pub fn get_collision_status(&self, bodies: &[CelestialBody]) -> Option<String> {
+    pub fn get_collision_status(&self, bodies: &[CelestialBody], mesh_radius: f32) -> Option<String> {
         for body in bodies {
-            if self.check_collision(body) {
+            if self.check_collision(body, mesh_radius) {
                 return Some(format!("Near {}", body.name));
             }
         }
@@ -346,23 +347,19 @@ impl Camera {
     }
 
     // Prevent camera from going through celestial bodies
-    pub fn avoid_collision(&mut self, bodies: &[CelestialBody]) {
+    pub fn avoid_collision(&mut self, bodies: &[CelestialBody], mesh_radius: f32) {
         for body in bodies {
-            if self.check_collision(body) {
+            if self.check_collision(body, mesh_radius) {
                 // Calculate direction away from the body
                 let direction = normalize_vector(self.eye - body.position);
-                let safe_distance = body.radius * 1.6; // Increased buffer
-                
+                let safe_distance = min_eye_distance(body, mesh_radius);
+
                 // Push camera to safe distance
                 self.eye = body.position + direction * safe_distance;
-                
-                // Update distance to maintain camera behavior
+
+                // Update distance to maintain camera behavior. The target
+                // stays put so the camera keeps orbiting the body's center
                 self.distance = vector_length(self.eye - self.target);
-                
-                // Optionally update target to maintain camera orientation
-                if vector_length(self.target - body.position) < body.radius * 1.2 {
-                    self.target = body.position + direction * (body.radius * 2.0);
-                }
             }
         }
     }
@@ -1,17 +1,52 @@
 #![allow(dead_code)]
 use raylib::prelude::*;
-use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::fragment_shader::{Material, SurfacePreset};
+use crate::matrix::{create_scale_matrix, create_translation_matrix, Matrix, Quaternion};
+use crate::orbit::OrbitalElements;
+use crate::texture::Texture;
 
 // Helper function to calculate vector length
 fn vector_length(v: Vector3) -> f32 {
     (v.x * v.x + v.y * v.y + v.z * v.z).sqrt()
 }
 
-#[derive(Debug, Clone, Copy)]
+// Default noise seed: FNV-1a hash of the name, so each body gets its own pattern
+pub fn name_seed(name: &str) -> u32 {
+    name.bytes().fold(0x811c_9dc5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+// Mass for bodies that don't specify one: unit density, so it scales with volume
+pub fn default_mass(radius: f32) -> f32 {
+    radius * radius * radius
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CelestialBodyType {
     Star,
     Planet,
     Moon,
+    /// Invisible, massless center that other nodes orbit (e.g. binary stars)
+    Barycenter,
+}
+
+/// Where a body is, how it moves and how far it has spun at one instant,
+/// in world space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodyState {
+    pub position: Vector3,
+    /// Orbital velocity (including the parent's), from the Keplerian elements
+    pub velocity: Vector3,
+    /// Spin around the body's own axis, radians
+    pub rotation_angle: f32,
+}
+
+impl BodyState {
+    /// A fixed point: the frame of bodies that orbit nothing
+    pub fn at_rest(position: Vector3) -> Self {
+        BodyState { position, velocity: Vector3::zero(), rotation_angle: 0.0 }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -20,25 +55,46 @@ pub struct CelestialBody {
     pub position: Vector3,
     pub radius: f32,
     pub color: Color,
+    /// Spin around the body's own axis, radians
     pub rotation_angle: f32,
     pub rotation_speed: f32,
+    /// Angle between the spin axis and the world up axis, radians.
+    /// The axis is tipped around world X, so it keeps a fixed direction in space.
+    pub axial_tilt: f32,
     
     // Orbital parameters
+    /// Focus of the orbit (the parent body's position)
     pub orbital_center: Vector3,
-    pub orbital_radius: f32,
-    pub orbital_angle: f32,
-    pub orbital_speed: f32,
-    pub orbital_inclination: f32,
+    /// Keplerian elements; `None` for bodies that don't orbit (stars)
+    pub orbit: Option<OrbitalElements>,
+    /// Fixed offset from the parent for bodies without an orbit (from the
+    /// origin for roots)
+    pub offset: Vector3,
+
+    // Physical state (used by the N-body mode)
+    pub mass: f32,
+    pub velocity: Vector3,
     
     // Visual properties
     pub name: String,
+    /// Shader used for the surface (stars are emissive by default)
+    pub material: Material,
+    /// Procedural pattern for the surface color, evaluated in object space
+    pub surface: SurfacePreset,
+    /// Noise seed for `surface`
+    pub surface_seed: u32,
+    /// Equirectangular albedo map; replaces `color` when present
+    pub texture: Option<Arc<Texture>>,
     pub has_rings: bool,
     pub ring_inner_radius: f32,
     pub ring_outer_radius: f32,
     pub ring_color: Color,
-    
-    // Moons (for planets)
-    pub moons: Vec<CelestialBody>,
+
+    // Light cast by stars, on top of the renderer's base light
+    /// Tint of the light (white keeps the base light's color)
+    pub light_color: Color,
+    /// Multiplies the base light's intensity
+    pub light_intensity: f32,
 }
 
 impl CelestialBody {
@@ -49,101 +105,113 @@ impl CelestialBody {
         color: Color,
         rotation_speed: f32,
     ) -> Self {
+        let body_type = CelestialBodyType::Star;
         CelestialBody {
-            body_type: CelestialBodyType::Star,
+            body_type,
             position,
             radius,
             color,
             rotation_angle: 0.0,
             rotation_speed,
+            axial_tilt: 0.0,
             orbital_center: Vector3::new(0.0, 0.0, 0.0),
-            orbital_radius: 0.0,
-            orbital_angle: 0.0,
-            orbital_speed: 0.0,
-            orbital_inclination: 0.0,
+            orbit: None,
+            offset: position,
+            mass: default_mass(radius),
+            velocity: Vector3::zero(),
+            surface_seed: name_seed(&name),
             name,
+            material: Material::for_body_type(body_type),
+            surface: SurfacePreset::Flat,
+            texture: None,
             has_rings: false,
             ring_inner_radius: 0.0,
             ring_outer_radius: 0.0,
             ring_color: Color::WHITE,
-            moons: Vec::new(),
+            light_color: Color::WHITE,
+            light_intensity: 1.0,
         }
     }
 
-    pub fn new_planet(
+    /// A star on a Keplerian orbit, e.g. one half of a binary
+    pub fn new_orbiting_star(
         name: String,
         orbital_center: Vector3,
-        orbital_radius: f32,
-        orbital_speed: f32,
+        orbit: OrbitalElements,
         radius: f32,
         color: Color,
         rotation_speed: f32,
-        orbital_inclination: f32,
     ) -> Self {
-        // Set a fixed position based on initial angle (no random movement)
-        let orbital_angle = 0.0; // Fixed starting angle
-        let position = Vector3::new(
-            orbital_center.x + orbital_radius,
-            orbital_center.y,
-            orbital_center.z,
-        );
+        Self::new_orbiting(CelestialBodyType::Star, name, orbital_center, orbit, radius, color, rotation_speed)
+    }
 
-        CelestialBody {
-            body_type: CelestialBodyType::Planet,
-            position,
-            radius,
-            color,
-            rotation_angle: 0.0,
-            rotation_speed,
-            orbital_center,
-            orbital_radius,
-            orbital_angle,
-            orbital_speed,
-            orbital_inclination,
-            name,
-            has_rings: false,
-            ring_inner_radius: 0.0,
-            ring_outer_radius: 0.0,
-            ring_color: Color::WHITE,
-            moons: Vec::new(),
-        }
+    /// A massless point that other bodies orbit; never rendered
+    pub fn new_barycenter(name: String, position: Vector3) -> Self {
+        let mut barycenter = Self::new_star(name, position, 0.0, Color::BLANK, 0.0);
+        barycenter.body_type = CelestialBodyType::Barycenter;
+        barycenter.material = Material::for_body_type(CelestialBodyType::Barycenter);
+        barycenter.mass = 0.0;
+        barycenter
+    }
+
+    pub fn new_planet(
+        name: String,
+        orbital_center: Vector3,
+        orbit: OrbitalElements,
+        radius: f32,
+        color: Color,
+        rotation_speed: f32,
+    ) -> Self {
+        Self::new_orbiting(CelestialBodyType::Planet, name, orbital_center, orbit, radius, color, rotation_speed)
     }
 
     pub fn new_moon(
         name: String,
         orbital_center: Vector3,
-        orbital_radius: f32,
-        orbital_speed: f32,
+        orbit: OrbitalElements,
         radius: f32,
         color: Color,
         rotation_speed: f32,
     ) -> Self {
-        // Set a fixed position for moon (no random movement)
-        let orbital_angle = PI / 4.0; // Fixed 45 degree angle
-        let position = Vector3::new(
-            orbital_center.x + orbital_radius * orbital_angle.cos(),
-            orbital_center.y,
-            orbital_center.z + orbital_radius * orbital_angle.sin(),
-        );
+        Self::new_orbiting(CelestialBodyType::Moon, name, orbital_center, orbit, radius, color, rotation_speed)
+    }
+
+    fn new_orbiting(
+        body_type: CelestialBodyType,
+        name: String,
+        orbital_center: Vector3,
+        orbit: OrbitalElements,
+        radius: f32,
+        color: Color,
+        rotation_speed: f32,
+    ) -> Self {
+        // Start at the epoch
+        let position = orbital_center + orbit.position_at(0.0);
 
         CelestialBody {
-            body_type: CelestialBodyType::Moon,
+            body_type,
             position,
             radius,
             color,
             rotation_angle: 0.0,
             rotation_speed,
+            axial_tilt: 0.0,
             orbital_center,
-            orbital_radius,
-            orbital_angle,
-            orbital_speed,
-            orbital_inclination: 0.0,
+            orbit: Some(orbit),
+            offset: Vector3::zero(),
+            mass: default_mass(radius),
+            velocity: Vector3::zero(),
+            surface_seed: name_seed(&name),
             name,
+            material: Material::for_body_type(body_type),
+            surface: SurfacePreset::Flat,
+            texture: None,
             has_rings: false,
             ring_inner_radius: 0.0,
             ring_outer_radius: 0.0,
             ring_color: Color::WHITE,
-            moons: Vec::new(),
+            light_color: Color::WHITE,
+            light_intensity: 1.0,
         }
     }
 
@@ -154,153 +222,179 @@ impl CelestialBody {
         self.ring_color = color;
     }
 
-    pub fn add_moon(&mut self, moon: CelestialBody) {
-        self.moons.push(moon);
+    pub fn is_barycenter(&self) -> bool {
+        self.body_type == CelestialBodyType::Barycenter
     }
 
-    pub fn update(&mut self, dt: f32) {
-        self.rotation_angle += self.rotation_speed * dt;
-        if self.rotation_angle > 2.0 * PI {
-            self.rotation_angle -= 2.0 * PI;
-        }
+    // Spin angle `time` units after epoch
+    pub fn rotation_at(&self, time: f64) -> f32 {
+        (self.rotation_speed as f64 * time).rem_euclid(std::f64::consts::TAU) as f32
+    }
 
-        if self.orbital_radius > 0.0 {
-            self.orbital_angle += self.orbital_speed * dt;
-            if self.orbital_angle > 2.0 * PI {
-                self.orbital_angle -= 2.0 * PI;
-            }
-            self.position = Vector3::new(
-                self.orbital_center.x + self.orbital_radius * self.orbital_angle.cos(),
-                self.orbital_center.y + self.orbital_radius * self.orbital_inclination.sin() * self.orbital_angle.sin(),
-                self.orbital_center.z + self.orbital_radius * self.orbital_angle.sin(),
-            );
+    /// State `time` units after epoch. `parent` is the state of the parent
+    /// node at the same time: the orbit is centered on it and its velocity is
+    /// added, so moons follow their planet. Bodies without an orbit stay at
+    /// `offset` from the parent.
+    pub fn state_at(&self, time: f64, parent: &BodyState) -> BodyState {
+        let rotation_angle = self.rotation_at(time);
+        match &self.orbit {
+            Some(orbit) => BodyState {
+                position: parent.position + orbit.position_at(time),
+                velocity: parent.velocity + orbit.velocity_at(time),
+                rotation_angle,
+            },
+            None => BodyState {
+                position: parent.position + self.offset,
+                velocity: parent.velocity,
+                rotation_angle,
+            },
         }
+    }
 
-        for moon in &mut self.moons {
-            moon.rotation_angle += moon.rotation_speed * dt;
-            if moon.rotation_angle > 2.0 * PI {
-                moon.rotation_angle -= 2.0 * PI;
-            }
-
-            moon.orbital_center = self.position;
-            if moon.orbital_radius > 0.0 {
-                moon.orbital_angle += moon.orbital_speed * dt;
-                if moon.orbital_angle > 2.0 * PI {
-                    moon.orbital_angle -= 2.0 * PI;
-                }
-                moon.position = Vector3::new(
-                    moon.orbital_center.x + moon.orbital_radius * moon.orbital_angle.cos(),
-                    moon.orbital_center.y,
-                    moon.orbital_center.z + moon.orbital_radius * moon.orbital_angle.sin(),
-                );
-            }
-        }
+    pub fn position_at(&self, time: f64, parent: &BodyState) -> Vector3 {
+        self.state_at(time, parent).position
     }
 
+    /// Move this body to where it is `time` units after epoch and return
+    /// its state, for the children. Velocities are left alone: they belong
+    /// to the N-body mode.
+    pub fn set_time(&mut self, time: f64, parent: &BodyState) -> BodyState {
+        let state = self.state_at(time, parent);
+        self.orbital_center = parent.position;
+        self.position = state.position;
+        self.rotation_angle = state.rotation_angle;
+        state
+    }
+
+    // Points along the true orbital ellipse, in world space
     pub fn get_orbit_points(&self, num_points: usize) -> Vec<Vector3> {
-        let mut points = Vec::new();
-        
-        if self.orbital_radius > 0.0 {
-            for i in 0..num_points {
-                let angle = (i as f32 / num_points as f32) * 2.0 * PI;
-                let point = Vector3::new(
-                    self.orbital_center.x + self.orbital_radius * angle.cos(),
-                    self.orbital_center.y + self.orbital_radius * self.orbital_inclination.sin() * angle.sin(),
-                    self.orbital_center.z + self.orbital_radius * angle.sin(),
-                );
-                points.push(point);
-            }
+        match &self.orbit {
+            Some(orbit) => orbit
+                .orbit_points(num_points)
+                .into_iter()
+                .map(|p| self.orbital_center + p)
+                .collect(),
+            None => Vec::new(),
         }
-        
-        points
+    }
+
+    // Farthest distance from the orbital center the body can reach (0 for stars)
+    pub fn get_orbit_extent(&self) -> f32 {
+        self.orbit.map_or(0.0, |orbit| orbit.apoapsis())
+    }
+
+    // Orientation of the equator: tilt only, without spin
+    pub fn get_tilt_rotation(&self) -> Quaternion {
+        Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), self.axial_tilt)
+    }
+
+    // Full orientation: spin around the local Y axis, then tilt
+    pub fn get_orientation(&self) -> Quaternion {
+        let spin = Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), self.rotation_angle);
+        self.get_tilt_rotation().multiply(&spin)
+    }
+
+    // Direction of the spin axis (north pole) in world space
+    pub fn get_spin_axis(&self) -> Vector3 {
+        self.get_tilt_rotation().rotate_vector(Vector3::new(0.0, 1.0, 0.0))
+    }
+
+    // Model matrix for the unit sphere: translation * tilt * spin * scale
+    pub fn get_model_matrix(&self) -> Matrix {
+        let translation = create_translation_matrix(self.position.x, self.position.y, self.position.z);
+        let rotation = self.get_orientation().to_matrix();
+        let scale = create_scale_matrix(self.radius, self.radius, self.radius);
+        translation.multiply(&rotation).multiply(&scale)
+    }
+
+    // Model matrix for the rings: in the tilted equatorial plane, without spin
+    pub fn get_ring_model_matrix(&self) -> Matrix {
+        let translation = create_translation_matrix(self.position.x, self.position.y, self.position.z);
+        translation.multiply(&self.get_tilt_rotation().to_matrix())
     }
 
     pub fn get_distance_from_camera(&self, camera_pos: Vector3) -> f32 {
         vector_length(self.position - camera_pos)
     }
 
+    // Distance along a ray (origin + t * direction, direction normalized) to the
+    // nearest point where it enters this body's drawn sphere, if any. The
+    // sphere mesh is scaled by `radius`, so the drawn surface is at
+    // `radius * mesh_radius` (see `LodChain::bounding_radius`).
+    pub fn intersect_ray(&self, origin: Vector3, direction: Vector3, mesh_radius: f32) -> Option<f32> {
+        let radius = self.radius * mesh_radius;
+        let oc = origin - self.position;
+        let b = oc.dot(direction);
+        let c = oc.dot(oc) - radius * radius;
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let sqrt_d = discriminant.sqrt();
+        let near = -b - sqrt_d;
+        let far = -b + sqrt_d;
+        if near > 0.0 {
+            Some(near)
+        } else if far > 0.0 {
+            // Origin inside the sphere
+            Some(0.0)
+        } else {
+            None
+        }
+    }
+
     pub fn is_visible_from_camera(&self, camera_pos: Vector3, max_distance: f32) -> bool {
         self.get_distance_from_camera(camera_pos) <= max_distance
     }
+}
 
-    // Generate vertices for a sphere (simplified icosphere)
-    pub fn get_sphere_vertices(&self, detail_level: u32) -> Vec<Vector3> {
-        let mut vertices = Vec::new();
-        
-        // Simple sphere generation using latitude/longitude
-        let lat_bands = detail_level + 10;
-        let lon_bands = detail_level + 10;
-        
-        for lat in 0..=lat_bands {
-            let theta = (lat as f32 / lat_bands as f32) * PI;
-            let sin_theta = theta.sin();
-            let cos_theta = theta.cos();
-            
-            for lon in 0..=lon_bands {
-                let phi = (lon as f32 / lon_bands as f32) * 2.0 * PI;
-                let sin_phi = phi.sin();
-                let cos_phi = phi.cos();
-                
-                let x = cos_phi * sin_theta;
-                let y = cos_theta;
-                let z = sin_phi * sin_theta;
-                
-                vertices.push(Vector3::new(
-                    self.position.x + x * self.radius,
-                    self.position.y + y * self.radius,
-                    self.position.z + z * self.radius,
-                ));
-            }
-        }
-        
-        vertices
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orbit::OrbitalElements;
+    use std::f32::consts::PI;
+
+    fn planet() -> CelestialBody {
+        CelestialBody::new_planet(
+            "Test".to_string(),
+            Vector3::zero(),
+            OrbitalElements::circular(50.0, 1.0, 0.0),
+            2.0,
+            Color::WHITE,
+            1.0,
+        )
     }
 
-    // Get triangles for rendering
-    pub fn get_sphere_triangles(&self, detail_level: u32) -> Vec<[Vector3; 3]> {
-        let mut triangles = Vec::new();
-        let lat_bands = detail_level + 10;
-        let lon_bands = detail_level + 10;
-        
-        for lat in 0..lat_bands {
-            for lon in 0..lon_bands {
-                let first = lat * (lon_bands + 1) + lon;
-                let second = first + lon_bands + 1;
-                
-                // First triangle
-                let v1 = self.get_sphere_vertex(lat, lon, lat_bands, lon_bands);
-                let v2 = self.get_sphere_vertex(lat + 1, lon, lat_bands, lon_bands);
-                let v3 = self.get_sphere_vertex(lat, lon + 1, lat_bands, lon_bands);
-                triangles.push([v1, v2, v3]);
-                
-                // Second triangle
-                let v4 = self.get_sphere_vertex(lat + 1, lon, lat_bands, lon_bands);
-                let v5 = self.get_sphere_vertex(lat + 1, lon + 1, lat_bands, lon_bands);
-                let v6 = self.get_sphere_vertex(lat, lon + 1, lat_bands, lon_bands);
-                triangles.push([v4, v5, v6]);
-            }
-        }
-        
-        triangles
+    #[test]
+    fn test_model_matrix_applies_tilt_and_spin() {
+        let mut body = planet();
+        body.axial_tilt = std::f32::consts::FRAC_PI_2;
+
+        // The north pole of the unit sphere lands on the tilted spin axis
+        let pole = body.get_model_matrix().transform_point(Vector3::new(0.0, 1.0, 0.0));
+        let expected = body.position + body.get_spin_axis() * body.radius;
+        assert!((pole - expected).length() < 1e-4);
+        assert!((body.get_spin_axis().z - 1.0).abs() < 1e-5);
+
+        // Spinning moves equator points but never the pole
+        body.rotation_angle = PI / 2.0;
+        let pole_after = body.get_model_matrix().transform_point(Vector3::new(0.0, 1.0, 0.0));
+        let equator_before = planet().get_model_matrix().transform_point(Vector3::new(1.0, 0.0, 0.0));
+        let mut untilted = planet();
+        untilted.rotation_angle = PI / 2.0;
+        let equator_after = untilted.get_model_matrix().transform_point(Vector3::new(1.0, 0.0, 0.0));
+
+        assert!((pole_after - expected).length() < 1e-4);
+        assert!((equator_after - equator_before).length() > 1.0);
     }
-    
-    fn get_sphere_vertex(&self, lat: u32, lon: u32, lat_bands: u32, lon_bands: u32) -> Vector3 {
-        let theta = (lat as f32 / lat_bands as f32) * PI;
-        let phi = (lon as f32 / lon_bands as f32) * 2.0 * PI;
-        
-        let sin_theta = theta.sin();
-        let cos_theta = theta.cos();
-        let sin_phi = phi.sin();
-        let cos_phi = phi.cos();
-        
-        let x = cos_phi * sin_theta;
-        let y = cos_theta;
-        let z = sin_phi * sin_theta;
-        
-        Vector3::new(
-            self.position.x + x * self.radius,
-            self.position.y + y * self.radius,
-            self.position.z + z * self.radius,
-        )
+
+    #[test]
+    fn test_intersect_ray_hits_sphere_front() {
+        let body = planet();
+        let origin = body.position + Vector3::new(-10.0, 0.0, 0.0);
+        let hit = body.intersect_ray(origin, Vector3::new(1.0, 0.0, 0.0), 1.0).unwrap();
+        assert!((hit - 8.0).abs() < 1e-4);
+        assert!(body.intersect_ray(origin, Vector3::new(-1.0, 0.0, 0.0), 1.0).is_none());
     }
 }
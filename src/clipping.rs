@@ -0,0 +1,241 @@
+//! Clipping - Recorta triángulos contra el frustum en clip space
+//!
+//! Va entre el ensamblado de primitivas y el rasterizer. Los vértices llegan
+//! con su posición homogénea (x, y, z, w) de antes de la división por w; un
+//! punto está dentro del frustum si -w ≤ x, y, z ≤ w.
+//!
+//! - Los triángulos que quedan del todo fuera de alguno de los 6 planos se
+//!   descartan.
+//! - Los que cruzan el near o el far plane se recortan con Sutherland–Hodgman
+//!   en clip space, antes de dividir por w (después de dividir, un vértice
+//!   detrás de la cámara aparece reflejado delante). El polígono resultante
+//!   se vuelve a dividir en triángulos.
+//! - Los bordes de la pantalla no se recortan: el rasterizer ya limita cada
+//!   triángulo a la pantalla. Solo se recorta contra una guard band mucho
+//!   más grande (`GUARD_BAND` veces la pantalla), para que ningún vértice
+//!   pase la coordenada máxima que el rasterizer lleva a punto fijo.
+
+use raylib::prelude::*;
+
+use crate::matrix::Matrix;
+use crate::primitive_assembly::Triangle;
+use crate::varyings::{Varying, Varyings};
+use crate::vertex_shader::{clip_to_screen, VertexShaderOutput};
+
+/// Planos del frustum como distancia con signo (≥ 0 adentro)
+const FRUSTUM_PLANES: [fn(Vector4) -> f32; 6] = [
+    |p| p.w + p.z, // near
+    |p| p.w - p.z, // far
+    |p| p.w + p.x, // izquierda
+    |p| p.w - p.x, // derecha
+    |p| p.w + p.y, // abajo
+    |p| p.w - p.y, // arriba
+];
+
+/// Mitad del ancho de la guard band en NDC (la pantalla es ±1). Con
+/// viewports de hasta 2^15 píxeles deja los vértices a menos de 2^20
+/// píxeles, dentro del rango en punto fijo del rasterizer.
+const GUARD_BAND: f32 = 64.0;
+
+/// Planos contra los que se recorta: near, far y la guard band. El near va
+/// primero: después de él w > 0 y la guard band tiene sentido.
+const CLIP_PLANES: [fn(Vector4) -> f32; 6] = [
+    |p| p.w + p.z,               // near
+    |p| p.w - p.z,               // far
+    |p| GUARD_BAND * p.w + p.x, // izquierda
+    |p| GUARD_BAND * p.w - p.x, // derecha
+    |p| GUARD_BAND * p.w + p.y, // abajo
+    |p| GUARD_BAND * p.w - p.y, // arriba
+];
+
+/// Posición de un triángulo respecto al frustum
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClipTest {
+    /// Se puede rasterizar sin recortar
+    Inside,
+    /// No se ve
+    Outside,
+    /// Cruza el near o el far plane, o sale de la guard band
+    Partial,
+}
+
+/// Bits de los planos que deja afuera cada vértice
+fn outcode(position: Vector4, planes: &[fn(Vector4) -> f32]) -> u8 {
+    planes
+        .iter()
+        .enumerate()
+        .filter(|(_, plane)| plane(position) < 0.0)
+        .fold(0, |code, (bit, _)| code | (1 << bit))
+}
+
+pub fn classify(positions: [Vector4; 3]) -> ClipTest {
+    let codes = positions.map(|position| outcode(position, &FRUSTUM_PLANES));
+    if codes[0] & codes[1] & codes[2] != 0 {
+        return ClipTest::Outside; // Todos del lado de afuera del mismo plano
+    }
+    if positions.iter().all(|&position| outcode(position, &CLIP_PLANES) == 0) {
+        ClipTest::Inside
+    } else {
+        ClipTest::Partial
+    }
+}
+
+/// Recorta un triángulo y pasa a `emit` cada triángulo visible que resulta
+/// (ninguno, el original o el abanico del polígono recortado)
+pub fn clip_triangle(vertices: [VertexShaderOutput; 3], viewport_matrix: &Matrix, mut emit: impl FnMut(Triangle)) {
+    match classify(vertices.map(|v| v.clip_position)) {
+        ClipTest::Outside => {}
+        ClipTest::Inside => emit(Triangle { vertices }),
+        ClipTest::Partial => {
+            let mut polygon = vertices.to_vec();
+            for plane in &CLIP_PLANES {
+                polygon = clip_polygon(&polygon, *plane, viewport_matrix);
+                if polygon.len() < 3 {
+                    return;
+                }
+            }
+            // Abanico desde el primer vértice; conserva el sentido de giro
+            for i in 1..polygon.len() - 1 {
+                emit(Triangle::new(polygon[0], polygon[i], polygon[i + 1]));
+            }
+        }
+    }
+}
+
+/// Una pasada de Sutherland–Hodgman: conserva la parte del polígono del
+/// lado de adentro del plano
+fn clip_polygon(
+    polygon: &[VertexShaderOutput],
+    plane: fn(Vector4) -> f32,
+    viewport_matrix: &Matrix,
+) -> Vec<VertexShaderOutput> {
+    let mut output = Vec::with_capacity(polygon.len() + 1);
+    for (i, &current) in polygon.iter().enumerate() {
+        let next = polygon[(i + 1) % polygon.len()];
+        let current_distance = plane(current.clip_position);
+        let next_distance = plane(next.clip_position);
+
+        if current_distance >= 0.0 {
+            output.push(current);
+        }
+        // La arista cruza el plano: agregar el punto de corte
+        if (current_distance >= 0.0) != (next_distance >= 0.0) {
+            let t = current_distance / (current_distance - next_distance);
+            output.push(interpolate_vertex(&current, &next, t, viewport_matrix));
+        }
+    }
+    output
+}
+
+/// Vértice en el punto `t` de la arista `a`→`b`. En clip space la posición
+/// y los varyings son lineales, así que se interpolan directamente; la
+/// posición en pantalla se recalcula a partir de la nueva posición homogénea.
+fn interpolate_vertex(a: &VertexShaderOutput, b: &VertexShaderOutput, t: f32, viewport_matrix: &Matrix) -> VertexShaderOutput {
+    let clip_position = <Vector4 as Varying>::lerp(a.clip_position, b.clip_position, t);
+    let (screen_position, inv_w) = clip_to_screen(clip_position, viewport_matrix);
+
+    VertexShaderOutput {
+        clip_position,
+        screen_position,
+        inv_w,
+        // Lineal: en clip space los atributos todavía no están divididos por w
+        varyings: Varyings::lerp(&a.varyings, &b.varyings, t),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::{create_projection_matrix, create_viewport_matrix};
+    use crate::obj_loader::Vertex;
+    use crate::rasterizer::rasterize_triangle;
+    use crate::vertex_shader::vertex_shader;
+
+    const NEAR: f32 = 1.0;
+    const FAR: f32 = 100.0;
+
+    /// Triángulo en view space (la cámara mira hacia -z)
+    fn shade(points: [Vector3; 3]) -> [VertexShaderOutput; 3] {
+        let identity = Matrix::identity();
+        let projection = create_projection_matrix(std::f32::consts::PI / 2.0, 1.0, NEAR, FAR);
+        let viewport = create_viewport_matrix(0.0, 0.0, 100.0, 100.0);
+        points.map(|p| {
+            let vertex = Vertex::new(p, Vector3::new(0.0, 0.0, 1.0), Vector2::new(-p.z, 0.0));
+            vertex_shader(&vertex, Color::WHITE, &identity, &identity, &projection, &viewport)
+        })
+    }
+
+    fn clip(points: [Vector3; 3]) -> Vec<Triangle> {
+        let viewport = create_viewport_matrix(0.0, 0.0, 100.0, 100.0);
+        let mut triangles = Vec::new();
+        clip_triangle(shade(points), &viewport, |triangle| triangles.push(triangle));
+        triangles
+    }
+
+    #[test]
+    fn test_classify() {
+        let inside = shade([Vector3::new(-1.0, 0.0, -10.0), Vector3::new(1.0, 0.0, -10.0), Vector3::new(0.0, 1.0, -10.0)]);
+        assert_eq!(classify(inside.map(|v| v.clip_position)), ClipTest::Inside);
+
+        let behind = shade([Vector3::new(-1.0, 0.0, 10.0), Vector3::new(1.0, 0.0, 10.0), Vector3::new(0.0, 1.0, 10.0)]);
+        assert_eq!(classify(behind.map(|v| v.clip_position)), ClipTest::Outside);
+
+        // Fuera por la izquierda: se descarta sin recortar
+        let left = shade([Vector3::new(-50.0, 0.0, -10.0), Vector3::new(-40.0, 0.0, -10.0), Vector3::new(-45.0, 1.0, -10.0)]);
+        assert_eq!(classify(left.map(|v| v.clip_position)), ClipTest::Outside);
+
+        let crossing = shade([Vector3::new(-1.0, 0.0, -10.0), Vector3::new(1.0, 0.0, -10.0), Vector3::new(0.0, 1.0, 5.0)]);
+        assert_eq!(classify(crossing.map(|v| v.clip_position)), ClipTest::Partial);
+    }
+
+    #[test]
+    fn test_clip_against_near_plane() {
+        // Un vértice detrás de la cámara: queda un cuadrilátero (2 triángulos)
+        let triangles = clip([Vector3::new(-1.0, 0.0, -10.0), Vector3::new(1.0, 0.0, -10.0), Vector3::new(0.0, 0.0, 5.0)]);
+        assert_eq!(triangles.len(), 2);
+
+        for triangle in &triangles {
+            for vertex in &triangle.vertices {
+                // Ningún vértice delante del near plane, y la profundidad es válida
+                assert!(vertex.clip_position.w >= NEAR - 1e-4);
+                assert!(vertex.screen_position.z >= -1.0 - 1e-4 && vertex.screen_position.z <= 1.0);
+                assert!((vertex.inv_w - 1.0 / vertex.clip_position.w).abs() < 1e-6);
+                // Los atributos se interpolan sobre la arista original
+                assert!((vertex.varyings.world_position.z + vertex.varyings.uv.x).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clip_against_both_planes() {
+        // De detrás de la cámara hasta más allá del far plane
+        let triangles = clip([Vector3::new(0.0, -1.0, 5.0), Vector3::new(0.0, 1.0, -200.0), Vector3::new(-1.0, 0.0, -50.0)]);
+        assert!(!triangles.is_empty());
+        for vertex in triangles.iter().flat_map(|triangle| triangle.vertices.iter()) {
+            assert!(vertex.clip_position.w >= NEAR - 1e-3 && vertex.clip_position.w <= FAR + 1e-2);
+        }
+
+        // Del todo detrás de la cámara: nada
+        assert!(clip([Vector3::new(0.0, 0.0, 1.0), Vector3::new(1.0, 0.0, 1.0), Vector3::new(0.0, 1.0, 1.0)]).is_empty());
+    }
+    #[test]
+    fn test_clip_against_guard_band() {
+        // Rozando el near plane, con vértices a millones de píxeles: se
+        // recorta a la guard band y sigue cubriendo toda la pantalla
+        let points = [Vector3::new(-1e5, -1e5, -1.01), Vector3::new(1e5, -1e5, -1.01), Vector3::new(0.0, 1e5, -1.01)];
+        let original = shade(points);
+        assert_eq!(classify(original.map(|v| v.clip_position)), ClipTest::Partial);
+        assert!(original.iter().any(|v| v.screen_position.x.abs() > 1e6));
+
+        let triangles = clip(points);
+        let mut covered = 0;
+        for triangle in &triangles {
+            for vertex in &triangle.vertices {
+                let p = vertex.clip_position;
+                assert!(p.x.abs() <= GUARD_BAND * p.w * 1.001 && p.y.abs() <= GUARD_BAND * p.w * 1.001, "{:?}", p);
+            }
+            rasterize_triangle(triangle, 0, 0, 99, 99, |_| covered += 1);
+        }
+        assert_eq!(covered, 100 * 100);
+    }
+}
@@ -0,0 +1,249 @@
+//! Reloj de la simulación - Tiempo absoluto, pausa, reversa y escala
+//!
+//! El reloj convierte el tiempo real de cada cuadro en el intervalo de
+//! tiempo de simulación que avanza el sistema. Guarda el tiempo absoluto
+//! desde la época (t = 0, donde valen las anomalías medias de la escena) en
+//! `f64`, para no perder precisión después de muchos años simulados.
+//!
+//! Una unidad de tiempo de la simulación (la de `mean_motion` y
+//! `rotation_speed` en la escena) equivale a un día. Las escalas van de
+//! tiempo real (1 s por segundo) a 10 años por segundo, cada una entre 4 y
+//! 12,2 veces más rápida que la anterior (de 30 días a un año); la escala
+//! por defecto, 1 día por segundo, es la velocidad de siempre.
+//!
+//! En reversa el intervalo es negativo: las órbitas keplerianas y la
+//! rotación retroceden exactamente y el integrador N-body integra hacia
+//! atrás.
+
+/// Segundos simulados en una unidad de tiempo (un día)
+pub const SECONDS_PER_TIME_UNIT: f64 = 86_400.0;
+
+const MINUTE: f64 = 60.0;
+const HOUR: f64 = 60.0 * MINUTE;
+const DAY: f64 = 24.0 * HOUR;
+const YEAR: f64 = 365.25 * DAY;
+
+/// Escalas disponibles, en segundos simulados por segundo real
+pub const TIME_SCALES: [f64; 11] = [
+    1.0,
+    10.0,
+    MINUTE,
+    10.0 * MINUTE,
+    HOUR,
+    6.0 * HOUR,
+    DAY,
+    7.0 * DAY,
+    30.0 * DAY,
+    YEAR,
+    10.0 * YEAR,
+];
+
+/// Escala con la que arranca el reloj: 1 día por segundo
+const DEFAULT_SCALE: usize = 6;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationClock {
+    /// Tiempo desde la época, en unidades de tiempo
+    time: f64,
+    /// Índice en `TIME_SCALES`
+    scale_index: usize,
+    reversed: bool,
+    paused: bool,
+    /// Avanzar un cuadro aunque esté en pausa
+    step_requested: bool,
+}
+
+impl SimulationClock {
+    /// Reloj en marcha a la escala por defecto, en el tiempo `epoch`
+    pub fn new(epoch: f64) -> Self {
+        SimulationClock {
+            time: epoch,
+            scale_index: DEFAULT_SCALE,
+            reversed: false,
+            paused: false,
+            step_requested: false,
+        }
+    }
+
+    /// Avanza el reloj con el tiempo real de un cuadro y devuelve cuánto
+    /// tiempo de simulación hay que avanzar (negativo en reversa, 0 en pausa)
+    pub fn tick(&mut self, real_dt: f32) -> f32 {
+        let step = std::mem::take(&mut self.step_requested);
+        if self.paused && !step {
+            return 0.0;
+        }
+        let dt = real_dt as f64 * self.time_scale();
+        self.time += dt;
+        dt as f32
+    }
+
+    /// Tiempo desde la época, en unidades de tiempo
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Lleva el reloj a `time`, p. ej. adonde llegó el sistema cuando el
+    /// integrador N-body no alcanzó a integrar todo el cuadro
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Unidades de tiempo simuladas por segundo real, con signo
+    pub fn time_scale(&self) -> f64 {
+        let direction = if self.reversed { -1.0 } else { 1.0 };
+        direction * self.seconds_per_second() / SECONDS_PER_TIME_UNIT
+    }
+
+    /// Segundos simulados por segundo real, sin signo
+    pub fn seconds_per_second(&self) -> f64 {
+        TIME_SCALES[self.scale_index]
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn toggle_reverse(&mut self) {
+        self.reversed = !self.reversed;
+    }
+
+    /// Pausa el reloj (si no lo estaba) y avanza un solo cuadro en el
+    /// siguiente `tick`, en el sentido actual
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.step_requested = true;
+    }
+
+    /// Pasa a la escala siguiente (hasta 10 años por segundo)
+    pub fn faster(&mut self) {
+        self.scale_index = (self.scale_index + 1).min(TIME_SCALES.len() - 1);
+    }
+
+    /// Pasa a la escala anterior (hasta tiempo real)
+    pub fn slower(&mut self) {
+        self.scale_index = self.scale_index.saturating_sub(1);
+    }
+
+    /// Vuelve a la escala por defecto, hacia adelante
+    pub fn reset_scale(&mut self) {
+        self.scale_index = DEFAULT_SCALE;
+        self.reversed = false;
+    }
+
+    /// Tiempo absoluto para el HUD, p. ej. "Year 2, day 45, 06:30:00"
+    pub fn format_time(&self) -> String {
+        let seconds = self.time * SECONDS_PER_TIME_UNIT;
+        let sign = if seconds < 0.0 { "-" } else { "" };
+        let seconds = seconds.abs();
+        let years = (seconds / YEAR).floor();
+        let days = ((seconds - years * YEAR) / DAY).floor();
+        let clock = (seconds - years * YEAR - days * DAY).floor() as u64;
+        format!(
+            "{}Year {}, day {}, {:02}:{:02}:{:02}",
+            sign, years, days, clock / 3600, clock / 60 % 60, clock % 60
+        )
+    }
+
+    /// Escala para el HUD, p. ej. "1 day/s", "-10 years/s" o "real time"
+    pub fn format_scale(&self) -> String {
+        let sign = if self.reversed { "-" } else { "" };
+        let scale = self.seconds_per_second();
+        if scale == 1.0 {
+            return format!("{}real time", sign);
+        }
+        let units = [(YEAR, "year", "years"), (DAY, "day", "days"), (HOUR, "h", "h"), (MINUTE, "min", "min"), (1.0, "s", "s")];
+        let (size, singular, plural) = units.iter().find(|(size, _, _)| scale >= *size).unwrap();
+        let count = (scale / size).round();
+        format!("{}{} {}/s", sign, count, if count == 1.0 { singular } else { plural })
+    }
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_scale_is_one_time_unit_per_second() {
+        let mut clock = SimulationClock::default();
+        assert_eq!(clock.time_scale(), 1.0);
+        assert_eq!(clock.tick(0.5), 0.5);
+        assert_eq!(clock.time(), 0.5);
+        assert_eq!(clock.format_scale(), "1 day/s");
+    }
+
+    #[test]
+    fn test_pause_and_step() {
+        let mut clock = SimulationClock::new(10.0);
+        clock.toggle_pause();
+        assert_eq!(clock.tick(1.0), 0.0);
+        assert_eq!(clock.time(), 10.0);
+
+        // Un paso avanza un solo cuadro y deja el reloj en pausa
+        clock.step();
+        assert_eq!(clock.tick(0.25), 0.25);
+        assert_eq!(clock.tick(0.25), 0.0);
+        assert!(clock.is_paused());
+        assert_eq!(clock.time(), 10.25);
+
+        clock.toggle_pause();
+        assert_eq!(clock.tick(1.0), 1.0);
+    }
+
+    #[test]
+    fn test_reverse_runs_backwards_past_the_epoch() {
+        let mut clock = SimulationClock::default();
+        clock.toggle_reverse();
+        assert_eq!(clock.tick(2.0), -2.0);
+        assert_eq!(clock.time(), -2.0);
+        assert!(clock.format_time().starts_with('-'));
+        assert_eq!(clock.format_scale(), "-1 day/s");
+
+        // El paso también respeta el sentido
+        clock.step();
+        assert!(clock.tick(1.0) < 0.0);
+    }
+
+    #[test]
+    fn test_scales_are_exponential_and_clamped() {
+        for pair in TIME_SCALES.windows(2) {
+            let ratio = pair[1] / pair[0];
+            assert!((4.0..=12.2).contains(&ratio), "{} -> {}", pair[0], pair[1]);
+        }
+
+        let mut clock = SimulationClock::default();
+        for _ in 0..20 {
+            clock.slower();
+        }
+        assert_eq!(clock.seconds_per_second(), 1.0);
+        assert_eq!(clock.format_scale(), "real time");
+        for _ in 0..20 {
+            clock.faster();
+        }
+        assert_eq!(clock.format_scale(), "10 years/s");
+        assert!((clock.time_scale() - 3652.5).abs() < 1e-9);
+
+        clock.reset_scale();
+        assert_eq!(clock.time_scale(), 1.0);
+    }
+
+    #[test]
+    fn test_format_time() {
+        // 1 año, 2 días y 3,5 horas
+        let clock = SimulationClock::new(365.25 + 2.0 + 3.5 / 24.0);
+        assert_eq!(clock.format_time(), "Year 1, day 2, 03:30:00");
+    }
+}
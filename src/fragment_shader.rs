@@ -6,10 +6,41 @@
 
 use raylib::prelude::*;
 
+use crate::celestial_body::CelestialBodyType;
+
+/// Caída de intensidad por defecto: a 250 unidades la luz conserva ~75%
+pub const DEFAULT_LIGHT_FALLOFF: f32 = 5.0e-6;
+
+/// Luz puntual (por ejemplo, la estrella del sistema)
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+    /// Posición de la luz en coordenadas de mundo
+    pub position: Vector3,
+    /// Color de la luz (se multiplica canal por canal con el color base)
+    pub color: Color,
+    /// Intensidad a distancia 0
+    pub intensity: f32,
+    /// Coeficiente cuadrático de atenuación: intensidad / (1 + falloff · d²)
+    pub falloff: f32,
+}
+
+impl PointLight {
+    pub fn new(position: Vector3, color: Color, intensity: f32, falloff: f32) -> Self {
+        PointLight { position, color, intensity, falloff }
+    }
+
+    /// Intensidad que llega a una distancia `distance` de la luz
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        self.intensity / (1.0 + self.falloff * distance * distance)
+    }
+}
+
 /// Configuración de iluminación para el fragment shader
+#[derive(Debug, Clone)]
 pub struct LightingConfig {
-    /// Dirección de la luz principal (normalizada)
-    pub light_direction: Vector3,
+    /// Luces puntuales de la escena. El renderer usa la primera como
+    /// plantilla: en cada frame la reemplaza por una luz en cada estrella.
+    pub lights: Vec<PointLight>,
     /// Intensidad de luz ambiental [0.0, 1.0]
     pub ambient_intensity: f32,
     /// Intensidad de luz difusa [0.0, 1.0]
@@ -21,7 +52,7 @@ pub struct LightingConfig {
 impl Default for LightingConfig {
     fn default() -> Self {
         LightingConfig {
-            light_direction: Vector3::new(-1.0, -1.0, -1.0).normalized(),
+            lights: vec![PointLight::new(Vector3::zero(), Color::WHITE, 1.0, DEFAULT_LIGHT_FALLOFF)],
             ambient_intensity: 0.3,
             diffuse_intensity: 0.7,
             enable_lighting: true,
@@ -29,18 +60,81 @@ impl Default for LightingConfig {
     }
 }
 
+/// Material de superficie: decide qué fragment shader colorea un cuerpo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Material {
+    /// Difusa pura iluminada por las luces de la escena
+    Lambert,
+    /// Difusa más brillo especular Blinn-Phong
+    Phong { specular: f32, shininess: f32 },
+    /// Emite su propia luz (estrellas): no se sombrea con las luces de la
+    /// escena. `limb_darkening` en [0, 1]; `corona` es el ancho del halo
+    /// en radios del cuerpo (0 = sin halo).
+    Emissive { limb_darkening: f32, corona: f32 },
+}
+
+impl Material {
+    /// Oscurecimiento al limbo por defecto, parecido al del Sol visible
+    pub const DEFAULT_LIMB_DARKENING: f32 = 0.6;
+    /// Ancho del halo por defecto, en radios de la estrella
+    pub const DEFAULT_CORONA: f32 = 0.35;
+
+    pub fn emissive() -> Self {
+        Material::Emissive {
+            limb_darkening: Self::DEFAULT_LIMB_DARKENING,
+            corona: Self::DEFAULT_CORONA,
+        }
+    }
+
+    /// Material por defecto según el tipo de cuerpo
+    pub fn for_body_type(body_type: CelestialBodyType) -> Self {
+        match body_type {
+            CelestialBodyType::Star => Self::emissive(),
+            CelestialBodyType::Planet | CelestialBodyType::Moon | CelestialBodyType::Barycenter => Material::Lambert,
+        }
+    }
+}
+
+/// Colorea un fragmento con el shader que corresponde a `material`
+pub fn shade_fragment(
+    material: &Material,
+    base_color: Color,
+    world_position: Vector3,
+    normal: Vector3,
+    camera_position: Vector3,
+    config: &LightingConfig,
+) -> Color {
+    match *material {
+        Material::Lambert => fragment_shader(base_color, world_position, normal, config),
+        Material::Phong { specular, shininess } => fragment_shader_phong(
+            base_color,
+            world_position,
+            normal,
+            camera_position,
+            config,
+            specular,
+            shininess,
+        ),
+        Material::Emissive { limb_darkening, .. } => {
+            fragment_shader_star(base_color, normal, camera_position - world_position, limb_darkening)
+        }
+    }
+}
+
 /// Fragment Shader - Calcula el color final aplicando iluminación
 /// 
-/// # Modelo de Iluminación (Phong simplificado):
+/// # Modelo de Iluminación (Lambert con luces puntuales):
 /// ```text
-/// Color Final = Color Base * (Ambiente + Difuso)
+/// Color Final = Color Base * (Ambiente + Σ Difuso_i * Color_i)
 /// 
 /// Ambiente = ambient_intensity
-/// Difuso = diffuse_intensity * max(0, dot(normal, light_direction))
+/// L_i = normalize(posición_luz_i - posición_fragmento)
+/// Difuso_i = diffuse_intensity * atenuación_i(d) * max(0, dot(normal, L_i))
 /// ```
 /// 
 /// # Argumentos
 /// * `base_color` - Color base del fragmento (interpolado del vértice)
+/// * `world_position` - Posición del fragmento en coordenadas de mundo
 /// * `normal` - Normal interpolada del fragmento (debe estar normalizada)
 /// * `config` - Configuración de iluminación
 /// 
@@ -48,9 +142,29 @@ impl Default for LightingConfig {
 /// Color final con iluminación aplicada
 pub fn fragment_shader(
     base_color: Color,
+    world_position: Vector3,
     normal: Vector3,
     config: &LightingConfig,
 ) -> Color {
+    fragment_shader_with_shadows(base_color, world_position, normal, config, false, |_| false)
+}
+
+/// Fragment Shader con sombras y superficies de dos caras
+///
+/// Igual que `fragment_shader`, pero cada luz para la que `is_occluded`
+/// retorna true no aporta luz difusa. Con `two_sided` la superficie se
+/// ilumina por ambas caras (anillos delgados).
+pub fn fragment_shader_with_shadows<F>(
+    base_color: Color,
+    world_position: Vector3,
+    normal: Vector3,
+    config: &LightingConfig,
+    two_sided: bool,
+    is_occluded: F,
+) -> Color
+where
+    F: Fn(&PointLight) -> bool,
+{
     // Si la iluminación está deshabilitada, retornar color base
     if !config.enable_lighting {
         return base_color;
@@ -59,20 +173,35 @@ pub fn fragment_shader(
     // Normalizar la normal (por si acaso la interpolación la desnormalizó)
     let normalized_normal = normalize_vector3(normal);
     
-    // Calcular componente ambiental
+    // Componente ambiental, igual en los tres canales
     let ambient = config.ambient_intensity;
+    let mut light = [ambient; 3];
     
-    // Calcular componente difusa (Lambertian)
-    // dot(N, L) = cuánto apunta la superficie hacia la luz
-    let light_dir = normalize_vector3(config.light_direction);
-    let diffuse_factor = dot_product(normalized_normal, light_dir).max(0.0);
-    let diffuse = config.diffuse_intensity * diffuse_factor;
-    
-    // Iluminación total
-    let total_light = (ambient + diffuse).min(1.0);
+    // Componente difusa (Lambertian) de cada luz puntual
+    for point_light in &config.lights {
+        let to_light = Vector3::new(
+            point_light.position.x - world_position.x,
+            point_light.position.y - world_position.y,
+            point_light.position.z - world_position.z,
+        );
+        let distance = (to_light.x * to_light.x + to_light.y * to_light.y + to_light.z * to_light.z).sqrt();
+        let light_dir = normalize_vector3(to_light);
+
+        // dot(N, L) = cuánto apunta la superficie hacia la luz
+        let n_dot_l = dot_product(normalized_normal, light_dir);
+        let diffuse_factor = if two_sided { n_dot_l.abs() } else { n_dot_l.max(0.0) };
+        if diffuse_factor <= 0.0 || is_occluded(point_light) {
+            continue;
+        }
+
+        let diffuse = config.diffuse_intensity * diffuse_factor * point_light.attenuation(distance);
+        light[0] += diffuse * point_light.color.r as f32 / 255.0;
+        light[1] += diffuse * point_light.color.g as f32 / 255.0;
+        light[2] += diffuse * point_light.color.b as f32 / 255.0;
+    }
     
-    // Aplicar iluminación al color base
-    apply_lighting_to_color(base_color, total_light)
+    // Aplicar iluminación al color base, canal por canal
+    apply_lighting_to_color(base_color, light.map(|channel| channel.min(1.0)))
 }
 
 /// Fragment Shader para objetos emisivos (sin iluminación)
@@ -83,63 +212,468 @@ pub fn fragment_shader_emissive(base_color: Color) -> Color {
     base_color
 }
 
+/// Fragment Shader emisivo con oscurecimiento al limbo
+///
+/// El disco de una estrella se ve más oscuro hacia el borde porque ahí la
+/// línea de visión atraviesa capas más frías. Se usa la ley lineal:
+/// ```text
+/// μ = max(0, dot(normal, view_direction))
+/// I(μ) = 1 - limb_darkening * (1 - μ)
+/// ```
+/// Con `limb_darkening = 0` equivale a `fragment_shader_emissive`.
+///
+/// # Argumentos
+/// * `view_direction` - Dirección del fragmento hacia la cámara
+pub fn fragment_shader_star(
+    base_color: Color,
+    normal: Vector3,
+    view_direction: Vector3,
+    limb_darkening: f32,
+) -> Color {
+    let mu = dot_product(normalize_vector3(normal), normalize_vector3(view_direction)).max(0.0);
+    let intensity = 1.0 - limb_darkening.clamp(0.0, 1.0) * (1.0 - mu);
+    fragment_shader_emissive(apply_lighting_to_color(base_color, [intensity; 3]))
+}
+
+/// Intensidad del halo (corona) alrededor de una estrella
+///
+/// `distance` es la distancia al centro del disco en unidades del radio
+/// (1.0 = borde). El halo se extiende hasta `1 + corona` y decae de forma
+/// cuadrática. Dentro del disco vale 1: el depth test deja ver el halo solo
+/// donde la malla de la esfera no cubre el círculo completo.
+pub fn corona_intensity(distance: f32, corona: f32) -> f32 {
+    if corona <= 0.0 {
+        return 0.0;
+    }
+    let t = 1.0 - (distance - 1.0).max(0.0) / corona;
+    if t <= 0.0 {
+        0.0
+    } else {
+        t * t
+    }
+}
+
 /// Fragment Shader con iluminación Phong completa (ambiente + difusa + especular)
-/// 
+///
+/// La parte ambiental y difusa es la de `fragment_shader`; cada luz puntual
+/// suma además un brillo especular Blinn-Phong, con el color de la luz (no
+/// con el de la superficie):
+/// ```text
+/// H_i = normalize(L_i + V)
+/// Especular_i = specular * atenuación_i(d) * max(0, dot(N, H_i))^shininess
+/// ```
+///
 /// # Argumentos
 /// * `base_color` - Color base del fragmento
-/// * `normal` - Normal del fragmento (normalizada)
-/// * `view_direction` - Dirección hacia la cámara (normalizada)
-/// * `light_direction` - Dirección hacia la luz (normalizada)
-/// * `ambient` - Intensidad ambiental
-/// * `diffuse` - Intensidad difusa
+/// * `world_position` - Posición del fragmento en coordenadas de mundo
+/// * `normal` - Normal del fragmento
+/// * `camera_position` - Posición de la cámara (para la dirección de vista)
+/// * `config` - Luces de la escena e intensidades ambiental y difusa
 /// * `specular` - Intensidad especular
 /// * `shininess` - Exponente especular (brillo)
 pub fn fragment_shader_phong(
     base_color: Color,
+    world_position: Vector3,
     normal: Vector3,
-    view_direction: Vector3,
-    light_direction: Vector3,
-    ambient: f32,
-    diffuse: f32,
+    camera_position: Vector3,
+    config: &LightingConfig,
     specular: f32,
     shininess: f32,
 ) -> Color {
+    let diffuse = fragment_shader(base_color, world_position, normal, config);
+    if !config.enable_lighting {
+        return diffuse;
+    }
+
     let n = normalize_vector3(normal);
-    let l = normalize_vector3(light_direction);
-    let v = normalize_vector3(view_direction);
-    
-    // Componente ambiental
-    let ambient_component = ambient;
-    
-    // Componente difusa
-    let n_dot_l = dot_product(n, l).max(0.0);
-    let diffuse_component = diffuse * n_dot_l;
-    
-    // Componente especular (Blinn-Phong)
-    let h = normalize_vector3(Vector3::new(
-        l.x + v.x,
-        l.y + v.y,
-        l.z + v.z,
-    ));
-    let n_dot_h = dot_product(n, h).max(0.0);
-    let specular_component = if n_dot_l > 0.0 {
-        specular * n_dot_h.powf(shininess)
+    let v = normalize_vector3(camera_position - world_position);
+    let mut highlight = [0.0f32; 3];
+    for point_light in &config.lights {
+        let to_light = point_light.position - world_position;
+        let l = normalize_vector3(to_light);
+        let strength = specular
+            * point_light.attenuation(to_light.length())
+            * blinn_phong_specular(n, l, v, shininess);
+        highlight[0] += strength * point_light.color.r as f32;
+        highlight[1] += strength * point_light.color.g as f32;
+        highlight[2] += strength * point_light.color.b as f32;
+    }
+
+    Color {
+        r: (diffuse.r as f32 + highlight[0]).clamp(0.0, 255.0) as u8,
+        g: (diffuse.g as f32 + highlight[1]).clamp(0.0, 255.0) as u8,
+        b: (diffuse.b as f32 + highlight[2]).clamp(0.0, 255.0) as u8,
+        a: diffuse.a,
+    }
+}
+
+// ===== SUPERFICIES PROCEDURALES =====
+
+/// Patrón procedural que da el color base de la superficie de un cuerpo.
+///
+/// Se evalúa en object space, así que el patrón gira junto con el cuerpo.
+/// El color del cuerpo (`CelestialBody::color`) sirve como tono principal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfacePreset {
+    /// Color plano, sin ruido
+    Flat,
+    /// Bandas de latitud turbulentas
+    GasGiant,
+    /// Roca con cráteres
+    Rocky,
+    /// Casquetes polares de hielo
+    IceCaps,
+    /// Continentes, océanos y nieve en las cumbres
+    Continents,
+    /// Corteza oscura con grietas de lava incandescente
+    Lava,
+}
+
+impl SurfacePreset {
+    /// Nombre usado en los archivos de escena
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "flat" => Some(SurfacePreset::Flat),
+            "gas_giant" => Some(SurfacePreset::GasGiant),
+            "rocky" => Some(SurfacePreset::Rocky),
+            "ice_caps" => Some(SurfacePreset::IceCaps),
+            "continents" => Some(SurfacePreset::Continents),
+            "lava" => Some(SurfacePreset::Lava),
+            _ => None,
+        }
+    }
+}
+
+/// Color base de la superficie en `object_position`
+///
+/// `seed` distingue cuerpos con el mismo preset. Para `Flat` retorna
+/// `base_color` sin cambios.
+pub fn surface_color(preset: SurfacePreset, object_position: Vector3, base_color: Color, seed: u32) -> Color {
+    // Sobre la esfera unitaria: el patrón no depende del tamaño de la malla
+    let p = normalize_vector3(object_position);
+
+    match preset {
+        SurfacePreset::Flat => base_color,
+        SurfacePreset::GasGiant => gas_giant_surface(p, base_color, seed),
+        SurfacePreset::Rocky => rocky_surface(p, base_color, seed),
+        SurfacePreset::IceCaps => ice_caps_surface(p, base_color, seed),
+        SurfacePreset::Continents => continents_surface(p, base_color, seed),
+        SurfacePreset::Lava => lava_surface(p, base_color, seed),
+    }
+}
+
+fn gas_giant_surface(p: Vector3, base_color: Color, seed: u32) -> Color {
+    // La turbulencia desplaza la latitud para que las bandas ondulen
+    let warped = domain_warp(p * 2.0, 0.35, seed);
+    let latitude = p.y + 0.08 * fbm(simplex_noise3, warped * 2.0, 4, 2.0, 0.5, seed);
+    let phase = hash_to_unit(seed) * std::f32::consts::TAU;
+    let bands = (latitude * 9.0 + phase).sin();
+    let detail = fbm(value_noise3, Vector3::new(p.x * 2.0, latitude * 40.0, p.z * 2.0), 3, 2.0, 0.5, seed);
+
+    let shaded = adjust_brightness(base_color, 0.75 + 0.25 * bands + 0.1 * detail);
+    let cream = Color::new(240, 225, 190, base_color.a);
+    blend_colors(shaded, cream, (bands * 0.5 + 0.5) * 0.35)
+}
+
+fn rocky_surface(p: Vector3, base_color: Color, seed: u32) -> Color {
+    let variation = fbm(perlin_noise3, p * 4.0, 5, 2.0, 0.5, seed);
+    let mut color = adjust_brightness(base_color, 0.85 + 0.25 * variation);
+
+    // Dos escalas de cráteres: pocos grandes y muchos pequeños
+    for (scale, offset) in [(3.0, 11), (7.0, 23)] {
+        let distance = cellular_noise3(p * scale, seed.wrapping_add(offset));
+        color = adjust_brightness(color, 1.0 + crater_profile(distance));
+    }
+    color
+}
+
+/// Perfil de un cráter según la distancia al centro de la celda:
+/// negativo (más oscuro) en el fondo, positivo en el borde levantado
+fn crater_profile(distance: f32) -> f32 {
+    const RADIUS: f32 = 0.3;
+    const RIM: f32 = 0.35 * RADIUS;
+    if distance < RADIUS {
+        let t = distance / RADIUS;
+        -0.25 * (1.0 - t * t)
+    } else if distance < RADIUS + RIM {
+        0.2 * (1.0 - (distance - RADIUS) / RIM)
     } else {
         0.0
+    }
+}
+
+fn ice_caps_surface(p: Vector3, base_color: Color, seed: u32) -> Color {
+    let variation = fbm(simplex_noise3, p * 3.0, 4, 2.0, 0.5, seed);
+    let color = adjust_brightness(base_color, 0.9 + 0.15 * variation);
+
+    let cap_edge = 0.72 + 0.08 * fbm(simplex_noise3, p * 5.0, 3, 2.0, 0.5, seed.wrapping_add(7));
+    let ice = Color::new(235, 245, 255, base_color.a);
+    blend_colors(color, ice, smoothstep(cap_edge - 0.04, cap_edge + 0.04, p.y.abs()))
+}
+
+fn continents_surface(p: Vector3, base_color: Color, seed: u32) -> Color {
+    const SEA_LEVEL: f32 = 0.0;
+    let q = domain_warp(p * 1.5, 0.5, seed);
+    let height = fbm(simplex_noise3, q * 2.0, 6, 2.0, 0.5, seed.wrapping_add(1));
+
+    let surface = if height < SEA_LEVEL {
+        let deep = Color::new(10, 30, 90, base_color.a);
+        let shallow = Color::new(30, 90, 160, base_color.a);
+        blend_colors(deep, shallow, smoothstep(-0.4, SEA_LEVEL, height))
+    } else {
+        let highlands = Color::new(120, 100, 70, base_color.a);
+        let snow = Color::new(240, 240, 240, base_color.a);
+        let land = blend_colors(base_color, highlands, smoothstep(SEA_LEVEL, 0.5, height));
+        blend_colors(land, snow, smoothstep(0.5, 0.6, height))
     };
-    
-    let total_light = (ambient_component + diffuse_component + specular_component).min(1.0);
-    
-    apply_lighting_to_color(base_color, total_light)
+
+    let cap_edge = 0.85 + 0.05 * fbm(simplex_noise3, p * 6.0, 2, 2.0, 0.5, seed.wrapping_add(2));
+    let ice = Color::new(240, 245, 250, base_color.a);
+    blend_colors(surface, ice, smoothstep(cap_edge - 0.02, cap_edge + 0.02, p.y.abs()))
+}
+
+fn lava_surface(p: Vector3, base_color: Color, seed: u32) -> Color {
+    let q = domain_warp(p * 2.0, 0.4, seed);
+    // 1 - |ruido| forma crestas finas donde el ruido cruza por cero
+    let cracks = 1.0 - perlin_noise3(q * 4.0, seed).abs();
+    let glow = smoothstep(0.82, 0.97, cracks);
+
+    let crust = adjust_brightness(base_color, 0.25 + 0.15 * fbm(value_noise3, p * 6.0, 3, 2.0, 0.5, seed));
+    let hot = blend_colors(Color::new(255, 80, 0, base_color.a), Color::new(255, 220, 60, base_color.a), glow);
+    blend_colors(crust, hot, glow)
+}
+
+// ===== RUIDO =====
+
+/// Hash entero de una celda de la red 3D
+#[inline]
+fn hash3(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    let mut h = seed
+        ^ (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297a_2d39);
+    h ^= h >> 15;
+    h
+}
+
+/// Convierte un hash en un número en [0, 1]
+#[inline]
+fn hash_to_unit(h: u32) -> f32 {
+    (h & 0x00ff_ffff) as f32 / 0x00ff_ffff as f32
+}
+
+/// Curva quíntica de Perlin: derivadas primera y segunda nulas en 0 y 1
+#[inline]
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
 }
 
-/// Aplica un factor de iluminación a un color
 #[inline]
-fn apply_lighting_to_color(color: Color, light_factor: f32) -> Color {
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[inline]
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Interpolación trilineal de los valores de las 8 esquinas de una celda
+/// (`corner(dx, dy, dz)` con dx, dy, dz ∈ {0, 1})
+#[inline]
+fn trilinear<F: Fn(i32, i32, i32) -> f32>(corner: F, u: f32, v: f32, w: f32) -> f32 {
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+    lerp(lerp(x00, x10, v), lerp(x01, x11, v), w)
+}
+
+/// Ruido de valor 3D: valores aleatorios en la red, interpolados. Rango [-1, 1]
+pub fn value_noise3(p: Vector3, seed: u32) -> f32 {
+    let (xi, yi, zi) = (p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32);
+    let (fx, fy, fz) = (p.x - p.x.floor(), p.y - p.y.floor(), p.z - p.z.floor());
+
+    trilinear(
+        |dx, dy, dz| hash_to_unit(hash3(xi + dx, yi + dy, zi + dz, seed)) * 2.0 - 1.0,
+        fade(fx),
+        fade(fy),
+        fade(fz),
+    )
+}
+
+/// Direcciones de gradiente: los 12 puntos medios de las aristas de un cubo
+const GRADIENTS: [[f32; 3]; 12] = [
+    [1.0, 1.0, 0.0], [-1.0, 1.0, 0.0], [1.0, -1.0, 0.0], [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0], [-1.0, 0.0, 1.0], [1.0, 0.0, -1.0], [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0], [0.0, -1.0, 1.0], [0.0, 1.0, -1.0], [0.0, -1.0, -1.0],
+];
+
+#[inline]
+fn gradient_dot(h: u32, x: f32, y: f32, z: f32) -> f32 {
+    let g = GRADIENTS[(h % 12) as usize];
+    g[0] * x + g[1] * y + g[2] * z
+}
+
+/// Ruido de Perlin 3D (ruido de gradiente). Rango aproximado [-1, 1]
+pub fn perlin_noise3(p: Vector3, seed: u32) -> f32 {
+    let (xi, yi, zi) = (p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32);
+    let (fx, fy, fz) = (p.x - p.x.floor(), p.y - p.y.floor(), p.z - p.z.floor());
+
+    trilinear(
+        |dx, dy, dz| {
+            gradient_dot(
+                hash3(xi + dx, yi + dy, zi + dz, seed),
+                fx - dx as f32,
+                fy - dy as f32,
+                fz - dz as f32,
+            )
+        },
+        fade(fx),
+        fade(fy),
+        fade(fz),
+    )
+}
+
+/// Ruido simplex 3D: suma las contribuciones de las 4 esquinas del
+/// tetraedro que contiene el punto. Más barato que Perlin y sin ejes
+/// preferidos. Rango aproximado [-1, 1]
+pub fn simplex_noise3(p: Vector3, seed: u32) -> f32 {
+    const F3: f32 = 1.0 / 3.0;
+    const G3: f32 = 1.0 / 6.0;
+
+    // Sesgar el espacio para encontrar la celda simplex
+    let s = (p.x + p.y + p.z) * F3;
+    let (i, j, k) = ((p.x + s).floor(), (p.y + s).floor(), (p.z + s).floor());
+    let t = (i + j + k) * G3;
+    let x0 = p.x - (i - t);
+    let y0 = p.y - (j - t);
+    let z0 = p.z - (k - t);
+
+    // Orden de los ejes: decide qué tetraedro de la celda contiene el punto
+    let (i1, j1, k1, i2, j2, k2) = if x0 >= y0 {
+        if y0 >= z0 {
+            (1, 0, 0, 1, 1, 0)
+        } else if x0 >= z0 {
+            (1, 0, 0, 1, 0, 1)
+        } else {
+            (0, 0, 1, 1, 0, 1)
+        }
+    } else if y0 < z0 {
+        (0, 0, 1, 0, 1, 1)
+    } else if x0 < z0 {
+        (0, 1, 0, 0, 1, 1)
+    } else {
+        (0, 1, 0, 1, 1, 0)
+    };
+
+    let (i, j, k) = (i as i32, j as i32, k as i32);
+    let corners = [
+        (0, 0, 0, x0, y0, z0),
+        (i1, j1, k1, x0 - i1 as f32 + G3, y0 - j1 as f32 + G3, z0 - k1 as f32 + G3),
+        (i2, j2, k2, x0 - i2 as f32 + 2.0 * G3, y0 - j2 as f32 + 2.0 * G3, z0 - k2 as f32 + 2.0 * G3),
+        (1, 1, 1, x0 - 1.0 + 3.0 * G3, y0 - 1.0 + 3.0 * G3, z0 - 1.0 + 3.0 * G3),
+    ];
+
+    let mut total = 0.0;
+    for (di, dj, dk, x, y, z) in corners {
+        let falloff = 0.6 - x * x - y * y - z * z;
+        if falloff > 0.0 {
+            let falloff = falloff * falloff;
+            total += falloff * falloff * gradient_dot(hash3(i + di, j + dj, k + dk, seed), x, y, z);
+        }
+    }
+    32.0 * total
+}
+
+/// Ruido celular (Worley): distancia al punto característico más cercano,
+/// con un punto aleatorio por celda. 0 en los puntos, ~1 en los bordes
+pub fn cellular_noise3(p: Vector3, seed: u32) -> f32 {
+    let (xi, yi, zi) = (p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32);
+    let mut nearest = f32::MAX;
+
+    for dz in -1..=1 {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (cx, cy, cz) = (xi + dx, yi + dy, zi + dz);
+                let h = hash3(cx, cy, cz, seed);
+                let feature = Vector3::new(
+                    cx as f32 + hash_to_unit(h),
+                    cy as f32 + hash_to_unit(h.rotate_left(11)),
+                    cz as f32 + hash_to_unit(h.rotate_left(22)),
+                );
+                nearest = nearest.min((feature - p).length());
+            }
+        }
+    }
+    nearest
+}
+
+/// Fractional Brownian motion: suma `octaves` capas de `noise`, cada una con
+/// `lacunarity` veces más frecuencia y `gain` veces menos amplitud.
+/// Normalizada para quedar en el rango de `noise`
+pub fn fbm<F: Fn(Vector3, u32) -> f32>(
+    noise: F,
+    p: Vector3,
+    octaves: u32,
+    lacunarity: f32,
+    gain: f32,
+    seed: u32,
+) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max_amplitude = 0.0;
+
+    for octave in 0..octaves {
+        total += amplitude * noise(p * frequency, seed.wrapping_add(octave));
+        max_amplitude += amplitude;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+
+    if max_amplitude > 0.0 {
+        total / max_amplitude
+    } else {
+        0.0
+    }
+}
+
+/// Domain warping: desplaza `p` con fBm para que los patrones se vean
+/// arremolinados en lugar de isotrópicos
+pub fn domain_warp(p: Vector3, strength: f32, seed: u32) -> Vector3 {
+    let offset = Vector3::new(
+        fbm(simplex_noise3, p, 3, 2.0, 0.5, seed),
+        fbm(simplex_noise3, p + Vector3::new(5.2, 1.3, 2.8), 3, 2.0, 0.5, seed),
+        fbm(simplex_noise3, p + Vector3::new(1.7, 9.2, 4.1), 3, 2.0, 0.5, seed),
+    );
+    p + offset * strength
+}
+
+/// Término especular de Blinn-Phong: max(0, dot(N, H))^shininess con
+/// H = normalize(L + V); 0 si la luz está detrás de la superficie.
+/// Espera vectores normalizados.
+#[inline]
+fn blinn_phong_specular(n: Vector3, l: Vector3, v: Vector3, shininess: f32) -> f32 {
+    if dot_product(n, l) <= 0.0 {
+        return 0.0;
+    }
+    let h = normalize_vector3(Vector3::new(l.x + v.x, l.y + v.y, l.z + v.z));
+    dot_product(n, h).max(0.0).powf(shininess)
+}
+
+/// Aplica un factor de iluminación por canal (r, g, b) a un color
+#[inline]
+fn apply_lighting_to_color(color: Color, light: [f32; 3]) -> Color {
     Color {
-        r: (color.r as f32 * light_factor).clamp(0.0, 255.0) as u8,
-        g: (color.g as f32 * light_factor).clamp(0.0, 255.0) as u8,
-        b: (color.b as f32 * light_factor).clamp(0.0, 255.0) as u8,
+        r: (color.r as f32 * light[0]).clamp(0.0, 255.0) as u8,
+        g: (color.g as f32 * light[1]).clamp(0.0, 255.0) as u8,
+        b: (color.b as f32 * light[2]).clamp(0.0, 255.0) as u8,
         a: color.a,
     }
 }
@@ -207,12 +741,184 @@ mod tests {
         let color = Color::BLUE;
         let normal = Vector3::new(0.0, 1.0, 0.0);
         
-        let result = fragment_shader(color, normal, &config);
+        let result = fragment_shader(color, Vector3::zero(), normal, &config);
         assert_eq!(result.r, color.r);
         assert_eq!(result.g, color.g);
         assert_eq!(result.b, color.b);
     }
     
+    #[test]
+    fn test_point_light_lights_the_facing_side() {
+        let config = LightingConfig {
+            lights: vec![PointLight::new(Vector3::new(10.0, 0.0, 0.0), Color::WHITE, 1.0, 0.0)],
+            ..Default::default()
+        };
+        let color = Color::new(200, 200, 200, 255);
+        
+        // El lado que mira a la luz recibe ambiente + difusa completa
+        let day = fragment_shader(color, Vector3::zero(), Vector3::new(1.0, 0.0, 0.0), &config);
+        assert_eq!(day.r, 200);
+        
+        // El lado opuesto solo recibe luz ambiental
+        let night = fragment_shader(color, Vector3::zero(), Vector3::new(-1.0, 0.0, 0.0), &config);
+        assert_eq!(night.r, 60);
+        
+        // Con la luz del otro lado se invierte el terminador
+        let moved = LightingConfig {
+            lights: vec![PointLight::new(Vector3::new(-10.0, 0.0, 0.0), Color::WHITE, 1.0, 0.0)],
+            ..Default::default()
+        };
+        let flipped = fragment_shader(color, Vector3::zero(), Vector3::new(-1.0, 0.0, 0.0), &moved);
+        assert_eq!(flipped.r, 200);
+    }
+    
+    #[test]
+    fn test_point_light_color_and_falloff() {
+        let light = PointLight::new(Vector3::new(0.0, 10.0, 0.0), Color::new(255, 0, 0, 255), 1.0, 0.01);
+        assert!((light.attenuation(10.0) - 0.5).abs() < 1e-6);
+        
+        let config = LightingConfig { lights: vec![light], ..Default::default() };
+        let lit = fragment_shader(Color::new(100, 100, 100, 255), Vector3::zero(), Vector3::new(0.0, 1.0, 0.0), &config);
+        // Rojo: 0.3 + 0.7 * 0.5; verde y azul solo ambiente
+        assert_eq!(lit.r, 65);
+        assert_eq!(lit.g, 30);
+        assert_eq!(lit.b, 30);
+    }
+    
+    #[test]
+    fn test_star_limb_darkening() {
+        let color = Color::new(200, 200, 0, 255);
+        let view = Vector3::new(0.0, 0.0, 1.0);
+        
+        // Centro del disco: brillo completo
+        let center = fragment_shader_star(color, view, view, 0.5);
+        assert_eq!(center.r, 200);
+        
+        // Borde del disco: 1 - 0.5 = 50%
+        let limb = fragment_shader_star(color, Vector3::new(1.0, 0.0, 0.0), view, 0.5);
+        assert_eq!(limb.r, 100);
+        assert_eq!(limb.b, 0);
+        
+        // Sin oscurecimiento es igual al shader emisivo
+        assert_eq!(fragment_shader_star(color, Vector3::new(1.0, 0.0, 0.0), view, 0.0).r, 200);
+    }
+    
+    #[test]
+    fn test_emissive_material_ignores_lights() {
+        let config = LightingConfig {
+            lights: vec![PointLight::new(Vector3::zero(), Color::WHITE, 1.0, 0.0)],
+            ..Default::default()
+        };
+        let material = Material::for_body_type(CelestialBodyType::Star);
+        assert!(matches!(material, Material::Emissive { .. }));
+        
+        // La luz está en el centro de la estrella, detrás de la superficie:
+        // con Lambert el disco quedaría en penumbra, emisivo no.
+        let surface = Vector3::new(0.0, 0.0, 10.0);
+        let normal = Vector3::new(0.0, 0.0, 1.0);
+        let camera = Vector3::new(0.0, 0.0, 100.0);
+        let star = shade_fragment(&material, Color::YELLOW, surface, normal, camera, &config);
+        let lambert = shade_fragment(&Material::Lambert, Color::YELLOW, surface, normal, camera, &config);
+        assert_eq!(star.r, Color::YELLOW.r);
+        assert!(lambert.r < star.r);
+    }
+    
+    #[test]
+    fn test_blinn_phong_adds_highlight_toward_camera() {
+        let config = LightingConfig {
+            lights: vec![PointLight::new(Vector3::new(0.0, 100.0, 0.0), Color::WHITE, 1.0, 0.0)],
+            ..Default::default()
+        };
+        let color = Color::new(50, 50, 50, 255);
+        let normal = Vector3::new(0.0, 1.0, 0.0);
+        let phong = Material::Phong { specular: 0.5, shininess: 32.0 };
+        
+        // Cámara en la dirección del reflejo: brillo especular visible
+        let reflected = shade_fragment(&phong, color, Vector3::zero(), normal, Vector3::new(0.0, 50.0, 0.0), &config);
+        let lambert = shade_fragment(&Material::Lambert, color, Vector3::zero(), normal, Vector3::new(0.0, 50.0, 0.0), &config);
+        assert!(reflected.r > lambert.r + 100);
+        
+        // Cámara rasante: casi sin especular
+        let grazing = shade_fragment(&phong, color, Vector3::zero(), normal, Vector3::new(50.0, 0.5, 0.0), &config);
+        assert!(grazing.r <= lambert.r + 2);
+    }
+    
+    #[test]
+    fn test_corona_intensity_fades_out() {
+        assert_eq!(corona_intensity(0.5, 0.4), 1.0);
+        assert_eq!(corona_intensity(1.0, 0.4), 1.0);
+        assert!((corona_intensity(1.2, 0.4) - 0.25).abs() < 1e-5);
+        assert_eq!(corona_intensity(1.5, 0.4), 0.0);
+        assert_eq!(corona_intensity(1.0, 0.0), 0.0);
+    }
+    
+    fn sample_points() -> Vec<Vector3> {
+        (0..200)
+            .map(|i| {
+                let t = i as f32 * 0.37;
+                Vector3::new(t.sin() * 3.1 + t * 0.11, (t * 1.7).cos() * 2.3, t * 0.53 - 4.0)
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_noise_ranges_and_determinism() {
+        for p in sample_points() {
+            for noise in [value_noise3, perlin_noise3, simplex_noise3] {
+                let n = noise(p, 42);
+                assert!((-1.05..=1.05).contains(&n), "noise out of range: {}", n);
+                assert_eq!(n, noise(p, 42));
+            }
+            let f = fbm(simplex_noise3, p, 5, 2.0, 0.5, 42);
+            assert!((-1.05..=1.05).contains(&f));
+            assert!(cellular_noise3(p, 42) >= 0.0);
+        }
+    }
+    
+    #[test]
+    fn test_noise_is_continuous_and_seeded() {
+        let p = Vector3::new(1.3, -0.7, 2.2);
+        let nearby = p + Vector3::new(1e-3, 0.0, 0.0);
+        for noise in [value_noise3, perlin_noise3, simplex_noise3] {
+            assert!((noise(p, 7) - noise(nearby, 7)).abs() < 0.05);
+        }
+        
+        // Semillas distintas dan patrones distintos
+        let differs = sample_points().iter().any(|&q| (perlin_noise3(q, 1) - perlin_noise3(q, 2)).abs() > 0.1);
+        assert!(differs);
+        
+        // El ruido de gradiente vale 0 en los puntos de la red
+        assert_eq!(perlin_noise3(Vector3::new(2.0, -3.0, 5.0), 9), 0.0);
+    }
+    
+    #[test]
+    fn test_surface_presets() {
+        let base = Color::new(100, 150, 50, 255);
+        let p = Vector3::new(0.3, 0.2, 0.5);
+        assert_eq!(surface_color(SurfacePreset::Flat, p, base, 3), base);
+        
+        // Los patrones dependen de la dirección, no del radio de la malla
+        let gas = surface_color(SurfacePreset::GasGiant, p, base, 3);
+        assert_eq!(gas, surface_color(SurfacePreset::GasGiant, p * 4.0, base, 3));
+        
+        // Casquetes: el polo es hielo, el ecuador conserva el tono base
+        let pole = surface_color(SurfacePreset::IceCaps, Vector3::new(0.0, 1.0, 0.0), base, 3);
+        assert!(pole.r > 200 && pole.b > 200);
+        let equator = surface_color(SurfacePreset::IceCaps, Vector3::new(1.0, 0.0, 0.0), base, 3);
+        assert!(equator.g > equator.r);
+        
+        // Continentes: hay océano y tierra sobre la esfera
+        let samples: Vec<Color> = sample_points()
+            .iter()
+            .map(|&q| surface_color(SurfacePreset::Continents, q, base, 3))
+            .collect();
+        assert!(samples.iter().any(|c| c.b > c.g && c.b > c.r));
+        assert!(samples.iter().any(|c| c.g > c.b));
+        
+        assert_eq!(SurfacePreset::parse("gas_giant"), Some(SurfacePreset::GasGiant));
+        assert_eq!(SurfacePreset::parse("plasma"), None);
+    }
+    
     #[test]
     fn test_normalize_vector() {
         let v = Vector3::new(3.0, 4.0, 0.0);
@@ -1,11 +1,52 @@
 use raylib::prelude::*;
 
+/// Función de comparación usada por el depth test
+///
+/// La profundidad se guarda en NDC: valores menores están más cerca de la cámara.
+/// El renderer usa `Less`; las demás quedan para quien configure el depth test.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthCompare {
+    Never,
+    Less,
+    LessEqual,
+    Equal,
+    GreaterEqual,
+    Greater,
+    NotEqual,
+    Always,
+}
+
+impl DepthCompare {
+    /// Retorna true si el fragmento nuevo pasa la prueba contra el valor almacenado
+    #[inline]
+    pub fn passes(self, incoming: f32, stored: f32) -> bool {
+        match self {
+            DepthCompare::Never => false,
+            DepthCompare::Less => incoming < stored,
+            DepthCompare::LessEqual => incoming <= stored,
+            DepthCompare::Equal => incoming == stored,
+            DepthCompare::GreaterEqual => incoming >= stored,
+            DepthCompare::Greater => incoming > stored,
+            DepthCompare::NotEqual => incoming != stored,
+            DepthCompare::Always => true,
+        }
+    }
+}
+
 pub struct Framebuffer {
     pub pixels: Vec<Color>,
+    /// Z-buffer: una profundidad por píxel
+    pub depth: Vec<f32>,
     pub width: u32,
     pub height: u32,
     pub current_color: Color,
     pub background_color: Color,
+    /// Valor con el que se limpia el z-buffer
+    pub clear_depth_value: f32,
+    pub depth_compare: DepthCompare,
+    pub depth_test_enabled: bool,
+    pub depth_write_enabled: bool,
     texture: Option<Texture2D>,
 }
 
@@ -15,19 +56,127 @@ impl Framebuffer {
         let total_pixels = (width * height) as usize;
         Self {
             pixels: vec![Color::BLACK; total_pixels],
+            depth: vec![f32::INFINITY; total_pixels],
             width,
             height,
             current_color: Color::WHITE,
             background_color: Color::BLACK,
+            clear_depth_value: f32::INFINITY,
+            depth_compare: DepthCompare::Less,
+            depth_test_enabled: true,
+            depth_write_enabled: true,
             texture: None,
         }
     }
 
-    // Limpio el framebuffer
+    // Limpio el framebuffer (color y profundidad)
     pub fn clear(&mut self) {
         for pixel in &mut self.pixels {
             *pixel = self.background_color;
         }
+        self.clear_depth();
+    }
+
+    // Limpio solo el z-buffer
+    pub fn clear_depth(&mut self) {
+        for depth in &mut self.depth {
+            *depth = self.clear_depth_value;
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn set_depth_compare(&mut self, compare: DepthCompare) {
+        self.depth_compare = compare;
+    }
+
+    #[cfg(test)]
+    pub fn get_depth(&self, x: u32, y: u32) -> f32 {
+        if x < self.width && y < self.height {
+            self.depth[(y * self.width + x) as usize]
+        } else {
+            self.clear_depth_value
+        }
+    }
+
+    /// Prueba de profundidad sin escribir nada
+    pub fn depth_test(&self, x: u32, y: u32, depth: f32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        if !self.depth_test_enabled {
+            return true;
+        }
+        let index = (y * self.width + x) as usize;
+        self.depth_compare.passes(depth, self.depth[index])
+    }
+
+    /// Escribe un píxel solo si pasa el depth test
+    ///
+    /// Retorna true si el píxel se escribió. La profundidad se actualiza
+    /// únicamente cuando `depth_write_enabled` está activo.
+    pub fn set_pixel_with_depth(&mut self, x: u32, y: u32, depth: f32, color: Color) -> bool {
+        if !self.depth_test(x, y, depth) {
+            return false;
+        }
+        let index = (y * self.width + x) as usize;
+        self.pixels[index] = color;
+        if self.depth_write_enabled {
+            self.depth[index] = depth;
+        }
+        true
+    }
+
+    /// Mezcla un píxel translúcido (alpha "source over") si pasa el depth test
+    ///
+    /// `resultado = src * a + dst * (1 - a)`, con `a = color.a / 255`.
+    /// Igual que `set_pixel_with_depth`, solo escribe profundidad si
+    /// `depth_write_enabled` está activo; para superficies translúcidas
+    /// normalmente se desactiva.
+    pub fn blend_pixel_with_depth(&mut self, x: u32, y: u32, depth: f32, color: Color) -> bool {
+        if !self.depth_test(x, y, depth) {
+            return false;
+        }
+        let index = (y * self.width + x) as usize;
+        let dst = self.pixels[index];
+        let alpha = color.a as f32 / 255.0;
+        let mix = |src: u8, dst: u8| (src as f32 * alpha + dst as f32 * (1.0 - alpha)).round() as u8;
+
+        self.pixels[index] = Color::new(mix(color.r, dst.r), mix(color.g, dst.g), mix(color.b, dst.b), dst.a);
+        if self.depth_write_enabled {
+            self.depth[index] = depth;
+        }
+        true
+    }
+
+    /// Copia de un rectángulo del framebuffer (color, profundidad y estado
+    /// del depth test), para dibujar sobre él por separado
+    pub fn region(&self, x: u32, y: u32, width: u32, height: u32) -> Framebuffer {
+        let mut region = Framebuffer::new(width, height);
+        region.background_color = self.background_color;
+        region.current_color = self.current_color;
+        region.clear_depth_value = self.clear_depth_value;
+        region.depth_compare = self.depth_compare;
+        region.depth_test_enabled = self.depth_test_enabled;
+        region.depth_write_enabled = self.depth_write_enabled;
+
+        for row in 0..height {
+            let source = ((y + row) * self.width + x) as usize;
+            let target = (row * width) as usize;
+            region.pixels[target..target + width as usize].copy_from_slice(&self.pixels[source..source + width as usize]);
+            region.depth[target..target + width as usize].copy_from_slice(&self.depth[source..source + width as usize]);
+        }
+        region
+    }
+
+    /// Escribe de vuelta un rectángulo obtenido con `region`
+    pub fn write_region(&mut self, x: u32, y: u32, region: &Framebuffer) {
+        let width = region.width as usize;
+        for row in 0..region.height {
+            let source = (row * region.width) as usize;
+            let target = ((y + row) * self.width + x) as usize;
+            self.pixels[target..target + width].copy_from_slice(&region.pixels[source..source + width]);
+            self.depth[target..target + width].copy_from_slice(&region.depth[source..source + width]);
+        }
     }
 
     pub fn set_background_color(&mut self, color: Color) {
@@ -107,16 +256,8 @@ impl Framebuffer {
     }
 
     pub fn swap_buffers(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
-        // Crear una nueva imagen
-        let mut image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
-        
-        // Copiar píxeles del framebuffer a la imagen
-        unsafe {
-            let image_ptr = image.data as *mut Color;
-            for (i, pixel) in self.pixels.iter().enumerate() {
-                *image_ptr.add(i) = *pixel;
-            }
-        }
+        // Crear una nueva imagen con los píxeles del framebuffer
+        let image = self.to_image(false);
 
         // Solo liberar la textura anterior si existe
         if let Some(_old_texture) = self.texture.take() {
@@ -144,6 +285,74 @@ impl Framebuffer {
         }
     }
 
+    // Copia los píxeles a una imagen de raylib (solo CPU, no necesita ventana)
+    // Con flip_vertical la imagen queda orientada como se ve en pantalla,
+    // ya que draw_to_screen dibuja la textura volteada en Y.
+    fn to_image(&self, flip_vertical: bool) -> Image {
+        let image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
+
+        unsafe {
+            let image_ptr = image.data as *mut Color;
+            for y in 0..self.height {
+                let source_y = if flip_vertical { self.height - 1 - y } else { y };
+                for x in 0..self.width {
+                    let source = (source_y * self.width + x) as usize;
+                    let target = (y * self.width + x) as usize;
+                    *image_ptr.add(target) = self.pixels[source];
+                }
+            }
+        }
+
+        image
+    }
+
+    /// Guarda el framebuffer como PNG (orientado como en pantalla)
+    pub fn save_png(&self, path: &str) -> Result<(), String> {
+        let image = self.to_image(true);
+        let c_path = std::ffi::CString::new(path).map_err(|_| format!("Invalid PNG path: {}", path))?;
+
+        // Image::export_image descarta el resultado de raylib; sin él, un
+        // archivo viejo con el mismo nombre haría pasar un error por éxito
+        let exported = unsafe { raylib::ffi::ExportImage(*image, c_path.as_ptr()) };
+        if exported {
+            Ok(())
+        } else {
+            Err(format!("Error exporting PNG: {}", path))
+        }
+    }
+
+    /// Guarda el framebuffer como PPM binario (P6), orientado como en pantalla
+    pub fn save_ppm(&self, path: &str) -> std::io::Result<()> {
+        use std::io::Write;
+
+        let mut data = Vec::with_capacity(self.pixels.len() * 3 + 32);
+        write!(data, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let pixel = self.pixels[(y * self.width + x) as usize];
+                data.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+            }
+        }
+
+        std::fs::write(path, data)
+    }
+
+    /// Carga un PPM binario (P6) escrito por `save_ppm`
+    #[cfg(test)]
+    pub fn load_ppm(path: &str) -> std::io::Result<Framebuffer> {
+        let (width, height, pixels) = read_ppm(path)?;
+
+        let mut framebuffer = Framebuffer::new(width, height);
+        for (i, &color) in pixels.iter().enumerate() {
+            // save_ppm escribe las filas invertidas, aquí se deshace
+            let x = i as u32 % width;
+            let y = height - 1 - i as u32 / width;
+            framebuffer.set_pixel_color(x, y, color);
+        }
+
+        Ok(framebuffer)
+    }
+
     // Método auxiliar para dibujar un punto (usado en rasterización)
     pub fn point(&mut self, x: i32, y: i32, color: Color) {
         if x >= 0 && x < self.width as i32 && y >= 0 && y < self.height as i32 {
@@ -151,3 +360,165 @@ impl Framebuffer {
         }
     }
 }
+
+/// Lee un PPM binario (P6) de 8 bits: ancho, alto y píxeles fila por fila
+/// en el orden del archivo (la primera fila es la de arriba)
+pub(crate) fn read_ppm(path: &str) -> std::io::Result<(u32, u32, Vec<Color>)> {
+    use std::io::{Error, ErrorKind};
+
+    let data = std::fs::read(path)?;
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, format!("{}: {}", path, message));
+
+    // Cabecera: "P6", ancho, alto y valor máximo separados por espacios
+    let mut fields = Vec::new();
+    let mut cursor = 0;
+    while fields.len() < 4 {
+        while cursor < data.len() && data[cursor].is_ascii_whitespace() {
+            cursor += 1;
+        }
+        if cursor < data.len() && data[cursor] == b'#' {
+            while cursor < data.len() && data[cursor] != b'\n' {
+                cursor += 1;
+            }
+            continue;
+        }
+        let start = cursor;
+        while cursor < data.len() && !data[cursor].is_ascii_whitespace() {
+            cursor += 1;
+        }
+        if start == cursor {
+            return Err(invalid("truncated header"));
+        }
+        fields.push(String::from_utf8_lossy(&data[start..cursor]).to_string());
+    }
+    cursor += 1; // Un único espacio antes de los datos binarios
+
+    if fields[0] != "P6" || fields[3] != "255" {
+        return Err(invalid("only 8-bit P6 images are supported"));
+    }
+    let width: u32 = fields[1].parse().map_err(|_| invalid("invalid width"))?;
+    let height: u32 = fields[2].parse().map_err(|_| invalid("invalid height"))?;
+
+    let expected = (width * height * 3) as usize;
+    if data.len() < cursor + expected {
+        return Err(invalid("truncated pixel data"));
+    }
+
+    let pixels = data[cursor..cursor + expected]
+        .chunks(3)
+        .map(|rgb| Color::new(rgb[0], rgb[1], rgb[2], 255))
+        .collect();
+
+    Ok((width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_test_keeps_closest_fragment() {
+        let mut fb = Framebuffer::new(4, 4);
+        fb.clear();
+
+        assert!(fb.set_pixel_with_depth(1, 1, 0.5, Color::RED));
+        // Más lejos: debe ser rechazado
+        assert!(!fb.set_pixel_with_depth(1, 1, 0.8, Color::BLUE));
+        // Más cerca: debe sobrescribir
+        assert!(fb.set_pixel_with_depth(1, 1, 0.2, Color::GREEN));
+
+        assert_eq!(fb.get_pixel(1, 1), Color::GREEN);
+        assert_eq!(fb.get_depth(1, 1), 0.2);
+    }
+
+    #[test]
+    fn test_depth_write_disabled_keeps_depth() {
+        let mut fb = Framebuffer::new(2, 2);
+        fb.clear();
+        fb.depth_write_enabled = false;
+
+        assert!(fb.set_pixel_with_depth(0, 0, 0.5, Color::RED));
+        assert_eq!(fb.get_pixel(0, 0), Color::RED);
+        assert_eq!(fb.get_depth(0, 0), f32::INFINITY);
+    }
+
+    #[test]
+    fn test_blend_respects_alpha_and_depth() {
+        let mut fb = Framebuffer::new(2, 1);
+        fb.clear();
+        fb.set_pixel_with_depth(0, 0, 0.2, Color::new(0, 0, 200, 255));
+        fb.set_pixel_with_depth(1, 0, 0.8, Color::new(0, 0, 200, 255));
+        fb.depth_write_enabled = false;
+
+        // Detrás del píxel 0 se descarta; delante del píxel 1 se mezcla al 50%
+        assert!(!fb.blend_pixel_with_depth(0, 0, 0.5, Color::new(200, 0, 0, 128)));
+        assert!(fb.blend_pixel_with_depth(1, 0, 0.5, Color::new(200, 0, 0, 128)));
+
+        assert_eq!(fb.get_pixel(0, 0), Color::new(0, 0, 200, 255));
+        let blended = fb.get_pixel(1, 0);
+        assert!((blended.r as i32 - 100).abs() <= 1 && (blended.b as i32 - 100).abs() <= 1);
+        assert_eq!(fb.get_depth(1, 0), 0.8);
+    }
+
+    #[test]
+    fn test_ppm_round_trip() {
+        let mut fb = Framebuffer::new(3, 2);
+        fb.set_pixel_color(0, 0, Color::new(10, 20, 30, 255));
+        fb.set_pixel_color(2, 1, Color::new(200, 100, 50, 255));
+
+        let path = std::env::temp_dir().join("framebuffer_round_trip.ppm");
+        let path = path.to_str().unwrap();
+        fb.save_ppm(path).unwrap();
+        let loaded = Framebuffer::load_ppm(path).unwrap();
+        let _ = std::fs::remove_file(path);
+
+        assert_eq!(loaded.width, 3);
+        assert_eq!(loaded.height, 2);
+        assert_eq!(loaded.pixels, fb.pixels);
+    }
+
+    #[test]
+    fn test_save_png_reports_failure_over_an_existing_path() {
+        let fb = Framebuffer::new(3, 2);
+
+        // Algo ya existe con ese nombre pero no se puede escribir encima
+        let path = std::env::temp_dir().join("framebuffer_save_png_dir.png");
+        std::fs::create_dir_all(&path).unwrap();
+        assert!(fb.save_png(path.to_str().unwrap()).is_err());
+
+        let path = std::env::temp_dir().join("framebuffer_save_png.png");
+        assert!(fb.save_png(path.to_str().unwrap()).is_ok());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_region_round_trip() {
+        let mut fb = Framebuffer::new(5, 4);
+        fb.clear();
+        fb.set_pixel_with_depth(2, 1, 0.5, Color::RED);
+
+        let mut region = fb.region(1, 1, 3, 2);
+        assert_eq!(region.get_pixel(1, 0), Color::RED);
+        assert_eq!(region.get_depth(1, 0), 0.5);
+        region.set_pixel_with_depth(2, 1, 0.25, Color::GREEN);
+
+        fb.write_region(1, 1, &region);
+        assert_eq!(fb.get_pixel(3, 2), Color::GREEN);
+        assert_eq!(fb.get_depth(3, 2), 0.25);
+        assert_eq!(fb.get_pixel(2, 1), Color::RED);
+        assert_eq!(fb.get_pixel(0, 0), Color::BLACK);
+    }
+
+    #[test]
+    fn test_depth_compare_greater() {
+        let mut fb = Framebuffer::new(2, 2);
+        fb.clear_depth_value = f32::NEG_INFINITY;
+        fb.set_depth_compare(DepthCompare::Greater);
+        fb.clear();
+
+        assert!(fb.set_pixel_with_depth(0, 0, 0.1, Color::RED));
+        assert!(!fb.set_pixel_with_depth(0, 0, 0.0, Color::BLUE));
+        assert!(fb.set_pixel_with_depth(0, 0, 0.3, Color::GREEN));
+        assert_eq!(fb.get_pixel(0, 0), Color::GREEN);
+    }
+}
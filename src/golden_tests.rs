@@ -0,0 +1,231 @@
+//! Golden-image tests - Pruebas de regresión del pipeline de software
+//!
+//! Cada escena fija (cámara, tiempo de simulación y estrellas con semilla)
+//! se renderiza a un `Framebuffer` y se compara contra una imagen de
+//! referencia en `tests/golden/<nombre>.ppm`.
+//!
+//! - Si un píxel difiere más de `channel_tolerance` en algún canal cuenta como distinto.
+//! - La prueba falla si los píxeles distintos superan `max_mismatch_ratio`.
+//! - Al fallar se escriben `<nombre>.actual.ppm` y `<nombre>.diff.ppm` en `target/golden/`.
+//!
+//! Para regenerar las referencias después de un cambio visual intencional:
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test golden
+//! ```
+
+use raylib::prelude::*;
+
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::renderer::Renderer;
+use crate::solar_system::SolarSystem;
+
+const GOLDEN_WIDTH: u32 = 240;
+const GOLDEN_HEIGHT: u32 = 160;
+const STAR_SEED: u64 = 2024;
+const STAR_COUNT: usize = 1500;
+
+const REFERENCE_DIR: &str = "tests/golden";
+const OUTPUT_DIR: &str = "target/golden";
+
+/// Escena determinista para comparar contra una referencia
+struct GoldenScene {
+    name: &'static str,
+    eye: Vector3,
+    target: Vector3,
+    time: f32,
+    show_orbits: bool,
+    /// Diferencia máxima permitida por canal
+    channel_tolerance: u8,
+    /// Fracción máxima de píxeles distintos [0, 1]
+    max_mismatch_ratio: f32,
+}
+
+impl GoldenScene {
+    fn render(&self) -> Framebuffer {
+        self.render_with_threads(1)
+    }
+
+    fn render_with_threads(&self, threads: usize) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(GOLDEN_WIDTH, GOLDEN_HEIGHT);
+
+        let mut camera = Camera::new(self.eye, self.target, Vector3::new(0.0, 1.0, 0.0));
+        camera.update(0.0);
+
+        let mut solar_system = SolarSystem::new();
+        solar_system.update(self.time);
+
+        let mut renderer = Renderer::new(GOLDEN_WIDTH as f32, GOLDEN_HEIGHT as f32).expect("assets/sphere.obj must load");
+        renderer.generate_stars_seeded(STAR_COUNT, STAR_SEED);
+        renderer.threads = threads;
+
+        framebuffer.clear();
+        renderer.render_solar_system_to_framebuffer(&mut framebuffer, &solar_system, &camera, self.show_orbits);
+        framebuffer
+    }
+}
+
+/// Resultado de comparar dos imágenes del mismo tamaño
+struct ImageComparison {
+    mismatched_pixels: usize,
+    max_channel_difference: u8,
+    diff: Framebuffer,
+}
+
+fn channel_difference(a: Color, b: Color) -> u8 {
+    a.r.abs_diff(b.r).max(a.g.abs_diff(b.g)).max(a.b.abs_diff(b.b))
+}
+
+fn compare_images(actual: &Framebuffer, expected: &Framebuffer, channel_tolerance: u8) -> ImageComparison {
+    let mut diff = Framebuffer::new(actual.width, actual.height);
+    let mut mismatched_pixels = 0;
+    let mut max_channel_difference = 0;
+
+    for y in 0..actual.height {
+        for x in 0..actual.width {
+            let difference = channel_difference(actual.get_pixel(x, y), expected.get_pixel(x, y));
+            max_channel_difference = max_channel_difference.max(difference);
+
+            // Diff: rojo donde falla, gris tenue de la imagen esperada donde coincide
+            let diff_color = if difference > channel_tolerance {
+                mismatched_pixels += 1;
+                Color::new(255, 0, 0, 255)
+            } else {
+                let expected_pixel = expected.get_pixel(x, y);
+                let gray = ((expected_pixel.r as u32 + expected_pixel.g as u32 + expected_pixel.b as u32) / 12) as u8;
+                Color::new(gray, gray, gray, 255)
+            };
+            diff.set_pixel_color(x, y, diff_color);
+        }
+    }
+
+    ImageComparison {
+        mismatched_pixels,
+        max_channel_difference,
+        diff,
+    }
+}
+
+fn assert_matches_golden(scene: &GoldenScene) {
+    let actual = scene.render();
+    let reference_path = format!("{}/{}.ppm", REFERENCE_DIR, scene.name);
+
+    if std::env::var("UPDATE_GOLDEN").is_ok() {
+        std::fs::create_dir_all(REFERENCE_DIR).unwrap();
+        actual.save_ppm(&reference_path).unwrap();
+        return;
+    }
+
+    let expected = Framebuffer::load_ppm(&reference_path).unwrap_or_else(|e| {
+        panic!("Missing golden image {} ({}). Run with UPDATE_GOLDEN=1 to create it.", reference_path, e)
+    });
+    assert_eq!(
+        (actual.width, actual.height),
+        (expected.width, expected.height),
+        "Golden image {} has a different size",
+        reference_path
+    );
+
+    let comparison = compare_images(&actual, &expected, scene.channel_tolerance);
+    let mismatch_ratio = comparison.mismatched_pixels as f32 / actual.pixels.len() as f32;
+
+    if mismatch_ratio > scene.max_mismatch_ratio {
+        std::fs::create_dir_all(OUTPUT_DIR).unwrap();
+        let actual_path = format!("{}/{}.actual.ppm", OUTPUT_DIR, scene.name);
+        let diff_path = format!("{}/{}.diff.ppm", OUTPUT_DIR, scene.name);
+        actual.save_ppm(&actual_path).unwrap();
+        comparison.diff.save_ppm(&diff_path).unwrap();
+
+        panic!(
+            "Golden image mismatch for '{}': {} pixels differ ({:.3}%, max channel difference {}). \
+             Wrote {} and {}",
+            scene.name,
+            comparison.mismatched_pixels,
+            mismatch_ratio * 100.0,
+            comparison.max_channel_difference,
+            actual_path,
+            diff_path,
+        );
+    }
+}
+
+fn system_overview() -> GoldenScene {
+    GoldenScene {
+        name: "system_overview",
+        eye: Vector3::new(0.0, 240.0, 180.0),
+        target: Vector3::new(0.0, 0.0, 0.0),
+        time: 0.0,
+        show_orbits: true,
+        channel_tolerance: 2,
+        max_mismatch_ratio: 0.002,
+    }
+}
+
+fn inner_system_after_time_step() -> GoldenScene {
+    GoldenScene {
+        name: "inner_system_t5",
+        eye: Vector3::new(0.0, 50.0, 100.0),
+        target: Vector3::new(0.0, 0.0, 0.0),
+        time: 5.0,
+        show_orbits: true,
+        channel_tolerance: 2,
+        max_mismatch_ratio: 0.002,
+    }
+}
+
+fn gas_giant_close_up() -> GoldenScene {
+    // Gigantus con anillos y lunas, sin órbitas para aislar los cuerpos
+    let gigantus = {
+        let mut solar_system = SolarSystem::new();
+        solar_system.update(1.0);
+        solar_system.planets()[2].position
+    };
+
+    GoldenScene {
+        name: "gas_giant_close_up",
+        eye: gigantus + Vector3::new(30.0, 15.0, 40.0),
+        target: gigantus,
+        time: 1.0,
+        show_orbits: false,
+        channel_tolerance: 2,
+        max_mismatch_ratio: 0.002,
+    }
+}
+
+#[test]
+fn golden_system_overview() {
+    assert_matches_golden(&system_overview());
+}
+
+#[test]
+fn golden_inner_system_after_time_step() {
+    assert_matches_golden(&inner_system_after_time_step());
+}
+
+#[test]
+fn golden_gas_giant_close_up() {
+    assert_matches_golden(&gas_giant_close_up());
+}
+
+#[test]
+fn golden_tiled_rendering_matches_single_threaded() {
+    // Las referencias se generan con un hilo; por tiles debe salir idéntico
+    for scene in [system_overview(), inner_system_after_time_step(), gas_giant_close_up()] {
+        let single = scene.render_with_threads(1);
+        let tiled = scene.render_with_threads(4);
+        assert!(tiled.pixels == single.pixels, "'{}' differs when rendered by tiles", scene.name);
+    }
+}
+
+#[test]
+fn test_compare_images_detects_differences() {
+    let mut a = Framebuffer::new(4, 4);
+    let b = Framebuffer::new(4, 4);
+    a.set_pixel_color(1, 2, Color::WHITE);
+    a.set_pixel_color(3, 3, Color::new(1, 1, 1, 255));
+
+    let comparison = compare_images(&a, &b, 2);
+    assert_eq!(comparison.mismatched_pixels, 1);
+    assert_eq!(comparison.max_channel_difference, 255);
+    assert_eq!(comparison.diff.get_pixel(1, 2), Color::new(255, 0, 0, 255));
+}
@@ -0,0 +1,291 @@
+//! Headless rendering - Renderiza el sistema solar sin ventana ni GPU
+//!
+//! Construye `SolarSystem`, `Camera` y `Renderer`, ejecuta el pipeline de
+//! software sobre un `Framebuffer` y guarda cada cuadro como imagen.
+//! Útil para CI o servidores sin pantalla.
+//!
+//! Uso:
+//! ```text
+//! cargo run --release -- --headless --frames 60 --start 0 --end 10 --output frames --format png
+//! ```
+
+use raylib::prelude::*;
+
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::physics::SimulationMode;
+use crate::mesh::Mesh;
+use crate::renderer::{Renderer, PROCEDURAL_SPHERE_RADIUS};
+use crate::solar_system::SolarSystem;
+
+/// Formato de las imágenes de salida
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Ppm,
+}
+
+impl ImageFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+}
+
+/// Malla con la que se dibujan los cuerpos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SphereMesh {
+    /// assets/sphere.obj (o una icosfera si no se puede cargar)
+    Obj,
+    UvSphere,
+    Icosphere,
+}
+
+/// Configuración del render sin ventana
+#[derive(Debug, Clone)]
+pub struct HeadlessConfig {
+    pub width: u32,
+    pub height: u32,
+    /// Cantidad de cuadros a generar
+    pub frames: u32,
+    /// Tiempo de simulación del primer cuadro
+    pub start_time: f32,
+    /// Tiempo de simulación del último cuadro
+    pub end_time: f32,
+    pub output_dir: String,
+    pub format: ImageFormat,
+    pub show_orbits: bool,
+    /// Archivo TOML del sistema; `None` usa el sistema por defecto
+    pub system_path: Option<String>,
+    /// Integrar la gravedad entre cuerpos en lugar de seguir las órbitas
+    pub nbody: bool,
+    pub sphere: SphereMesh,
+    /// Hilos para rasterizar; `None` usa todos los núcleos
+    pub threads: Option<usize>,
+    /// Medir el rasterizer en lugar de guardar imágenes (ver `benchmark`)
+    pub benchmark: bool,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        HeadlessConfig {
+            width: 1200,
+            height: 800,
+            frames: 1,
+            start_time: 0.0,
+            end_time: 0.0,
+            output_dir: "frames".to_string(),
+            format: ImageFormat::Png,
+            show_orbits: true,
+            system_path: None,
+            nbody: false,
+            sphere: SphereMesh::Obj,
+            threads: None,
+            benchmark: false,
+        }
+    }
+}
+
+pub const USAGE: &str = "\
+Uso: main --headless [opciones]
+
+  --frames N        Cantidad de cuadros (default 1)
+  --start T         Tiempo de simulación inicial (default 0)
+  --end T           Tiempo de simulación final (default = start)
+  --width W         Ancho en píxeles (default 1200)
+  --height H        Alto en píxeles (default 800)
+  --output DIR      Carpeta de salida (default frames)
+  --format png|ppm  Formato de imagen (default png)
+  --no-orbits       No dibujar las órbitas
+  --system FILE     Archivo TOML del sistema (default assets/systems/default.toml)
+  --nbody           Simular la gravedad entre cuerpos (N-body)
+  --sphere obj|uv|icosphere
+                    Malla de los cuerpos (default obj)
+  --threads N       Hilos para rasterizar (default: todos los núcleos)
+  --benchmark       Medir el rasterizer en el cuadro de --start, repitiéndolo
+                    --frames veces, en lugar de guardar imágenes";
+
+impl HeadlessConfig {
+    /// Lee la configuración desde los argumentos de línea de comandos
+    /// (sin incluir el nombre del programa)
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut config = HeadlessConfig::default();
+        let mut end_time = None;
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--headless" => {}
+                "--no-orbits" => config.show_orbits = false,
+                "--nbody" => config.nbody = true,
+                "--benchmark" => config.benchmark = true,
+                "--frames" => config.frames = parse_value(arg, iter.next())?,
+                "--start" => config.start_time = parse_value(arg, iter.next())?,
+                "--end" => end_time = Some(parse_value(arg, iter.next())?),
+                "--width" => config.width = parse_value(arg, iter.next())?,
+                "--height" => config.height = parse_value(arg, iter.next())?,
+                "--output" => config.output_dir = parse_value(arg, iter.next())?,
+                "--threads" => config.threads = Some(parse_value(arg, iter.next())?),
+                "--system" => config.system_path = Some(parse_value(arg, iter.next())?),
+                "--format" => {
+                    let value: String = parse_value(arg, iter.next())?;
+                    config.format = match value.to_lowercase().as_str() {
+                        "png" => ImageFormat::Png,
+                        "ppm" => ImageFormat::Ppm,
+                        other => return Err(format!("Unknown image format: {}", other)),
+                    };
+                }
+                "--sphere" => {
+                    let value: String = parse_value(arg, iter.next())?;
+                    config.sphere = match value.to_lowercase().as_str() {
+                        "obj" => SphereMesh::Obj,
+                        "uv" => SphereMesh::UvSphere,
+                        "icosphere" => SphereMesh::Icosphere,
+                        other => return Err(format!("Unknown sphere mesh: {}", other)),
+                    };
+                }
+                other => return Err(format!("Unknown argument: {}", other)),
+            }
+        }
+
+        config.end_time = end_time.unwrap_or(config.start_time);
+
+        if config.frames == 0 {
+            return Err("--frames must be at least 1".to_string());
+        }
+        if config.width == 0 || config.height == 0 {
+            return Err("--width and --height must be greater than 0".to_string());
+        }
+        if config.threads == Some(0) {
+            return Err("--threads must be at least 1".to_string());
+        }
+
+        Ok(config)
+    }
+
+    /// Tiempo de simulación del cuadro `frame`
+    pub fn frame_time(&self, frame: u32) -> f32 {
+        if self.frames <= 1 {
+            return self.start_time;
+        }
+        let t = frame as f32 / (self.frames - 1) as f32;
+        self.start_time + (self.end_time - self.start_time) * t
+    }
+
+    fn frame_path(&self, frame: u32) -> String {
+        format!("{}/frame_{:04}.{}", self.output_dir, frame, self.format.extension())
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("Missing value for {}", flag))?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+/// Renderiza todos los cuadros y los guarda en `output_dir`
+pub fn run(config: &HeadlessConfig) -> Result<(), String> {
+    std::fs::create_dir_all(&config.output_dir)
+        .map_err(|e| format!("Error creating {}: {}", config.output_dir, e))?;
+
+    let mut framebuffer = Framebuffer::new(config.width, config.height);
+    let (mut solar_system, camera, mut renderer) = build_scene(config)?;
+
+    for frame in 0..config.frames {
+        // El primer cuadro avanza la simulación hasta start_time
+        let frame_time = config.frame_time(frame);
+        solar_system.advance_to(frame_time as f64);
+
+        framebuffer.clear();
+        renderer.render_solar_system_to_framebuffer(&mut framebuffer, &solar_system, &camera, config.show_orbits);
+
+        let path = config.frame_path(frame);
+        match config.format {
+            ImageFormat::Png => framebuffer.save_png(&path)?,
+            ImageFormat::Ppm => framebuffer
+                .save_ppm(&path)
+                .map_err(|e| format!("Error writing {}: {}", path, e))?,
+        }
+        match solar_system.energy_drift() {
+            Some(drift) => println!(
+                "[{}/{}] t = {:.3} dE = {:+.6}% -> {}",
+                frame + 1, config.frames, frame_time, drift * 100.0, path
+            ),
+            None => println!("[{}/{}] t = {:.3} -> {}", frame + 1, config.frames, frame_time, path),
+        }
+    }
+
+    Ok(())
+}
+
+/// Sistema, cámara y renderer descritos por la configuración. La cámara es
+/// la misma con la que arranca la versión con ventana.
+pub fn build_scene(config: &HeadlessConfig) -> Result<(SolarSystem, Camera, Renderer), String> {
+    let mut camera = Camera::new(
+        Vector3::new(0.0, 50.0, 100.0),
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    );
+    camera.update(0.0);
+
+    let mut solar_system = match &config.system_path {
+        Some(path) => SolarSystem::from_file(path).map_err(|e| e.to_string())?,
+        None => SolarSystem::new(),
+    };
+    if config.nbody {
+        solar_system.set_simulation_mode(SimulationMode::NBody);
+    }
+    let (width, height) = (config.width as f32, config.height as f32);
+    let mut renderer = match config.sphere {
+        SphereMesh::Obj => Renderer::new(width, height).unwrap_or_else(|e| {
+            eprintln!("Error loading sphere model: {}; using a procedural sphere", e);
+            Renderer::with_procedural_sphere(width, height)
+        }),
+        SphereMesh::UvSphere => Renderer::with_sphere_mesh(width, height, Mesh::uv_sphere(PROCEDURAL_SPHERE_RADIUS, 32, 16)),
+        SphereMesh::Icosphere => Renderer::with_procedural_sphere(width, height),
+    };
+    if let Some(threads) = config.threads {
+        renderer.threads = threads;
+    }
+
+    Ok((solar_system, camera, renderer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_headless_args() {
+        let config = HeadlessConfig::from_args(&args(&[
+            "--headless", "--frames", "5", "--start", "1", "--end", "3", "--format", "ppm",
+            "--system", "custom.toml", "--sphere", "icosphere", "--threads", "3", "--benchmark",
+        ]))
+        .unwrap();
+
+        assert_eq!(config.system_path.as_deref(), Some("custom.toml"));
+        assert_eq!(config.frames, 5);
+        assert_eq!(config.format, ImageFormat::Ppm);
+        assert_eq!(config.sphere, SphereMesh::Icosphere);
+        assert_eq!(config.threads, Some(3));
+        assert!(config.benchmark);
+        assert_eq!(config.frame_time(0), 1.0);
+        assert_eq!(config.frame_time(2), 2.0);
+        assert_eq!(config.frame_time(4), 3.0);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_argument() {
+        assert!(HeadlessConfig::from_args(&args(&["--bogus"])).is_err());
+        assert!(HeadlessConfig::from_args(&args(&["--frames", "0"])).is_err());
+        assert!(HeadlessConfig::from_args(&args(&["--sphere", "cube"])).is_err());
+        assert!(HeadlessConfig::from_args(&args(&["--threads", "0"])).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+//! Nivel de detalle (LOD) - Qué malla usar para cada cuerpo
+//!
+//! Cada cuerpo se dibuja con una de varias mallas pre-construidas, de la más
+//! detallada a la más simple, según su radio proyectado en pantalla (en
+//! píxeles) visto desde la cámara. Los cuerpos más chicos que el último nivel
+//! se dibujan como un punto (impostor).
+//!
+//! Para que un cuerpo que está justo en el límite entre dos niveles no
+//! alterne de malla en cada cuadro, el cambio tiene histéresis: para pasar a
+//! un nivel más detallado el radio debe superar el umbral por un margen, y
+//! para volver a uno más simple debe quedar por debajo por el mismo margen.
+
+use crate::mesh::Mesh;
+
+/// Una malla y el radio en pantalla desde el que se usa
+#[derive(Clone, Debug)]
+pub struct LodLevel {
+    pub mesh: Mesh,
+    /// Radio proyectado mínimo, en píxeles
+    pub min_screen_radius: f32,
+}
+
+/// Cómo se dibuja un cuerpo en este cuadro
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LodChoice {
+    /// Índice en `LodChain::levels`
+    Mesh(usize),
+    /// Punto del color del cuerpo
+    Impostor,
+}
+
+/// Niveles de detalle de una malla, del más detallado al más simple
+#[derive(Clone, Debug)]
+pub struct LodChain {
+    pub levels: Vec<LodLevel>,
+    /// Margen relativo alrededor de cada umbral (0.15 = ±15 %)
+    pub hysteresis: f32,
+}
+
+impl LodChain {
+    /// Cadena para las esferas de los cuerpos: una icosfera más fina para
+    /// los cuerpos que llenan la pantalla, `base` para tamaños medianos e
+    /// icosferas cada vez más simples para los lejanos. Todas tienen el
+    /// radio de `base`, así un cambio de nivel no cambia el tamaño. Si toda
+    /// `base` usa un material del MTL, las icosferas también lo usan.
+    pub fn for_sphere(base: Mesh) -> Self {
+        let radius = base.bounding_radius();
+        let part = base.uniform_part().cloned();
+        let icosphere = |subdivisions| Mesh::icosphere(radius, subdivisions).with_uniform_part(part.clone());
+        LodChain {
+            levels: vec![
+                LodLevel { mesh: icosphere(4), min_screen_radius: 160.0 },
+                LodLevel { mesh: base, min_screen_radius: 24.0 },
+                LodLevel { mesh: icosphere(2), min_screen_radius: 8.0 },
+                LodLevel { mesh: icosphere(1), min_screen_radius: 2.0 },
+            ],
+            hysteresis: 0.15,
+        }
+    }
+
+    /// Malla de un nivel
+    pub fn mesh(&self, level: usize) -> &Mesh {
+        &self.levels[level].mesh
+    }
+
+    /// Radio de las mallas (el mismo en todos los niveles)
+    pub fn bounding_radius(&self) -> f32 {
+        self.levels.first().map_or(1.0, |level| level.mesh.bounding_radius())
+    }
+
+    /// Elige el nivel para un radio en pantalla. `previous` es lo que se
+    /// eligió en el cuadro anterior (o `None` la primera vez).
+    pub fn select(&self, screen_radius: f32, previous: Option<LodChoice>) -> LodChoice {
+        // Posición del cuadro anterior en la lista (el impostor va al final)
+        let previous = previous.map(|choice| match choice {
+            LodChoice::Mesh(level) => level,
+            LodChoice::Impostor => self.levels.len(),
+        });
+
+        for (level, lod) in self.levels.iter().enumerate() {
+            let threshold = match previous {
+                // Ya estaba en este nivel o en uno más detallado: cuesta bajar
+                Some(previous) if previous <= level => lod.min_screen_radius * (1.0 - self.hysteresis),
+                // Estaba en uno más simple: cuesta subir
+                Some(_) => lod.min_screen_radius * (1.0 + self.hysteresis),
+                None => lod.min_screen_radius,
+            };
+            if screen_radius >= threshold {
+                return LodChoice::Mesh(level);
+            }
+        }
+        LodChoice::Impostor
+    }
+}
+
+/// Radio en píxeles de una esfera de radio `radius` a `distance` de la
+/// cámara. `focal_length` es la distancia al plano de la imagen en píxeles:
+/// (alto de pantalla / 2) / tan(fov vertical / 2).
+pub fn screen_radius(radius: f32, distance: f32, focal_length: f32) -> f32 {
+    if distance <= radius {
+        return f32::INFINITY; // La cámara está dentro de la esfera
+    }
+    // El borde visible de la esfera está a un ángulo asin(r / d) del centro
+    let angle = (radius / distance).asin();
+    angle.tan() * focal_length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain() -> LodChain {
+        LodChain::for_sphere(Mesh::icosphere(0.5, 3))
+    }
+
+    #[test]
+    fn test_levels_get_simpler_and_keep_the_radius() {
+        let chain = chain();
+        for pair in chain.levels.windows(2) {
+            assert!(pair[0].mesh.triangles.len() > pair[1].mesh.triangles.len());
+            assert!(pair[0].min_screen_radius > pair[1].min_screen_radius);
+            assert!((pair[0].mesh.bounding_radius() - pair[1].mesh.bounding_radius()).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_select_by_screen_radius() {
+        let chain = chain();
+        assert_eq!(chain.select(500.0, None), LodChoice::Mesh(0));
+        assert_eq!(chain.select(30.0, None), LodChoice::Mesh(1));
+        assert_eq!(chain.select(10.0, None), LodChoice::Mesh(2));
+        assert_eq!(chain.select(2.0, None), LodChoice::Mesh(3));
+        assert_eq!(chain.select(1.0, None), LodChoice::Impostor);
+    }
+
+    #[test]
+    fn test_hysteresis_avoids_popping() {
+        let chain = chain();
+        // Justo debajo del umbral de 24 px: sigue en el nivel 1 si ya estaba ahí
+        assert_eq!(chain.select(23.0, Some(LodChoice::Mesh(1))), LodChoice::Mesh(1));
+        assert_eq!(chain.select(23.0, Some(LodChoice::Mesh(2))), LodChoice::Mesh(2));
+        // Justo encima: no sube desde el nivel 2 hasta pasar el margen
+        assert_eq!(chain.select(25.0, Some(LodChoice::Mesh(2))), LodChoice::Mesh(2));
+        assert_eq!(chain.select(28.0, Some(LodChoice::Mesh(2))), LodChoice::Mesh(1));
+        // Lejos de los umbrales la histéresis no importa
+        assert_eq!(chain.select(10.0, Some(LodChoice::Mesh(0))), LodChoice::Mesh(2));
+        assert_eq!(chain.select(1.8, Some(LodChoice::Mesh(3))), LodChoice::Mesh(3));
+        assert_eq!(chain.select(2.2, Some(LodChoice::Impostor)), LodChoice::Impostor);
+    }
+
+    #[test]
+    fn test_screen_radius() {
+        // 45° de fov en 800 px de alto: focal = 400 / tan(22.5°)
+        let focal = 400.0 / (std::f32::consts::PI / 8.0).tan();
+        let near = screen_radius(1.0, 10.0, focal);
+        let far = screen_radius(1.0, 20.0, focal);
+        // Al doble de distancia se ve (casi) la mitad de grande
+        assert!((near / far - 2.0).abs() < 0.01, "{} vs {}", near, far);
+        assert!((near - focal / 10.0).abs() < 1.0);
+        assert_eq!(screen_radius(1.0, 0.5, focal), f32::INFINITY);
+    }
+}
@@ -1,6 +1,8 @@
 mod matrix;
 mod camera;
 mod celestial_body;
+mod orbit;
+mod physics;
 mod renderer;
 mod solar_system;
 mod framebuffer;
@@ -9,19 +11,74 @@ mod vertex_shader;
 mod primitive_assembly;
 mod rasterizer;
 mod fragment_shader;
+mod headless;
+mod scene_loader;
+mod texture;
+mod asset_error;
+mod mesh;
+mod lod;
+mod clipping;
+mod varyings;
+mod tiles;
+mod benchmark;
+mod clock;
+mod scene_graph;
+mod picking;
+
+#[cfg(test)]
+mod golden_tests;
 
 use raylib::prelude::*;
 
 use crate::camera::Camera;
+use crate::clock::SimulationClock;
 use crate::celestial_body::CelestialBody;
 use crate::solar_system::SolarSystem;
 use crate::renderer::Renderer;
 use crate::framebuffer::Framebuffer;
+use crate::physics::SimulationMode;
+use crate::picking::{PickState, Ray};
 
 const SCREEN_WIDTH: i32 = 1200;
 const SCREEN_HEIGHT: i32 = 800;
 
 fn main() {
+    // Modo sin ventana: renderiza cuadros a archivos y termina
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--headless") {
+        let result = headless::HeadlessConfig::from_args(&args).and_then(|config| {
+            if config.benchmark {
+                benchmark::run(&config)
+            } else {
+                headless::run(&config)
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("{}\n\n{}", e, headless::USAGE);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Sistema a cargar: --system <archivo.toml> o el sistema por defecto
+    let solar_system = match args.iter().position(|arg| arg == "--system") {
+        Some(i) => match args.get(i + 1) {
+            Some(path) => SolarSystem::from_file(path),
+            None => {
+                eprintln!("Missing value for --system");
+                std::process::exit(1);
+            }
+        },
+        None => Ok(SolarSystem::new()),
+    };
+    let mut solar_system = match solar_system {
+        Ok(system) => system,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
     let (mut rl, thread) = raylib::init()
         .size(SCREEN_WIDTH, SCREEN_HEIGHT)
         .title("Solar System Simulator")
@@ -39,37 +96,57 @@ fn main() {
         Vector3::new(0.0, 1.0, 0.0),     // up vector
     );
 
-    // Initialize solar system
-    let mut solar_system = SolarSystem::new();
-    
     // Initialize renderer
-    let mut renderer = Renderer::new(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32);
+    // Sin sphere.obj la escena se dibuja con esferas generadas por código
+    let mut renderer = Renderer::new(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32).unwrap_or_else(|e| {
+        eprintln!("Error loading sphere model: {}; using a procedural sphere", e);
+        Renderer::with_procedural_sphere(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32)
+    });
 
-    let mut time = 0.0f32;
-    let mut show_orbits = true;
-    let mut warp_mode = false;
-    let mut selected_planet = 0;
-    let mut follow_planet: Option<usize> = None;
+    // Radio de la malla de esfera: la superficie que la cámara no atraviesa
+    let mesh_radius = renderer.sphere_lods.bounding_radius();
+
+    let mut clock = SimulationClock::default();
+    let mut ui = UiState::default();
+    let mut picking = PickState::default();
 
     while !rl.window_should_close() {
         let dt = rl.get_frame_time();
-        time += dt;
 
         // Handle input
-        handle_input(&mut rl, &mut camera, &mut show_orbits, 
-                    &mut warp_mode, &mut selected_planet, &mut follow_planet, &solar_system, dt);
-
-        // Update solar system
-        solar_system.update(dt);
-
-        if let Some(i) = follow_planet {
-            if solar_system.planets.len() > i {
-                camera.set_target(solar_system.planets[i].position);
+        handle_input(&mut rl, &mut camera, &mut clock, &mut ui, &mut solar_system, dt);
+
+        // Update solar system: el reloj convierte el tiempo real en tiempo de
+        // simulación y es el único que lo acumula, así el HUD muestra el
+        // tiempo en que están los cuerpos
+        clock.tick(dt);
+        solar_system.advance_to(clock.time());
+        // En N-body el integrador puede quedarse corto: el reloj lo sigue
+        clock.set_time(solar_system.time());
+
+        if let Some(i) = ui.follow_planet {
+            if let Some(planet) = solar_system.planets().get(i) {
+                camera.set_target(planet.position);
             } else {
-                follow_planet = None;
+                ui.follow_planet = None;
             }
         }
         
+        // Picking: cuerpo bajo el cursor; clic elige, doble clic hace warp
+        let mouse = rl.get_mouse_position();
+        let view_matrix = camera.get_view_matrix();
+        let hit = Ray::from_screen(mouse.x, mouse.y, &view_matrix, &renderer.projection_matrix, &renderer.viewport_matrix)
+            .and_then(|ray| picking::pick(&solar_system, &ray, mesh_radius));
+        // El mismo botón gira la cámara: solo cuenta si se suelta sin arrastrar
+        let clicked = picking.click(
+            mouse,
+            rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT),
+            rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT),
+        );
+        if let Some(target) = picking.update(hit, clicked, rl.get_time()) {
+            camera.warp_to_planet(solar_system.graph.body(target));
+        }
+
         // Update camera
         camera.update(dt);
 
@@ -78,18 +155,16 @@ fn main() {
             .into_iter()
             .cloned()
             .collect();
-        camera.avoid_collision(&all_bodies);
+        camera.avoid_collision(&all_bodies, mesh_radius);
         
-        // OPTIMIZACIÓN: Aplicar límites de distancia mínima
-        // Evita que la cámara se acerque tanto que genere millones de fragmentos
-        // Esto previene caídas de FPS cuando hay planetas grandes muy cerca
-        camera.enforce_minimum_distance(&all_bodies);
+        // Mantener la cámara sobre la superficie del cuerpo más cercano
+        camera.enforce_minimum_distance(&all_bodies, mesh_radius);
 
         // Clear framebuffer
         framebuffer.clear();
 
         // Render to framebuffer
-        renderer.render_solar_system_to_framebuffer(&mut framebuffer, &solar_system, &camera, show_orbits);
+        renderer.render_solar_system_to_framebuffer(&mut framebuffer, &solar_system, &camera, ui.show_orbits);
 
         // Swap buffers (convert framebuffer to texture)
         framebuffer.swap_buffers(&mut rl, &thread);
@@ -102,18 +177,40 @@ fn main() {
         framebuffer.draw_to_screen(&mut d);
 
         // Render UI on top
-        render_ui(&mut d, &camera, show_orbits, warp_mode, selected_planet, &all_bodies);
+        render_ui(&mut d, &camera, &clock, &ui, &all_bodies, mesh_radius, &solar_system);
+        render_picking(&mut d, &picking, &solar_system, &renderer, &camera, mesh_radius);
+    }
+}
+
+/// Estado de la interfaz que cambia con el teclado
+struct UiState {
+    show_orbits: bool,
+    warp_mode: bool,
+    selected_planet: usize,
+    /// Próxima estrella a la que lleva la tecla 0
+    selected_star: usize,
+    /// Planeta que la cámara sigue, por índice en `SolarSystem::planets`
+    follow_planet: Option<usize>,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        UiState {
+            show_orbits: true,
+            warp_mode: false,
+            selected_planet: 0,
+            selected_star: 0,
+            follow_planet: None,
+        }
     }
 }
 
 fn handle_input(
     rl: &mut RaylibHandle,
     camera: &mut Camera,
-    show_orbits: &mut bool,
-    warp_mode: &mut bool,
-    selected_planet: &mut usize,
-    follow_planet: &mut Option<usize>,
-    solar_system: &SolarSystem,
+    clock: &mut SimulationClock,
+    ui: &mut UiState,
+    solar_system: &mut SolarSystem,
     dt: f32,
 ) {
     // Camera controls
@@ -129,80 +226,108 @@ fn handle_input(
 
     // Keyboard controls
     if rl.is_key_pressed(KeyboardKey::KEY_O) {
-        *show_orbits = !*show_orbits;
+        ui.show_orbits = !ui.show_orbits;
     }
 
     if rl.is_key_pressed(KeyboardKey::KEY_W) {
-        *warp_mode = !*warp_mode;
+        ui.warp_mode = !ui.warp_mode;
+    }
+
+    // Reloj de la simulación
+    if rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+        clock.toggle_pause();
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_R) {
+        clock.toggle_reverse();
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_PERIOD) {
+        clock.step();
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_EQUAL) || rl.is_key_pressed(KeyboardKey::KEY_KP_ADD) {
+        clock.faster();
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_MINUS) || rl.is_key_pressed(KeyboardKey::KEY_KP_SUBTRACT) {
+        clock.slower();
+    }
+    if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+        clock.reset_scale();
     }
 
-    // Warp al sol (tecla 0)
-    if rl.is_key_pressed(KeyboardKey::KEY_ZERO) {
-        if *warp_mode {
-            camera.warp_to_sun();
+    // Alternar entre órbitas keplerianas y gravedad N-body
+    if rl.is_key_pressed(KeyboardKey::KEY_N) {
+        solar_system.toggle_simulation_mode();
+    }
+
+    // Warp a una estrella (tecla 0); con varias, cada pulsación pasa a la siguiente
+    if rl.is_key_pressed(KeyboardKey::KEY_ZERO) && ui.warp_mode {
+        let stars = solar_system.stars();
+        if !stars.is_empty() && !camera.is_warping {
+            ui.selected_star %= stars.len();
+            camera.warp_to_star(stars[ui.selected_star]);
+            ui.selected_star = (ui.selected_star + 1) % stars.len();
         }
     }
 
     // Vista general del sistema (tecla 9)
     if rl.is_key_pressed(KeyboardKey::KEY_NINE) {
-        if *warp_mode {
+        if ui.warp_mode {
             camera.warp_to_system_overview();
         }
     }
 
     // Planet selection for warping
     if rl.is_key_pressed(KeyboardKey::KEY_ONE) {
-        *selected_planet = 0;
-        if *warp_mode && solar_system.planets.len() > *selected_planet {
-            camera.warp_to_planet(&solar_system.planets[*selected_planet]);
+        ui.selected_planet = 0;
+        if ui.warp_mode && solar_system.planets().len() > ui.selected_planet {
+            camera.warp_to_planet(solar_system.planets()[ui.selected_planet]);
         }
     }
-    if rl.is_key_pressed(KeyboardKey::KEY_TWO) && solar_system.planets.len() > 1 {
-        *selected_planet = 1;
-        if *warp_mode {
-            camera.warp_to_planet(&solar_system.planets[*selected_planet]);
+    if rl.is_key_pressed(KeyboardKey::KEY_TWO) && solar_system.planets().len() > 1 {
+        ui.selected_planet = 1;
+        if ui.warp_mode {
+            camera.warp_to_planet(solar_system.planets()[ui.selected_planet]);
         }
     }
-    if rl.is_key_pressed(KeyboardKey::KEY_THREE) && solar_system.planets.len() > 2 {
-        *selected_planet = 2;
-        if *warp_mode {
-            camera.warp_to_planet(&solar_system.planets[*selected_planet]);
+    if rl.is_key_pressed(KeyboardKey::KEY_THREE) && solar_system.planets().len() > 2 {
+        ui.selected_planet = 2;
+        if ui.warp_mode {
+            camera.warp_to_planet(solar_system.planets()[ui.selected_planet]);
         }
     }
-    if rl.is_key_pressed(KeyboardKey::KEY_FOUR) && solar_system.planets.len() > 3 {
-        *selected_planet = 3;
-        if *warp_mode {
-            camera.warp_to_planet(&solar_system.planets[*selected_planet]);
+    if rl.is_key_pressed(KeyboardKey::KEY_FOUR) && solar_system.planets().len() > 3 {
+        ui.selected_planet = 3;
+        if ui.warp_mode {
+            camera.warp_to_planet(solar_system.planets()[ui.selected_planet]);
         }
     }
-    if rl.is_key_pressed(KeyboardKey::KEY_FIVE) && solar_system.planets.len() > 4 {
-        *selected_planet = 4;
-        if *warp_mode {
-            camera.warp_to_planet(&solar_system.planets[*selected_planet]);
+    if rl.is_key_pressed(KeyboardKey::KEY_FIVE) && solar_system.planets().len() > 4 {
+        ui.selected_planet = 4;
+        if ui.warp_mode {
+            camera.warp_to_planet(solar_system.planets()[ui.selected_planet]);
         }
     }
 
     // Warps instantaneos (sin animacion) con Shift
     if rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT) {
-        if rl.is_key_pressed(KeyboardKey::KEY_ONE) && solar_system.planets.len() > 0 {
-            camera.instant_warp_to_planet(&solar_system.planets[0]);
-            *follow_planet = Some(0);
+        if rl.is_key_pressed(KeyboardKey::KEY_ONE) && !solar_system.planets().is_empty() {
+            camera.instant_warp_to_planet(solar_system.planets()[0]);
+            ui.follow_planet = Some(0);
         }
-        if rl.is_key_pressed(KeyboardKey::KEY_TWO) && solar_system.planets.len() > 1 {
-            camera.instant_warp_to_planet(&solar_system.planets[1]);
-            *follow_planet = Some(1);
+        if rl.is_key_pressed(KeyboardKey::KEY_TWO) && solar_system.planets().len() > 1 {
+            camera.instant_warp_to_planet(solar_system.planets()[1]);
+            ui.follow_planet = Some(1);
         }
-        if rl.is_key_pressed(KeyboardKey::KEY_THREE) && solar_system.planets.len() > 2 {
-            camera.instant_warp_to_planet(&solar_system.planets[2]);
-            *follow_planet = Some(2);
+        if rl.is_key_pressed(KeyboardKey::KEY_THREE) && solar_system.planets().len() > 2 {
+            camera.instant_warp_to_planet(solar_system.planets()[2]);
+            ui.follow_planet = Some(2);
         }
-        if rl.is_key_pressed(KeyboardKey::KEY_FOUR) && solar_system.planets.len() > 3 {
-            camera.instant_warp_to_planet(&solar_system.planets[3]);
-            *follow_planet = Some(3);
+        if rl.is_key_pressed(KeyboardKey::KEY_FOUR) && solar_system.planets().len() > 3 {
+            camera.instant_warp_to_planet(solar_system.planets()[3]);
+            ui.follow_planet = Some(3);
         }
-        if rl.is_key_pressed(KeyboardKey::KEY_FIVE) && solar_system.planets.len() > 4 {
-            camera.instant_warp_to_planet(&solar_system.planets[4]);
-            *follow_planet = Some(4);
+        if rl.is_key_pressed(KeyboardKey::KEY_FIVE) && solar_system.planets().len() > 4 {
+            camera.instant_warp_to_planet(solar_system.planets()[4]);
+            ui.follow_planet = Some(4);
         }
     }
 
@@ -235,10 +360,11 @@ fn handle_input(
 fn render_ui(
     d: &mut RaylibDrawHandle,
     camera: &Camera,
-    show_orbits: bool,
-    warp_mode: bool,
-    _selected_planet: usize,
+    clock: &SimulationClock,
+    ui: &UiState,
     all_bodies: &[CelestialBody],
+    mesh_radius: f32,
+    solar_system: &SolarSystem,
 ) {
     let y_offset = 10;
     let mut current_y = y_offset;
@@ -249,8 +375,8 @@ fn render_ui(
     current_y += 35;
 
     // Controls section
-    d.draw_rectangle(8, current_y - 2, 280, 140, Color::new(0, 0, 0, 150));
-    d.draw_rectangle_lines(8, current_y - 2, 280, 140, Color::new(100, 100, 255, 200));
+    d.draw_rectangle(8, current_y - 2, 280, 220, Color::new(0, 0, 0, 150));
+    d.draw_rectangle_lines(8, current_y - 2, 280, 220, Color::new(100, 100, 255, 200));
     
     d.draw_text("CONTROLS:", 15, current_y, 16, Color::new(100, 200, 255, 255));
     current_y += 20;
@@ -259,6 +385,8 @@ fn render_ui(
     current_y += 16;
     d.draw_text("Mouse Wheel: Zoom", 15, current_y, 12, Color::WHITE);
     current_y += 16;
+    d.draw_text("Click: Select   Double-click: Warp", 15, current_y, 12, Color::WHITE);
+    current_y += 16;
     d.draw_text("WASD/QE: Move Camera", 15, current_y, 12, Color::WHITE);
     current_y += 16;
     d.draw_text("O: Toggle Orbits", 15, current_y, 12, Color::WHITE);
@@ -269,28 +397,60 @@ fn render_ui(
     current_y += 16;
     d.draw_text("Shift+1-5: Follow Planet", 15, current_y, 12, Color::WHITE);
     current_y += 16;
-    d.draw_text("0: Warp to Sun", 15, current_y, 12, Color::WHITE);
+    d.draw_text("0: Warp to Star (cycles)", 15, current_y, 12, Color::WHITE);
+    current_y += 16;
+    d.draw_text("N: Toggle N-body Physics", 15, current_y, 12, Color::WHITE);
+    current_y += 16;
+    d.draw_text("Space: Pause   R: Reverse", 15, current_y, 12, Color::WHITE);
+    current_y += 16;
+    d.draw_text(".: Step One Frame", 15, current_y, 12, Color::WHITE);
+    current_y += 16;
+    d.draw_text("+/-: Time Scale   Backspace: 1 day/s", 15, current_y, 12, Color::WHITE);
     current_y += 20;
 
     // Status section
-    d.draw_rectangle(8, current_y - 2, 200, 65, Color::new(0, 0, 0, 150));
-    d.draw_rectangle_lines(8, current_y - 2, 200, 65, Color::new(100, 255, 100, 200));
+    d.draw_rectangle(8, current_y - 2, 330, 119, Color::new(0, 0, 0, 150));
+    d.draw_rectangle_lines(8, current_y - 2, 330, 119, Color::new(100, 255, 100, 200));
     
     d.draw_text("STATUS:", 15, current_y, 16, Color::new(100, 255, 100, 255));
     current_y += 20;
 
-    let orbits_text = if show_orbits { "ON" } else { "OFF" };
-    let orbits_color = if show_orbits { Color::GREEN } else { Color::RED };
+    // Reloj: tiempo desde la época (t en unidades de la escena, como --start) y velocidad
+    let time_text = format!("Time: {} (t = {:.2})", clock.format_time(), clock.time());
+    d.draw_text(&time_text, 15, current_y, 12, Color::WHITE);
+    current_y += 18;
+    let (speed_text, speed_color) = if clock.is_paused() {
+        (format!("Speed: PAUSED ({})", clock.format_scale()), Color::RED)
+    } else if clock.is_reversed() {
+        (format!("Speed: {}", clock.format_scale()), Color::ORANGE)
+    } else {
+        (format!("Speed: {}", clock.format_scale()), Color::GREEN)
+    };
+    d.draw_text(&speed_text, 15, current_y, 14, speed_color);
+    current_y += 18;
+
+    let orbits_text = if ui.show_orbits { "ON" } else { "OFF" };
+    let orbits_color = if ui.show_orbits { Color::GREEN } else { Color::RED };
     d.draw_text(&format!("Orbits: {}", orbits_text), 15, current_y, 14, orbits_color);
     current_y += 18;
 
-    let warp_text = if warp_mode { "ENABLED" } else { "DISABLED" };
-    let warp_color = if warp_mode { Color::YELLOW } else { Color::GRAY };
+    let warp_text = if ui.warp_mode { "ENABLED" } else { "DISABLED" };
+    let warp_color = if ui.warp_mode { Color::YELLOW } else { Color::GRAY };
     d.draw_text(&format!("Warp Mode: {}", warp_text), 15, current_y, 14, warp_color);
+    current_y += 18;
+
+    // Deriva relativa de la energía total: indica qué tan estable es la integración
+    match (solar_system.simulation_mode(), solar_system.energy_drift()) {
+        (SimulationMode::NBody, Some(drift)) => {
+            let text = format!("Physics: N-body (dE {:+.4}%)", drift * 100.0);
+            d.draw_text(&text, 15, current_y, 14, Color::ORANGE);
+        }
+        _ => d.draw_text("Physics: Kepler", 15, current_y, 14, Color::GRAY),
+    }
     current_y += 25;
 
     // Show collision status
-    if let Some(collision_info) = camera.get_collision_status(all_bodies) {
+    if let Some(collision_info) = camera.get_collision_status(all_bodies, mesh_radius) {
         d.draw_rectangle(8, current_y - 2, 280, 25, Color::new(255, 100, 0, 150));
         d.draw_text(&format!("⚠ {}", collision_info), 15, current_y, 14, Color::ORANGE);
         current_y += 30;
@@ -323,12 +483,50 @@ fn render_ui(
     
     d.draw_text("SOLAR SYSTEM:", info_x, info_y, 14, Color::new(255, 200, 100, 255));
     
-    let planet_names = ["Pyrion", "Verdania", "Gigantus", "Glacialis", "Plutonix"];
-    for (i, name) in planet_names.iter().enumerate() {
+    // Solo las teclas 1-5 tienen planeta asignado
+    for (i, planet) in solar_system.planets().iter().take(5).enumerate() {
         let y = info_y + 20 + (i as i32 * 16);
-        d.draw_text(&format!("{}: {}", i + 1, name), info_x, y, 12, Color::WHITE);
+        d.draw_text(&format!("{}: {}", i + 1, planet.name), info_x, y, 12, Color::WHITE);
     }
     
-    d.draw_text("0: Sol (Sun)", info_x, info_y + 20 + (5 * 16), 12, Color::YELLOW);
+    // La tecla 0 recorre las estrellas en este orden
+    let star_names: Vec<&str> = solar_system.stars().iter().map(|star| star.name.as_str()).collect();
+    let star_label = if star_names.len() == 1 { "Sun" } else { "Stars" };
+    d.draw_text(&format!("0: {} ({})", star_names.join(" / "), star_label), info_x, info_y + 20 + (5 * 16), 12, Color::YELLOW);
     d.draw_text("9: System View", info_x, info_y + 20 + (6 * 16), 12, Color::SKYBLUE);
 }
+
+// Resalta el cuerpo bajo el cursor y el cuerpo elegido con un círculo y su nombre
+fn render_picking(
+    d: &mut RaylibDrawHandle,
+    picking: &PickState,
+    solar_system: &SolarSystem,
+    renderer: &Renderer,
+    camera: &Camera,
+    mesh_radius: f32,
+) {
+    let view_matrix = camera.get_view_matrix();
+    let highlights = [
+        (picking.selected, Color::new(100, 255, 100, 230)),
+        (picking.hovered, Color::new(255, 215, 0, 200)),
+    ];
+
+    for (id, color) in highlights {
+        let Some(id) = id else {
+            continue;
+        };
+        let body = solar_system.graph.body(id);
+        let Some((center, radius)) = picking::screen_circle(body, mesh_radius, &view_matrix, &renderer.projection_matrix, &renderer.viewport_matrix) else {
+            continue;
+        };
+        let radius = radius + 6.0;
+        d.draw_circle_lines(center.x as i32, center.y as i32, radius, color);
+
+        // Nombre y de qué cuerpo cuelga (p. ej. "Luna (Moon of Verdania)")
+        let label = match solar_system.graph.parent(id).map(|parent| solar_system.graph.body(parent)) {
+            Some(parent) if !parent.is_barycenter() => format!("{} ({:?} of {})", body.name, body.body_type, parent.name),
+            _ => format!("{} ({:?})", body.name, body.body_type),
+        };
+        d.draw_text(&label, (center.x + radius + 4.0) as i32, (center.y - 6.0) as i32, 12, color);
+    }
+}
@@ -57,6 +57,45 @@ impl Matrix {
             Vector3::new(x, y, z)
         }
     }
+
+    /// Inversa por eliminación de Gauss-Jordan con pivoteo parcial, en f64
+    /// para no perder precisión con proyecciones de rango de profundidad
+    /// grande. `None` si la matriz es singular.
+    pub fn inverse(&self) -> Option<Matrix> {
+        let mut a = self.m.map(|row| row.map(|value| value as f64));
+        let mut inverse = [[0.0f64; 4]; 4];
+        for (i, row) in inverse.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+
+        for column in 0..4 {
+            // Fila con el pivote más grande en esta columna
+            let pivot = (column..4).max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))?;
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = 1.0 / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= scale;
+                inverse[column][j] *= scale;
+            }
+            for row in 0..4 {
+                if row == column {
+                    continue;
+                }
+                let factor = a[row][column];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[column][j];
+                    inverse[row][j] -= factor * inverse[column][j];
+                }
+            }
+        }
+
+        Some(Matrix { m: inverse.map(|row| row.map(|value| value as f32)) })
+    }
 }
 
 /// Creates a 4x4 matrix with the given values
@@ -210,4 +249,182 @@ pub fn create_scale_matrix(x: f32, y: f32, z: f32) -> Matrix {
         0.0, 0.0, z, 0.0,
         0.0, 0.0, 0.0, 1.0,
     )
-}
\ No newline at end of file
+}
+
+/// Creates a rotation matrix of `angle` radians around an arbitrary axis
+/// (Rodrigues' formula). The axis doesn't need to be normalized.
+pub fn create_rotation_axis_angle_matrix(axis: Vector3, angle: f32) -> Matrix {
+    let length = (axis.x * axis.x + axis.y * axis.y + axis.z * axis.z).sqrt();
+    if length == 0.0 {
+        return Matrix::identity();
+    }
+    let (x, y, z) = (axis.x / length, axis.y / length, axis.z / length);
+    let cos_a = angle.cos();
+    let sin_a = angle.sin();
+    let t = 1.0 - cos_a;
+
+    new_matrix4(
+        t * x * x + cos_a, t * x * y - sin_a * z, t * x * z + sin_a * y, 0.0,
+        t * x * y + sin_a * z, t * y * y + cos_a, t * y * z - sin_a * x, 0.0,
+        t * x * z - sin_a * y, t * y * z + sin_a * x, t * z * z + cos_a, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+/// Creates a rotation matrix from Euler angles in radians.
+/// Applied as roll (Z), then pitch (X), then yaw (Y): R = Ry * Rx * Rz
+pub fn create_rotation_euler_matrix(pitch: f32, yaw: f32, roll: f32) -> Matrix {
+    create_rotation_y_matrix(yaw)
+        .multiply(&create_rotation_x_matrix(pitch))
+        .multiply(&create_rotation_z_matrix(roll))
+}
+
+/// Creates a rotation matrix from a quaternion
+pub fn create_rotation_quaternion_matrix(q: &Quaternion) -> Matrix {
+    let q = q.normalized();
+    let (x, y, z, w) = (q.x, q.y, q.z, q.w);
+
+    new_matrix4(
+        1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w), 0.0,
+        2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w), 0.0,
+        2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y), 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+/// Rotation quaternion (x, y, z vector part, w scalar part)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Quaternion { x: 0.0, y: 0.0, z: 0.0, w: 1.0 }
+    }
+
+    /// Rotation of `angle` radians around `axis`
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Self {
+        let length = (axis.x * axis.x + axis.y * axis.y + axis.z * axis.z).sqrt();
+        if length == 0.0 {
+            return Quaternion::identity();
+        }
+        let s = (angle * 0.5).sin() / length;
+        Quaternion {
+            x: axis.x * s,
+            y: axis.y * s,
+            z: axis.z * s,
+            w: (angle * 0.5).cos(),
+        }
+    }
+
+    /// Same convention as `create_rotation_euler_matrix`
+    pub fn from_euler(pitch: f32, yaw: f32, roll: f32) -> Self {
+        Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), yaw)
+            .multiply(&Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), pitch))
+            .multiply(&Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), roll))
+    }
+
+    /// Hamilton product; `a.multiply(&b)` rotates by `b` first, then by `a`
+    pub fn multiply(&self, other: &Quaternion) -> Quaternion {
+        Quaternion {
+            x: self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            y: self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            z: self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+            w: self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+        }
+    }
+
+    pub fn normalized(&self) -> Quaternion {
+        let length = (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt();
+        if length == 0.0 {
+            return Quaternion::identity();
+        }
+        Quaternion {
+            x: self.x / length,
+            y: self.y / length,
+            z: self.z / length,
+            w: self.w / length,
+        }
+    }
+
+    pub fn to_matrix(self) -> Matrix {
+        create_rotation_quaternion_matrix(&self)
+    }
+
+    pub fn rotate_vector(&self, v: Vector3) -> Vector3 {
+        self.to_matrix().transform_point(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn assert_matrix_eq(a: &Matrix, b: &Matrix) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((a.m[i][j] - b.m[i][j]).abs() < 1e-5, "m[{}][{}]: {} vs {}", i, j, a.m[i][j], b.m[i][j]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_axis_angle_matches_principal_axes() {
+        let angle = 0.7;
+        assert_matrix_eq(&create_rotation_axis_angle_matrix(Vector3::new(1.0, 0.0, 0.0), angle), &create_rotation_x_matrix(angle));
+        assert_matrix_eq(&create_rotation_axis_angle_matrix(Vector3::new(0.0, 2.0, 0.0), angle), &create_rotation_y_matrix(angle));
+        assert_matrix_eq(&create_rotation_axis_angle_matrix(Vector3::new(0.0, 0.0, 1.0), angle), &create_rotation_z_matrix(angle));
+    }
+
+    #[test]
+    fn test_quaternion_matches_axis_angle_and_euler() {
+        let axis = Vector3::new(1.0, 2.0, -0.5);
+        let q = Quaternion::from_axis_angle(axis, 1.1);
+        assert_matrix_eq(&q.to_matrix(), &create_rotation_axis_angle_matrix(axis, 1.1));
+
+        let (pitch, yaw, roll) = (0.3, -1.2, 0.8);
+        assert_matrix_eq(
+            &Quaternion::from_euler(pitch, yaw, roll).to_matrix(),
+            &create_rotation_euler_matrix(pitch, yaw, roll),
+        );
+    }
+
+    #[test]
+    fn test_quaternion_product_composes_rotations() {
+        let a = Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), 0.4);
+        let b = Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), 1.3);
+        assert_matrix_eq(&a.multiply(&b).to_matrix(), &a.to_matrix().multiply(&b.to_matrix()));
+
+        let rotated = Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), PI / 2.0).rotate_vector(Vector3::new(1.0, 0.0, 0.0));
+        assert!((rotated.x).abs() < 1e-5 && (rotated.y - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_inverse_undoes_view_projection_and_viewport() {
+        let view = create_view_matrix(Vector3::new(30.0, 20.0, 90.0), Vector3::new(-5.0, 0.0, 3.0), Vector3::new(0.0, 1.0, 0.0));
+        let projection = create_projection_matrix(PI / 4.0, 1.5, 0.5, 1500.0);
+        let viewport = create_viewport_matrix(0.0, 0.0, 1200.0, 800.0);
+        for matrix in [view, projection, viewport] {
+            let inverse = matrix.inverse().unwrap();
+            assert_matrix_eq(&matrix.multiply(&inverse), &Matrix::identity());
+        }
+
+        // Ida y vuelta de un punto por toda la cadena (sus coeficientes son de
+        // cientos de píxeles, así que se compara el punto y no la identidad)
+        let chain = viewport.multiply(&projection).multiply(&view);
+        let point = Vector3::new(12.0, -3.0, 40.0);
+        let back = chain.inverse().unwrap().transform_point(chain.transform_point(point));
+        assert!((back - point).length() < 1e-2);
+    }
+
+    #[test]
+    fn test_singular_matrix_has_no_inverse() {
+        assert!(create_scale_matrix(1.0, 0.0, 1.0).inverse().is_none());
+        assert!(Matrix::new().inverse().is_none());
+    }
+}
@@ -0,0 +1,454 @@
+//! Mallas indexadas - Geometría lista para el pipeline
+//!
+//! Una `Mesh` guarda cada vértice una sola vez (posición, normal, tangente y
+//! UV) y los triángulos como índices, así el vertex shader procesa cada
+//! vértice una vez aunque lo compartan varios triángulos. Se puede construir
+//! desde un `ObjModel` o generar por código: esferas UV (meridianos y
+//! paralelos) e icosferas (icosaedro subdividido, con triángulos de tamaño
+//! casi uniforme).
+//!
+//! Las coordenadas de textura de las esferas usan la misma proyección
+//! equirectangular que los mapas de planetas: u = longitud, v = latitud, con
+//! el polo norte (+y) en v = 1. La tangente apunta hacia donde crece u.
+//!
+//! Las mallas de un OBJ guardan además qué triángulos usan un material del
+//! MTL (`MeshPart`); en esos triángulos el color, la textura y el brillo
+//! del material reemplazan a los del cuerpo.
+
+use raylib::prelude::*;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::fragment_shader::Material;
+use crate::obj_loader::{ObjModel, Vertex};
+use crate::texture::Texture;
+
+/// Vértice de una malla
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshVertex {
+    pub position: Vector3,
+    pub normal: Vector3,
+    /// Dirección en que crece u, perpendicular a la normal
+    pub tangent: Vector3,
+    pub uv: Vector2,
+}
+
+impl MeshVertex {
+    /// Atributos que recibe el vertex shader
+    pub fn shader_input(&self) -> Vertex {
+        Vertex::new(self.position, self.normal, self.uv)
+    }
+}
+
+/// Triángulos que usan un material del MTL: `Kd` es el color, `map_Kd`
+/// la textura y `Ks`/`Ns` el material
+#[derive(Clone, Debug)]
+pub struct MeshPart {
+    /// Triángulos en `Mesh::triangles`
+    pub triangles: Range<usize>,
+    pub color: Color,
+    pub texture: Option<Arc<Texture>>,
+    pub material: Material,
+}
+
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    pub vertices: Vec<MeshVertex>,
+    /// Índices en `vertices`, en sentido antihorario vistos desde afuera
+    pub triangles: Vec<[usize; 3]>,
+    /// Triángulos con material propio; los demás usan los del cuerpo
+    pub parts: Vec<MeshPart>,
+}
+
+impl Mesh {
+    /// Esfera de meridianos y paralelos. `segments` divide la longitud y
+    /// `rings` la latitud; la costura repite una columna de vértices para que
+    /// u vaya de 0 a 1 sin saltos.
+    pub fn uv_sphere(radius: f32, segments: usize, rings: usize) -> Self {
+        let segments = segments.max(3);
+        let rings = rings.max(2);
+
+        let mut vertices = Vec::with_capacity((rings + 1) * (segments + 1));
+        for ring in 0..=rings {
+            let v = 1.0 - ring as f32 / rings as f32;
+            for segment in 0..=segments {
+                let mut u = segment as f32 / segments as f32;
+                // En los polos la longitud no está definida: se usa el centro del gajo
+                if ring == 0 || ring == rings {
+                    u = (segment as f32 + 0.5) / segments as f32;
+                }
+                vertices.push(sphere_vertex(radius, Vector2::new(u, v)));
+            }
+        }
+
+        // En los polos solo hay un triángulo por gajo
+        let index = |ring: usize, segment: usize| ring * (segments + 1) + segment;
+        let mut triangles = Vec::with_capacity(2 * segments * (rings - 1));
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let top_left = index(ring, segment);
+                let top_right = index(ring, segment + 1);
+                let bottom_left = index(ring + 1, segment);
+                let bottom_right = index(ring + 1, segment + 1);
+                if ring > 0 {
+                    triangles.push([top_left, top_right, bottom_right]);
+                }
+                if ring < rings - 1 {
+                    triangles.push([top_left, bottom_right, bottom_left]);
+                }
+            }
+        }
+
+        Mesh { vertices, triangles, parts: Vec::new() }
+    }
+
+    /// Icosaedro con cada triángulo dividido en 4, `subdivisions` veces, y
+    /// los vértices proyectados sobre la esfera: 20·4ⁿ triángulos
+    pub fn icosphere(radius: f32, subdivisions: u32) -> Self {
+        let (mut positions, mut triangles) = icosahedron();
+
+        for _ in 0..subdivisions {
+            // Cada arista se parte una sola vez, aunque la compartan dos triángulos
+            let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+            let mut midpoint = |a: usize, b: usize, positions: &mut Vec<Vector3>| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    positions.push(((positions[a] + positions[b]) * 0.5).normalized());
+                    positions.len() - 1
+                })
+            };
+
+            let mut next = Vec::with_capacity(triangles.len() * 4);
+            for [a, b, c] in triangles {
+                let ab = midpoint(a, b, &mut positions);
+                let bc = midpoint(b, c, &mut positions);
+                let ca = midpoint(c, a, &mut positions);
+                next.extend([[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+            }
+            triangles = next;
+        }
+
+        // Los vértices de la costura y de los polos necesitan una UV distinta
+        // en cada lado: se duplican según la u que reciben en cada triángulo
+        let mut vertices = Vec::new();
+        let mut copies: HashMap<(usize, u32), usize> = HashMap::new();
+        let triangles = triangles
+            .into_iter()
+            .map(|corners| {
+                let uvs = spherical_face_uvs(&corners.map(|i| positions[i]));
+                let mut indexed = [0; 3];
+                for ((slot, &position), uv) in indexed.iter_mut().zip(&corners).zip(uvs) {
+                    *slot = *copies.entry((position, uv.x.to_bits())).or_insert_with(|| {
+                        vertices.push(sphere_vertex(radius, uv));
+                        vertices.len() - 1
+                    });
+                }
+                indexed
+            })
+            .collect();
+
+        Mesh { vertices, triangles, parts: Vec::new() }
+    }
+
+    /// Convierte un modelo OBJ: cada combinación distinta de posición,
+    /// coordenada de textura y normal es un vértice, y las caras de más de
+    /// tres lados se dividen en abanico. Las submeshes con un material
+    /// definido en el MTL son las partes de la malla.
+    pub fn from_obj(model: &ObjModel) -> Self {
+        let mut vertices = Vec::new();
+        let mut indices: HashMap<(usize, Option<usize>, Option<usize>), usize> = HashMap::new();
+        let mut triangles = Vec::new();
+        let mut parts = Vec::new();
+
+        for submesh in &model.submeshes {
+            let first_triangle = triangles.len();
+            for face in &model.faces[submesh.faces.clone()] {
+                let corners: Vec<usize> = (0..face.vertices.len())
+                    .map(|corner| {
+                        let key = (face.vertices[corner], face.texcoords.get(corner).copied(), face.normals.get(corner).copied());
+                        *indices.entry(key).or_insert_with(|| {
+                            vertices.push(MeshVertex {
+                                position: model.vertices[face.vertices[corner]],
+                                normal: model.face_normal(face, corner),
+                                tangent: Vector3::zero(),
+                                uv: model.face_uv(face, corner),
+                            });
+                            vertices.len() - 1
+                        })
+                    })
+                    .collect();
+
+                for i in 1..corners.len().saturating_sub(1) {
+                    triangles.push([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            if let Some(material) = submesh.material.as_ref().and_then(|name| model.materials.get(name)) {
+                parts.push(MeshPart {
+                    triangles: first_triangle..triangles.len(),
+                    color: material.diffuse_color(),
+                    texture: material.diffuse_texture.clone(),
+                    material: material.shading_material(),
+                });
+            }
+        }
+
+        let mut mesh = Mesh { vertices, triangles, parts };
+        mesh.calculate_tangents();
+        mesh
+    }
+
+    /// Índice en `parts` de la parte a la que pertenece un triángulo
+    pub fn part_of(&self, triangle: usize) -> Option<usize> {
+        self.parts.iter().position(|part| part.triangles.contains(&triangle))
+    }
+
+    /// La parte que cubre toda la malla, si hay una sola
+    pub fn uniform_part(&self) -> Option<&MeshPart> {
+        match self.parts.as_slice() {
+            [part] if part.triangles == (0..self.triangles.len()) => Some(part),
+            _ => None,
+        }
+    }
+
+    /// La misma malla con `part` aplicado a todos sus triángulos
+    pub fn with_uniform_part(mut self, part: Option<MeshPart>) -> Self {
+        self.parts = part.into_iter().map(|part| MeshPart { triangles: 0..self.triangles.len(), ..part }).collect();
+        self
+    }
+
+    /// Distancia del vértice más lejano al origen de la malla
+    pub fn bounding_radius(&self) -> f32 {
+        self.vertices.iter().map(|vertex| vertex.position.length()).fold(0.0, f32::max)
+    }
+
+    /// Tangentes a partir de cómo cambian las UV en cada triángulo,
+    /// promediadas por vértice y hechas perpendiculares a la normal
+    fn calculate_tangents(&mut self) {
+        let mut sums = vec![Vector3::zero(); self.vertices.len()];
+        for &[a, b, c] in &self.triangles {
+            let [p0, p1, p2] = [a, b, c].map(|i| self.vertices[i].position);
+            let [t0, t1, t2] = [a, b, c].map(|i| self.vertices[i].uv);
+            let (edge1, edge2) = (p1 - p0, p2 - p0);
+            let (du1, dv1, du2, dv2) = (t1.x - t0.x, t1.y - t0.y, t2.x - t0.x, t2.y - t0.y);
+            let determinant = du1 * dv2 - du2 * dv1;
+            if determinant.abs() < 1e-12 {
+                continue; // Sin UVs útiles en este triángulo
+            }
+            let tangent = (edge1 * dv2 - edge2 * dv1) / determinant;
+            for i in [a, b, c] {
+                sums[i] += tangent;
+            }
+        }
+
+        for (vertex, sum) in self.vertices.iter_mut().zip(sums) {
+            // Gram-Schmidt; si no hay UVs se usa cualquier dirección perpendicular
+            let tangent = sum - vertex.normal * sum.dot(vertex.normal);
+            vertex.tangent = if tangent.length() > 1e-6 {
+                tangent.normalized()
+            } else {
+                any_perpendicular(vertex.normal)
+            };
+        }
+    }
+}
+
+/// Coordenadas equirectangulares de los vértices de una cara de una esfera
+/// centrada en el origen.
+///
+/// Si la cara cruza la costura (u = 0 ↔ 1), las esquinas cercanas a 0 pasan
+/// al otro lado (u > 1, el sampler repite) para no interpolar a través de
+/// todo el mapa. En los polos la longitud no está definida y se usa la del
+/// resto de la cara.
+pub(crate) fn spherical_face_uvs(positions: &[Vector3]) -> Vec<Vector2> {
+    let mut uvs: Vec<Vector2> = positions.iter().map(|&position| spherical_uv(position)).collect();
+
+    let max_u = uvs.iter().map(|uv| uv.x).fold(f32::MIN, f32::max);
+    let min_u = uvs.iter().map(|uv| uv.x).fold(f32::MAX, f32::min);
+    if max_u - min_u > 0.5 {
+        for uv in &mut uvs {
+            if uv.x < 0.5 {
+                uv.x += 1.0;
+            }
+        }
+    }
+
+    let poles: Vec<bool> = positions.iter().map(|&position| is_pole(position)).collect();
+    let others: Vec<f32> = uvs.iter().zip(&poles).filter(|(_, &pole)| !pole).map(|(uv, _)| uv.x).collect();
+    if !others.is_empty() {
+        let mean_u = others.iter().sum::<f32>() / others.len() as f32;
+        for (uv, &pole) in uvs.iter_mut().zip(&poles) {
+            if pole {
+                uv.x = mean_u;
+            }
+        }
+    }
+
+    uvs
+}
+
+/// Proyección equirectangular de una dirección desde el centro del modelo
+fn spherical_uv(position: Vector3) -> Vector2 {
+    let direction = position.normalized();
+    let u = 0.5 + direction.z.atan2(direction.x) / (2.0 * PI);
+    let v = 0.5 + direction.y.clamp(-1.0, 1.0).asin() / PI;
+    Vector2::new(u, v)
+}
+
+fn is_pole(position: Vector3) -> bool {
+    position.x.abs() < 1e-5 && position.z.abs() < 1e-5
+}
+
+/// Vértice de una esfera en el punto con coordenadas equirectangulares `uv`
+fn sphere_vertex(radius: f32, uv: Vector2) -> MeshVertex {
+    let longitude = (uv.x - 0.5) * 2.0 * PI;
+    let latitude = (uv.y - 0.5) * PI;
+    let normal = Vector3::new(
+        latitude.cos() * longitude.cos(),
+        latitude.sin(),
+        latitude.cos() * longitude.sin(),
+    );
+    MeshVertex {
+        position: normal * radius,
+        normal,
+        // Derivada de la posición respecto a la longitud (definida también en los polos)
+        tangent: Vector3::new(-longitude.sin(), 0.0, longitude.cos()),
+        uv,
+    }
+}
+
+/// Los 12 vértices y 20 caras de un icosaedro inscrito en la esfera unitaria
+fn icosahedron() -> (Vec<Vector3>, Vec<[usize; 3]>) {
+    let phi = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let positions = [
+        (-1.0, phi, 0.0), (1.0, phi, 0.0), (-1.0, -phi, 0.0), (1.0, -phi, 0.0),
+        (0.0, -1.0, phi), (0.0, 1.0, phi), (0.0, -1.0, -phi), (0.0, 1.0, -phi),
+        (phi, 0.0, -1.0), (phi, 0.0, 1.0), (-phi, 0.0, -1.0), (-phi, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| Vector3::new(x, y, z).normalized())
+    .collect();
+
+    let triangles = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    (positions, triangles)
+}
+
+fn any_perpendicular(normal: Vector3) -> Vector3 {
+    let axis = if normal.x.abs() < 0.9 { Vector3::new(1.0, 0.0, 0.0) } else { Vector3::new(0.0, 1.0, 0.0) };
+    normal.cross(axis).normalized()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Comprueba lo que toda esfera generada debe cumplir
+    fn check_sphere(mesh: &Mesh, radius: f32) {
+        assert!((mesh.bounding_radius() - radius).abs() < 1e-4);
+        for vertex in &mesh.vertices {
+            assert!((vertex.position.length() - radius).abs() < 1e-4);
+            assert!((vertex.normal - vertex.position / radius).length() < 1e-4);
+            assert!(vertex.tangent.dot(vertex.normal).abs() < 1e-4);
+            assert!((vertex.tangent.length() - 1.0).abs() < 1e-4);
+        }
+
+        for &[a, b, c] in &mesh.triangles {
+            let [p0, p1, p2] = [a, b, c].map(|i| mesh.vertices[i].position);
+            let outward = (p1 - p0).cross(p2 - p0).dot(p0 + p1 + p2);
+            assert!(outward > 0.0, "triangle {:?} points inwards", [a, b, c]);
+
+            // Ningún triángulo interpola a través de todo el mapa
+            let us = [a, b, c].map(|i| mesh.vertices[i].uv.x);
+            let spread = us.iter().cloned().fold(f32::MIN, f32::max) - us.iter().cloned().fold(f32::MAX, f32::min);
+            assert!(spread < 0.5, "triangle {:?} spans the seam: {:?}", [a, b, c], us);
+        }
+    }
+
+    #[test]
+    fn test_uv_sphere() {
+        let mesh = Mesh::uv_sphere(2.0, 16, 8);
+        check_sphere(&mesh, 2.0);
+        assert_eq!(mesh.vertices.len(), 17 * 9);
+        // Dos triángulos por gajo salvo en los polos
+        assert_eq!(mesh.triangles.len(), 16 * 8 * 2 - 2 * 16);
+
+        // El ecuador, frente al eje +x, queda en el centro del mapa
+        let equator = mesh.vertices.iter().find(|v| (v.position.x - 2.0).abs() < 1e-5).unwrap();
+        assert_eq!(equator.uv, Vector2::new(0.5, 0.5));
+        assert!((equator.tangent - Vector3::new(0.0, 0.0, 1.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_icosphere() {
+        // El icosaedro sin subdividir tiene caras de más de medio mapa de ancho
+        assert_eq!(Mesh::icosphere(1.5, 0).triangles.len(), 20);
+        for subdivisions in 1..4 {
+            let mesh = Mesh::icosphere(1.5, subdivisions);
+            check_sphere(&mesh, 1.5);
+            assert_eq!(mesh.triangles.len(), 20 * 4usize.pow(subdivisions));
+        }
+
+        // Las aristas compartidas se parten una sola vez: 10·4ⁿ + 2 posiciones
+        // distintas (más las copias de la costura y los polos)
+        let mesh = Mesh::icosphere(1.0, 2);
+        let mut positions: Vec<[i32; 3]> = mesh
+            .vertices
+            .iter()
+            .map(|v| [v.position.x, v.position.y, v.position.z].map(|c| (c * 1e4).round() as i32))
+            .collect();
+        positions.sort();
+        positions.dedup();
+        assert_eq!(positions.len(), 162);
+    }
+
+    #[test]
+    fn test_from_obj_shares_vertices() {
+        let path = std::env::temp_dir().join("mesh_test_quad.obj");
+        std::fs::write(&path, "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nf 1/1 2/2 3/3 4/4\n").unwrap();
+        let model = ObjModel::load(&path.to_string_lossy()).unwrap();
+
+        let mesh = Mesh::from_obj(&model);
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+        // u crece hacia +x
+        for vertex in &mesh.vertices {
+            assert!((vertex.tangent - Vector3::new(1.0, 0.0, 0.0)).length() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_from_obj_keeps_mtl_materials() {
+        let dir = std::env::temp_dir();
+        std::fs::write(dir.join("mesh_test_parts.mtl"), "newmtl brillo\nKd 0 0 1\nKs 0.5 0.5 0.5\nNs 20\n").unwrap();
+        let path = dir.join("mesh_test_parts.obj");
+        std::fs::write(
+            &path,
+            "mtllib mesh_test_parts.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nusemtl brillo\nf 1 2 3 4\n",
+        )
+        .unwrap();
+        let model = ObjModel::load(&path.to_string_lossy()).unwrap();
+
+        // La primera cara no tiene material; el cuadrado (dos triángulos) sí
+        let mesh = Mesh::from_obj(&model);
+        assert_eq!(mesh.triangles.len(), 3);
+        assert_eq!(mesh.part_of(0), None);
+        assert_eq!((mesh.part_of(1), mesh.part_of(2)), (Some(0), Some(0)));
+        let part = &mesh.parts[0];
+        assert_eq!(part.color, Color::new(0, 0, 255, 255));
+        assert_eq!(part.material, Material::Phong { specular: 0.5, shininess: 20.0 });
+        assert!(mesh.uniform_part().is_none());
+
+        // Un material que cubre toda la malla pasa a las icosferas del LOD
+        let uniform = Mesh::icosphere(0.5, 1).with_uniform_part(Some(part.clone()));
+        let chain = crate::lod::LodChain::for_sphere(uniform);
+        for level in &chain.levels {
+            assert_eq!(level.mesh.uniform_part().map(|part| part.color), Some(Color::new(0, 0, 255, 255)));
+        }
+    }
+}
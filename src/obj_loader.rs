@@ -1,101 +1,450 @@
 use raylib::prelude::*;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
 
-#[derive(Clone, Debug)]
+use crate::asset_error::AssetError;
+use crate::fragment_shader::Material;
+use crate::mesh::spherical_face_uvs;
+use crate::texture::Texture;
+
+/// Atributos de entrada de un vértice para el vertex shader
+#[derive(Clone, Copy, Debug)]
 pub struct Vertex {
     pub position: Vector3,
     pub normal: Vector3,
+    /// Coordenadas de textura (u a la derecha, v hacia arriba)
+    pub uv: Vector2,
+}
+
+impl Vertex {
+    pub fn new(position: Vector3, normal: Vector3, uv: Vector2) -> Self {
+        Vertex { position, normal, uv }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Face {
     pub vertices: Vec<usize>, // Indices de vértices
+    /// Índices de coordenadas de textura, uno por vértice (vacío si la cara no tiene `vt`)
+    pub texcoords: Vec<usize>,
+    /// Índices de normales, uno por vértice. Las caras sin `vn` usan normales
+    /// suavizadas calculadas al cargar, así que nunca queda vacío.
+    pub normals: Vec<usize>,
+}
+
+/// Parte de un modelo: las caras de un objeto (`o`) o grupo (`g`) que
+/// comparten material (`usemtl`)
+#[derive(Clone, Debug)]
+pub struct Submesh {
+    pub name: String,
+    pub material: Option<String>,
+    /// Caras en `ObjModel::faces`
+    pub faces: Range<usize>,
+}
+
+/// Material de un archivo MTL
+#[derive(Clone, Debug)]
+pub struct ObjMaterial {
+    pub name: String,
+    /// `Ka`, color ambiental
+    pub ambient: [f32; 3],
+    /// `Kd`, color difuso
+    pub diffuse: [f32; 3],
+    /// `Ks`, color especular
+    pub specular: [f32; 3],
+    /// `Ns`, exponente especular
+    pub shininess: f32,
+    /// `map_Kd`, textura difusa (ruta relativa al directorio de trabajo)
+    pub diffuse_map: Option<String>,
+    /// La imagen de `diffuse_map`, si se pudo cargar
+    pub diffuse_texture: Option<Arc<Texture>>,
+}
+
+impl ObjMaterial {
+    fn new(name: String) -> Self {
+        // Valores por defecto de la especificación MTL
+        ObjMaterial {
+            name,
+            ambient: [0.2, 0.2, 0.2],
+            diffuse: [0.8, 0.8, 0.8],
+            specular: [1.0, 1.0, 1.0],
+            shininess: 0.0,
+            diffuse_map: None,
+            diffuse_texture: None,
+        }
+    }
+
+    /// `Kd` como color de 8 bits
+    pub fn diffuse_color(&self) -> Color {
+        let [r, g, b] = self.diffuse.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        Color::new(r, g, b, 255)
+    }
+
+    /// `Ks` y `Ns` como material del fragment shader: Phong con la
+    /// intensidad especular promedio de `Ks`, o Lambert si no hay brillo
+    pub fn shading_material(&self) -> Material {
+        let specular = self.specular.iter().sum::<f32>() / 3.0;
+        if specular <= 0.0 {
+            return Material::Lambert;
+        }
+        Material::Phong { specular, shininess: self.shininess.max(1.0) }
+    }
 }
 
 pub struct ObjModel {
     pub vertices: Vec<Vector3>,
     pub normals: Vec<Vector3>,
+    pub texcoords: Vec<Vector2>,
     pub faces: Vec<Face>,
+    /// Objetos y grupos, en el orden del archivo; cubren todas las caras
+    pub submeshes: Vec<Submesh>,
+    /// Materiales de los archivos `mtllib`, por nombre
+    pub materials: HashMap<String, ObjMaterial>,
+    /// Problemas que no impiden usar el modelo (bibliotecas de materiales
+    /// que no existen, materiales sin definir), con número de línea
+    pub warnings: Vec<String>,
+}
+
+/// Palabra de una línea con su columna (desde 1)
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    column: usize,
+}
+
+/// Separa una línea en palabras, sin el comentario final
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+    let content = line.split('#').next().unwrap_or("");
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (offset, ch) in content.char_indices().chain(std::iter::once((content.len(), ' '))) {
+        match (ch.is_whitespace(), start) {
+            (false, None) => start = Some(offset),
+            (true, Some(begin)) => {
+                tokens.push(Token { text: &content[begin..offset], column: begin + 1 });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+/// Archivo y línea que se están leyendo, para construir los errores
+#[derive(Clone, Copy)]
+struct LineContext<'a> {
+    path: &'a str,
+    line: usize,
+}
+
+impl LineContext<'_> {
+    fn parse_error(&self, column: usize, message: impl Into<String>) -> AssetError {
+        AssetError::Parse {
+            path: self.path.to_string(),
+            line: self.line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    fn warning(&self, message: impl fmt::Display) -> String {
+        format!("{}:{}: {}", self.path, self.line, message)
+    }
+
+    /// Lee hasta `N` números después de la palabra clave; exige al menos
+    /// `required` y los que faltan toman el valor de `defaults`
+    fn parse_floats<const N: usize>(
+        &self,
+        tokens: &[Token],
+        required: usize,
+        defaults: [f32; N],
+    ) -> Result<[f32; N], AssetError> {
+        let keyword = tokens[0];
+        let values = &tokens[1..];
+        if values.len() < required {
+            let column = tokens.last().map_or(1, |last| last.column + last.text.len());
+            return Err(self.parse_error(
+                column,
+                format!("'{}' needs at least {} values, got {}", keyword.text, required, values.len()),
+            ));
+        }
+
+        let mut result = defaults;
+        for (slot, token) in result.iter_mut().zip(values) {
+            *slot = match token.text.parse::<f32>() {
+                Ok(value) if value.is_finite() => value,
+                _ => {
+                    return Err(self.parse_error(
+                        token.column,
+                        format!("invalid number '{}' in '{}'", token.text, keyword.text),
+                    ))
+                }
+            };
+        }
+        Ok(result)
+    }
+
+    /// Convierte un índice de OBJ (desde 1, o negativo relativo al final) en
+    /// un índice desde 0 válido para una lista de `count` elementos
+    fn resolve_index(&self, text: &str, column: usize, count: usize, kind: &'static str) -> Result<usize, AssetError> {
+        let index: i64 = text
+            .parse()
+            .map_err(|_| self.parse_error(column, format!("invalid {} index '{}'", kind, text)))?;
+        // 0 no es válido: los índices de OBJ empiezan en 1
+        let resolved = if index > 0 { index - 1 } else { count as i64 + index };
+        if index == 0 || resolved < 0 || resolved >= count as i64 {
+            return Err(AssetError::IndexOutOfRange {
+                path: self.path.to_string(),
+                line: self.line,
+                column,
+                kind,
+                index,
+                count,
+            });
+        }
+        Ok(resolved as usize)
+    }
+}
+
+/// Lista de submeshes que se va armando mientras se leen las caras
+struct SubmeshBuilder {
+    submeshes: Vec<Submesh>,
+}
+
+impl SubmeshBuilder {
+    fn new() -> Self {
+        SubmeshBuilder {
+            submeshes: vec![Submesh { name: "default".to_string(), material: None, faces: 0..0 }],
+        }
+    }
+
+    fn current(&mut self) -> &mut Submesh {
+        self.submeshes.last_mut().expect("there is always a submesh")
+    }
+
+    /// Empieza una submesh nueva en la cara `first_face`; si la actual no
+    /// tiene caras, se reutiliza
+    fn start(&mut self, first_face: usize, name: Option<String>, material: Option<Option<String>>) {
+        let current = self.current();
+        current.faces.end = first_face;
+        let name = name.unwrap_or_else(|| current.name.clone());
+        let material = material.unwrap_or_else(|| current.material.clone());
+
+        if current.faces.is_empty() {
+            current.name = name;
+            current.material = material;
+            current.faces = first_face..first_face;
+        } else {
+            self.submeshes.push(Submesh { name, material, faces: first_face..first_face });
+        }
+    }
+
+    fn finish(mut self, face_count: usize) -> Vec<Submesh> {
+        self.current().faces.end = face_count;
+        self.submeshes.retain(|submesh| !submesh.faces.is_empty());
+        self.submeshes
+    }
 }
 
 impl ObjModel {
-    pub fn load(path: &str) -> Result<Self, String> {
-        let file = File::open(path).map_err(|e| format!("Error opening file: {}", e))?;
-        let reader = BufReader::new(file);
+    pub fn load(path: &str) -> Result<Self, AssetError> {
+        let source = std::fs::read_to_string(path).map_err(|e| AssetError::Io {
+            path: path.to_string(),
+            message: e.to_string(),
+        })?;
+        let base_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        Self::parse(&source, path, base_dir)
+    }
 
+    /// Lee un OBJ ya cargado en memoria. `path` solo se usa en los mensajes
+    /// de error y los `mtllib` se buscan en `base_dir`
+    fn parse(source: &str, path: &str, base_dir: &Path) -> Result<Self, AssetError> {
         let mut vertices = Vec::new();
         let mut normals = Vec::new();
-        let mut faces = Vec::new();
-
-        for line in reader.lines() {
-            let line = line.map_err(|e| format!("Error reading line: {}", e))?;
-            let line = line.trim();
-
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
+        let mut texcoords = Vec::new();
+        let mut faces: Vec<Face> = Vec::new();
+        let mut materials = HashMap::new();
+        let mut submeshes = SubmeshBuilder::new();
+        let mut warnings = Vec::new();
 
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.is_empty() {
+        for (line_index, line) in source.lines().enumerate() {
+            let context = LineContext { path, line: line_index + 1 };
+            let tokens = tokenize(line);
+            let Some(keyword) = tokens.first() else {
                 continue;
-            }
+            };
 
-            match parts[0] {
+            match keyword.text {
                 "v" => {
-                    // Vértice
-                    if parts.len() >= 4 {
-                        let x = parts[1].parse::<f32>().unwrap_or(0.0);
-                        let y = parts[2].parse::<f32>().unwrap_or(0.0);
-                        let z = parts[3].parse::<f32>().unwrap_or(0.0);
-                        vertices.push(Vector3::new(x, y, z));
-                    }
+                    // Vértice (la w opcional se ignora)
+                    let [x, y, z, _] = context.parse_floats(&tokens, 3, [0.0, 0.0, 0.0, 1.0])?;
+                    vertices.push(Vector3::new(x, y, z));
                 }
                 "vn" => {
                     // Normal
-                    if parts.len() >= 4 {
-                        let x = parts[1].parse::<f32>().unwrap_or(0.0);
-                        let y = parts[2].parse::<f32>().unwrap_or(0.0);
-                        let z = parts[3].parse::<f32>().unwrap_or(0.0);
-                        normals.push(Vector3::new(x, y, z));
-                    }
+                    let [x, y, z] = context.parse_floats(&tokens, 3, [0.0; 3])?;
+                    normals.push(Vector3::new(x, y, z));
+                }
+                "vt" => {
+                    // Coordenada de textura (v y w son opcionales; w se ignora)
+                    let [u, v, _] = context.parse_floats(&tokens, 1, [0.0; 3])?;
+                    texcoords.push(Vector2::new(u, v));
                 }
                 "f" => {
                     // Cara (face)
+                    if tokens.len() < 4 {
+                        return Err(context.parse_error(
+                            keyword.column,
+                            format!("face needs at least 3 vertices, got {}", tokens.len() - 1),
+                        ));
+                    }
+
                     let mut vertex_indices = Vec::new();
-                    for i in 1..parts.len() {
+                    let mut texcoord_indices = Vec::new();
+                    let mut normal_indices = Vec::new();
+                    for corner in &tokens[1..] {
                         // Formato puede ser v, v/vt, v/vt/vn, o v//vn
-                        let face_part = parts[i];
-                        let indices: Vec<&str> = face_part.split('/').collect();
-                        
-                        if let Ok(v_idx) = indices[0].parse::<usize>() {
-                            // OBJ usa índices desde 1, convertimos a índices desde 0
-                            vertex_indices.push(v_idx - 1);
+                        let mut column = corner.column;
+                        for (slot, text) in corner.text.split('/').enumerate() {
+                            match (slot, text) {
+                                (0, _) => vertex_indices.push(context.resolve_index(text, column, vertices.len(), "vertex")?),
+                                (1 | 2, "") => {}
+                                (1, _) => texcoord_indices.push(context.resolve_index(text, column, texcoords.len(), "texcoord")?),
+                                (2, _) => normal_indices.push(context.resolve_index(text, column, normals.len(), "normal")?),
+                                _ => return Err(context.parse_error(corner.column, format!("invalid face vertex '{}'", corner.text))),
+                            }
+                            column += text.len() + 1;
                         }
                     }
-                    
-                    if vertex_indices.len() >= 3 {
-                        faces.push(Face {
-                            vertices: vertex_indices,
-                        });
+
+                    // Todas las esquinas deben tener los mismos atributos
+                    for (indices, kind) in [(&texcoord_indices, "texcoord"), (&normal_indices, "normal")] {
+                        if !indices.is_empty() && indices.len() != vertex_indices.len() {
+                            return Err(context.parse_error(
+                                keyword.column,
+                                format!("some face vertices have a {} index and some don't", kind),
+                            ));
+                        }
+                    }
+
+                    faces.push(Face {
+                        vertices: vertex_indices,
+                        texcoords: texcoord_indices,
+                        normals: normal_indices,
+                    });
+                }
+                "o" | "g" => {
+                    let name = tokens[1..].iter().map(|token| token.text).collect::<Vec<_>>().join(" ");
+                    let name = if name.is_empty() { "default".to_string() } else { name };
+                    submeshes.start(faces.len(), Some(name), None);
+                }
+                "usemtl" => {
+                    let name = tokens
+                        .get(1)
+                        .ok_or_else(|| context.parse_error(keyword.column, "usemtl needs a material name"))?
+                        .text;
+                    // Blender escribe `usemtl None` en los objetos sin material
+                    let material = (name != "None").then(|| name.to_string());
+                    if material.is_some() && !materials.contains_key(name) {
+                        warnings.push(context.warning(format!("material '{}' is not defined in any mtllib", name)));
                     }
+                    submeshes.start(faces.len(), None, Some(material));
                 }
+                "mtllib" => {
+                    if tokens.len() < 2 {
+                        return Err(context.parse_error(keyword.column, "mtllib needs a file name"));
+                    }
+                    for library in &tokens[1..] {
+                        let library_path = base_dir.join(library.text);
+                        // Un archivo que no existe no impide usar la geometría;
+                        // uno que existe pero tiene errores sí es un error
+                        if !library_path.exists() {
+                            warnings.push(context.warning(format!("material library '{}' not found", library_path.display())));
+                            continue;
+                        }
+                        for mut material in load_mtl(&library_path)? {
+                            // Igual que con la biblioteca, una textura que falta solo se avisa
+                            if let Some(map) = &material.diffuse_map {
+                                match Texture::load(map) {
+                                    Ok(texture) => material.diffuse_texture = Some(Arc::new(texture)),
+                                    Err(e) => warnings.push(context.warning(format!("material '{}': {}", material.name, e))),
+                                }
+                            }
+                            materials.insert(material.name.clone(), material);
+                        }
+                    }
+                }
+                // Suavizado, líneas, puntos, curvas, etc. no se usan
                 _ => {}
             }
         }
 
-        // Si no hay normales en el archivo, las calculamos
-        if normals.is_empty() {
-            normals = Self::calculate_normals(&vertices, &faces);
+        if faces.is_empty() {
+            return Err(AssetError::EmptyMesh { path: path.to_string() });
+        }
+
+        // Las caras sin `vn` usan normales suavizadas, una por vértice
+        if faces.iter().any(|face| face.normals.is_empty()) {
+            let first = normals.len();
+            normals.extend(Self::calculate_normals(&vertices, &faces));
+            for face in faces.iter_mut().filter(|face| face.normals.is_empty()) {
+                face.normals = face.vertices.iter().map(|&v| first + v).collect();
+            }
         }
 
+        let submeshes = submeshes.finish(faces.len());
+
         Ok(ObjModel {
             vertices,
             normals,
+            texcoords,
             faces,
+            submeshes,
+            materials,
+            warnings,
         })
     }
 
+    /// Normal del vértice `corner` de una cara
+    pub fn face_normal(&self, face: &Face, corner: usize) -> Vector3 {
+        face.normals
+            .get(corner)
+            .and_then(|&i| self.normals.get(i))
+            .copied()
+            .unwrap_or(Vector3::new(0.0, 1.0, 0.0))
+    }
+
+    /// Coordenada de textura del vértice `corner` de una cara
+    /// (0, 0) si la cara no tiene `vt` o el índice no existe
+    pub fn face_uv(&self, face: &Face, corner: usize) -> Vector2 {
+        face.texcoords
+            .get(corner)
+            .and_then(|&i| self.texcoords.get(i))
+            .copied()
+            .unwrap_or(Vector2::zero())
+    }
+
+    /// Reemplaza las coordenadas de textura por una proyección equirectangular
+    /// centrada en el origen del modelo: u = longitud, v = latitud (polo norte en v = 1).
+    ///
+    /// Cada esquina de cada cara recibe su propia coordenada, así los triángulos
+    /// que cruzan la costura (u = 0 ↔ 1) no interpolan a través de todo el mapa.
+    pub fn generate_spherical_uvs(&mut self) {
+        let mut texcoords = Vec::new();
+
+        for face in &mut self.faces {
+            let positions: Vec<Vector3> = face.vertices.iter().map(|&i| self.vertices[i]).collect();
+            let uvs = spherical_face_uvs(&positions);
+            face.texcoords = (texcoords.len()..texcoords.len() + uvs.len()).collect();
+            texcoords.extend(uvs);
+        }
+
+        self.texcoords = texcoords;
+    }
+
     fn calculate_normals(vertices: &[Vector3], faces: &[Face]) -> Vec<Vector3> {
         let mut normals = vec![Vector3::zero(); vertices.len()];
         
@@ -155,3 +504,203 @@ impl ObjModel {
         triangles
     }
 }
+
+/// Lee los materiales de un archivo MTL
+fn load_mtl(path: &Path) -> Result<Vec<ObjMaterial>, AssetError> {
+    let file = path.to_string_lossy();
+    let source = std::fs::read_to_string(path).map_err(|e| AssetError::Io {
+        path: file.to_string(),
+        message: e.to_string(),
+    })?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+    for (line_index, line) in source.lines().enumerate() {
+        let context = LineContext { path: &file, line: line_index + 1 };
+        let tokens = tokenize(line);
+        let Some(keyword) = tokens.first() else {
+            continue;
+        };
+
+        if keyword.text == "newmtl" {
+            let name = tokens
+                .get(1)
+                .ok_or_else(|| context.parse_error(keyword.column, "newmtl needs a material name"))?;
+            materials.push(ObjMaterial::new(name.text.to_string()));
+            continue;
+        }
+
+        let Some(material) = materials.last_mut() else {
+            return Err(context.parse_error(keyword.column, format!("'{}' before any newmtl", keyword.text)));
+        };
+
+        match keyword.text {
+            "Ka" => material.ambient = context.parse_floats(&tokens, 3, [0.0; 3])?,
+            "Kd" => material.diffuse = context.parse_floats(&tokens, 3, [0.0; 3])?,
+            "Ks" => material.specular = context.parse_floats(&tokens, 3, [0.0; 3])?,
+            "Ns" => material.shininess = context.parse_floats(&tokens, 1, [0.0])?[0],
+            "map_Kd" => {
+                // Las opciones (-s, -o, ...) van antes del nombre; se usa el último campo
+                if tokens.len() < 2 {
+                    return Err(context.parse_error(keyword.column, "map_Kd needs a file name"));
+                }
+                let texture = tokens[tokens.len() - 1].text;
+                material.diffuse_map = Some(base_dir.join(texture).to_string_lossy().to_string());
+            }
+            // Transparencia, modelo de iluminación, otros mapas, etc. no se usan
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_source(name: &str, source: &str) -> ObjModel {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, source).unwrap();
+        ObjModel::load(&path.to_string_lossy()).unwrap()
+    }
+
+    #[test]
+    fn test_face_texture_coordinates() {
+        let model = load_source(
+            "obj_loader_test_uvs.obj",
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.0 0.0\nvt 1.0 0.0\nvt 0.0 1.0\nf 1/1 2/2 3/3\nf 1 2 3\n",
+        );
+        assert_eq!(model.texcoords.len(), 3);
+        assert_eq!(model.face_uv(&model.faces[0], 1), Vector2::new(1.0, 0.0));
+        // Cara sin vt: coordenadas en cero
+        assert!(model.faces[1].texcoords.is_empty());
+        assert_eq!(model.face_uv(&model.faces[1], 2), Vector2::zero());
+    }
+
+    #[test]
+    fn test_spherical_uvs_handle_the_seam() {
+        // Triángulo que cruza la costura (longitud ±180°, detrás del eje -x)
+        let mut model = load_source(
+            "obj_loader_test_seam.obj",
+            "v -1 0 0.1\nv -1 0 -0.1\nv -1 0.1 0\nf 1 2 3\n",
+        );
+        model.generate_spherical_uvs();
+
+        let us: Vec<f32> = (0..3).map(|c| model.face_uv(&model.faces[0], c).x).collect();
+        let spread = us.iter().cloned().fold(f32::MIN, f32::max) - us.iter().cloned().fold(f32::MAX, f32::min);
+        assert!(spread < 0.1, "triangle spans the whole map: {:?}", us);
+
+        // El ecuador queda en v = 0.5
+        assert!((model.face_uv(&model.faces[0], 0).y - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_corner_indices_and_relative_indices() {
+        let model = load_source(
+            "obj_loader_test_indices.obj",
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nvn 0 0 -1\nvt 0.5\n\
+             f 1//2 2//2 3//2\nf -3/-1/-1 -2/1/1 -1/1/1\n",
+        );
+        assert_eq!(model.face_normal(&model.faces[0], 0), Vector3::new(0.0, 0.0, -1.0));
+        // -1 es el último elemento definido hasta esa línea
+        assert_eq!(model.faces[1].vertices, vec![0, 1, 2]);
+        assert_eq!(model.faces[1].normals, vec![1, 0, 0]);
+        assert_eq!(model.face_uv(&model.faces[1], 0), Vector2::new(0.5, 0.0));
+    }
+
+    #[test]
+    fn test_computes_normals_for_faces_without_vn() {
+        let model = load_source("obj_loader_test_no_normals.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n");
+        for corner in 0..3 {
+            assert_eq!(model.face_normal(&model.faces[0], corner), Vector3::new(0.0, 0.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn test_groups_and_materials() {
+        let dir = std::env::temp_dir();
+        std::fs::write(
+            dir.join("obj_loader_test.mtl"),
+            "newmtl roca\nKa 0.1 0.1 0.1\nKd 1.0 0.5 0.0\nKs 0.2 0.2 0.2\nNs 32\nmap_Kd -s 1 1 1 obj_loader_roca.ppm\n\nnewmtl hielo\n",
+        )
+        .unwrap();
+        let mut texture = b"P6\n2 1\n255\n".to_vec();
+        texture.extend([200, 100, 50, 10, 20, 30]);
+        std::fs::write(dir.join("obj_loader_roca.ppm"), texture).unwrap();
+        let model = load_source(
+            "obj_loader_test_groups.obj",
+            "mtllib obj_loader_test.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\n\
+             o Roca\nusemtl roca\nf 1 2 3\nf 1 2 3\nusemtl hielo\nf 1 2 3\ng Vacio\ng Ultimo\nf 1 2 3\n",
+        );
+
+        let summary: Vec<(&str, Option<&str>, Range<usize>)> = model
+            .submeshes
+            .iter()
+            .map(|submesh| (submesh.name.as_str(), submesh.material.as_deref(), submesh.faces.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("Roca", Some("roca"), 0..2), ("Roca", Some("hielo"), 2..3), ("Ultimo", Some("hielo"), 3..4)]
+        );
+
+        let roca = &model.materials["roca"];
+        assert_eq!(roca.diffuse, [1.0, 0.5, 0.0]);
+        assert_eq!(roca.diffuse_color(), Color::new(255, 128, 0, 255));
+        assert_eq!(roca.shininess, 32.0);
+        assert!(roca.diffuse_map.as_ref().unwrap().ends_with("obj_loader_roca.ppm"));
+        assert_eq!(roca.diffuse_texture.as_ref().map(|texture| texture.width()), Some(2));
+        match roca.shading_material() {
+            Material::Phong { specular, shininess } => assert!((specular - 0.2).abs() < 1e-6 && shininess == 32.0),
+            other => panic!("expected Phong, got {:?}", other),
+        }
+        // Valores por defecto de MTL
+        assert_eq!(model.materials["hielo"].diffuse, [0.8, 0.8, 0.8]);
+        assert!(model.materials["hielo"].diffuse_texture.is_none());
+        assert!(model.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_errors_report_line_numbers() {
+        let path = std::env::temp_dir().join("obj_loader_test_errors.obj");
+        let load = |source: &str| {
+            std::fs::write(&path, source).unwrap();
+            ObjModel::load(&path.to_string_lossy()).err().unwrap()
+        };
+
+        let file = path.to_string_lossy().to_string();
+        assert_eq!(
+            load("v 0 0 0\nv 1 zero 0\n"),
+            AssetError::Parse { path: file.clone(), line: 2, column: 5, message: "invalid number 'zero' in 'v'".to_string() }
+        );
+        // La columna apunta al índice dentro de la esquina
+        assert_eq!(
+            load("v 0 0 0\nf 1 1/2 1\n"),
+            AssetError::IndexOutOfRange { path: file.clone(), line: 2, column: 7, kind: "texcoord", index: 2, count: 0 }
+        );
+        assert_eq!(
+            load("v 0 0 0\n\nf 1 0 1\n"),
+            AssetError::IndexOutOfRange { path: file.clone(), line: 3, column: 5, kind: "vertex", index: 0, count: 1 }
+        );
+        assert!(load("v 0 0 0\nf 1 2 3\n").to_string().ends_with(":2:5: vertex index 2 is out of range (valid: 1 to 1 or -1 to -1)"));
+        assert!(load("v 0 0 0\nvt 0 0\nf 1/1 1 1\n").to_string().contains(":3:1: some face vertices have a texcoord"));
+        assert!(load("v 0 0\n").to_string().contains(":1:6: 'v' needs at least 3 values"));
+        assert_eq!(load("# solo vértices\nv 0 0 0\n"), AssetError::EmptyMesh { path: file });
+        assert!(matches!(ObjModel::load("no_existe.obj"), Err(AssetError::Io { .. })));
+
+        // Una biblioteca de materiales que no existe solo produce un aviso
+        std::fs::write(&path, "mtllib no_existe.mtl\nv 0 0 0\nf 1 1 1\n").unwrap();
+        let model = ObjModel::load(&path.to_string_lossy()).unwrap();
+        assert_eq!(model.warnings.len(), 1);
+        assert!(model.warnings[0].contains(":1: material library"));
+    }
+
+    #[test]
+    fn test_bundled_sphere_loads_without_warnings() {
+        // Exportada por Blender: mtllib sin materiales y `usemtl None`
+        let model = ObjModel::load("assets/sphere.obj").unwrap();
+        assert!(model.warnings.is_empty(), "{:?}", model.warnings);
+        assert!(model.materials.is_empty());
+        assert!(model.submeshes.iter().all(|submesh| submesh.material.is_none()));
+    }
+}
@@ -0,0 +1,255 @@
+//! Keplerian orbits - Classical orbital elements and Kepler's equation
+//!
+//! Elements are expressed in a reference frame whose X/Y plane is the
+//! ecliptic and whose Z axis points "north". The renderer uses Y as up, so
+//! reference coordinates map to world coordinates as X→x, Y→z, Z→y. With
+//! zero inclination and eccentricity this reproduces the old circular
+//! orbits in the XZ plane.
+
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+const KEPLER_TOLERANCE: f32 = 1e-6;
+const KEPLER_MAX_ITERATIONS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitalElements {
+    /// Semi-major axis (a), world units
+    pub semi_major_axis: f32,
+    /// Eccentricity (e), 0 <= e < 1
+    pub eccentricity: f32,
+    /// Inclination (i) against the reference plane, radians
+    pub inclination: f32,
+    /// Longitude of the ascending node (Ω), radians
+    pub longitude_of_ascending_node: f32,
+    /// Argument of periapsis (ω), radians
+    pub argument_of_periapsis: f32,
+    /// Mean anomaly at epoch (M0), radians
+    pub mean_anomaly_at_epoch: f32,
+    /// Mean motion (n), radians per time unit
+    pub mean_motion: f32,
+}
+
+impl OrbitalElements {
+    /// Circular orbit in the reference plane starting at `mean_anomaly`
+    #[cfg(test)]
+    pub fn circular(radius: f32, mean_motion: f32, mean_anomaly: f32) -> Self {
+        OrbitalElements {
+            semi_major_axis: radius,
+            eccentricity: 0.0,
+            inclination: 0.0,
+            longitude_of_ascending_node: 0.0,
+            argument_of_periapsis: 0.0,
+            mean_anomaly_at_epoch: mean_anomaly,
+            mean_motion,
+        }
+    }
+
+    pub fn semi_minor_axis(&self) -> f32 {
+        self.semi_major_axis * (1.0 - self.eccentricity * self.eccentricity).sqrt()
+    }
+
+    /// Closest distance to the focus
+    pub fn periapsis(&self) -> f32 {
+        self.semi_major_axis * (1.0 - self.eccentricity)
+    }
+
+    /// Farthest distance from the focus
+    pub fn apoapsis(&self) -> f32 {
+        self.semi_major_axis * (1.0 + self.eccentricity)
+    }
+
+    /// Mean anomaly `time` units after epoch, wrapped to [0, 2π). Computed
+    /// in f64 so it stays accurate far from the epoch.
+    pub fn mean_anomaly_at(&self, time: f64) -> f32 {
+        let mean_anomaly = self.mean_anomaly_at_epoch as f64 + self.mean_motion as f64 * time;
+        mean_anomaly.rem_euclid(std::f64::consts::TAU) as f32
+    }
+
+    /// Eccentric anomaly for a mean anomaly, solving M = E - e·sin(E)
+    pub fn eccentric_anomaly(&self, mean_anomaly: f32) -> f32 {
+        solve_kepler(mean_anomaly, self.eccentricity)
+    }
+
+    /// Position relative to the focus for a mean anomaly
+    pub fn position_at_mean_anomaly(&self, mean_anomaly: f32) -> Vector3 {
+        self.position_at_eccentric_anomaly(self.eccentric_anomaly(mean_anomaly))
+    }
+
+    /// Position relative to the focus for an eccentric anomaly
+    pub fn position_at_eccentric_anomaly(&self, eccentric_anomaly: f32) -> Vector3 {
+        // Perifocal frame: periapsis along +x, motion towards +y
+        let x = self.semi_major_axis * (eccentric_anomaly.cos() - self.eccentricity);
+        let y = self.semi_minor_axis() * eccentric_anomaly.sin();
+        self.perifocal_to_world(x, y)
+    }
+
+    /// Position relative to the focus `time` units after epoch
+    pub fn position_at(&self, time: f64) -> Vector3 {
+        self.position_at_mean_anomaly(self.mean_anomaly_at(time))
+    }
+
+    /// Velocity relative to the focus `time` units after epoch, following
+    /// `mean_motion` (the time derivative of `position_at`)
+    pub fn velocity_at(&self, time: f64) -> Vector3 {
+        let a = self.semi_major_axis;
+        let mu = self.mean_motion * self.mean_motion * a * a * a;
+        self.velocity_at_mean_anomaly(self.mean_anomaly_at(time), mu)
+    }
+
+    /// Velocity relative to the focus for a mean anomaly under a real
+    /// gravitational parameter `mu` = G·(M + m). Used to start N-body runs;
+    /// the speed follows from `mu`, not from `mean_motion`.
+    pub fn velocity_at_mean_anomaly(&self, mean_anomaly: f32, mu: f32) -> Vector3 {
+        let eccentric_anomaly = self.eccentric_anomaly(mean_anomaly);
+        let a = self.semi_major_axis;
+        let physical_mean_motion = (mu / (a * a * a)).sqrt();
+        let rate = physical_mean_motion / (1.0 - self.eccentricity * eccentric_anomaly.cos());
+
+        let x = -a * eccentric_anomaly.sin() * rate;
+        let y = self.semi_minor_axis() * eccentric_anomaly.cos() * rate;
+        self.perifocal_to_world(x, y)
+    }
+
+    /// Points along the full ellipse, relative to the focus. Sampled
+    /// uniformly in eccentric anomaly so the curve stays smooth near periapsis.
+    pub fn orbit_points(&self, num_points: usize) -> Vec<Vector3> {
+        (0..num_points)
+            .map(|i| {
+                let eccentric_anomaly = (i as f32 / num_points as f32) * 2.0 * PI;
+                self.position_at_eccentric_anomaly(eccentric_anomaly)
+            })
+            .collect()
+    }
+
+    /// Rotate a perifocal vector by ω, i and Ω, then map to world axes
+    fn perifocal_to_world(&self, x: f32, y: f32) -> Vector3 {
+        let (sin_o, cos_o) = self.longitude_of_ascending_node.sin_cos();
+        let (sin_w, cos_w) = self.argument_of_periapsis.sin_cos();
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+
+        let ref_x = x * (cos_o * cos_w - sin_o * sin_w * cos_i) - y * (cos_o * sin_w + sin_o * cos_w * cos_i);
+        let ref_y = x * (sin_o * cos_w + cos_o * sin_w * cos_i) + y * (cos_o * cos_w * cos_i - sin_o * sin_w);
+        let ref_z = x * (sin_w * sin_i) + y * (cos_w * sin_i);
+
+        Vector3::new(ref_x, ref_z, ref_y)
+    }
+}
+
+/// Solve Kepler's equation M = E - e·sin(E) for E with Newton's method
+pub fn solve_kepler(mean_anomaly: f32, eccentricity: f32) -> f32 {
+    let m = mean_anomaly.rem_euclid(2.0 * PI);
+    if eccentricity == 0.0 {
+        return m;
+    }
+
+    // For very eccentric orbits E = π converges more reliably than E = M
+    let mut e_anomaly = if eccentricity > 0.8 { PI } else { m };
+    for _ in 0..KEPLER_MAX_ITERATIONS {
+        let f = e_anomaly - eccentricity * e_anomaly.sin() - m;
+        let f_prime = 1.0 - eccentricity * e_anomaly.cos();
+        let delta = f / f_prime;
+        e_anomaly -= delta;
+        if delta.abs() < KEPLER_TOLERANCE {
+            break;
+        }
+    }
+    e_anomaly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn length(v: Vector3) -> f32 {
+        (v.x * v.x + v.y * v.y + v.z * v.z).sqrt()
+    }
+
+    #[test]
+    fn test_circular_orbit_matches_flat_circle() {
+        let orbit = OrbitalElements::circular(50.0, 1.0, 0.0);
+        let angle = 1.2_f32;
+        let p = orbit.position_at_mean_anomaly(angle);
+        assert!((p.x - 50.0 * angle.cos()).abs() < 1e-3);
+        assert!((p.z - 50.0 * angle.sin()).abs() < 1e-3);
+        assert!(p.y.abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_kepler_solution_satisfies_equation() {
+        for &e in &[0.0, 0.1, 0.5, 0.9, 0.97] {
+            for i in 0..16 {
+                let m = i as f32 * 0.4;
+                let big_e = solve_kepler(m, e);
+                let residual = big_e - e * big_e.sin() - m.rem_euclid(2.0 * PI);
+                assert!(residual.abs() < 1e-4, "e = {}, M = {}, residual = {}", e, m, residual);
+            }
+        }
+    }
+
+    #[test]
+    fn test_periapsis_and_apoapsis_distances() {
+        let mut orbit = OrbitalElements::circular(100.0, 1.0, 0.0);
+        orbit.eccentricity = 0.3;
+        orbit.argument_of_periapsis = 0.7;
+        orbit.inclination = 0.4;
+
+        assert!((length(orbit.position_at_mean_anomaly(0.0)) - 70.0).abs() < 1e-3);
+        assert!((length(orbit.position_at_mean_anomaly(PI)) - 130.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_orbit_points_trace_ellipse() {
+        let mut orbit = OrbitalElements::circular(80.0, 1.0, 0.0);
+        orbit.eccentricity = 0.5;
+        orbit.longitude_of_ascending_node = 1.0;
+        orbit.inclination = 0.3;
+
+        // The empty focus sits 2·a·e away from the occupied one, on the apsis line
+        let periapsis_dir = orbit.position_at_mean_anomaly(0.0) / orbit.periapsis();
+        let other_focus = periapsis_dir * (-2.0 * orbit.semi_major_axis * orbit.eccentricity);
+
+        for p in orbit.orbit_points(64) {
+            let sum = length(p) + length(p - other_focus);
+            assert!((sum - 2.0 * orbit.semi_major_axis).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_velocity_follows_vis_viva() {
+        let mut orbit = OrbitalElements::circular(60.0, 1.0, 0.0);
+        orbit.eccentricity = 0.4;
+        orbit.inclination = 0.5;
+        let mu = 5000.0;
+
+        for &m in &[0.0, 1.0, 2.5, 4.0] {
+            let r = length(orbit.position_at_mean_anomaly(m));
+            let speed = length(orbit.velocity_at_mean_anomaly(m, mu));
+            let expected = (mu * (2.0 / r - 1.0 / orbit.semi_major_axis)).sqrt();
+            assert!((speed - expected).abs() < 1e-2, "M = {}: {} vs {}", m, speed, expected);
+        }
+    }
+
+    #[test]
+    fn test_velocity_at_is_derivative_of_position_at() {
+        let mut orbit = OrbitalElements::circular(60.0, 0.8, 0.3);
+        orbit.eccentricity = 0.4;
+        orbit.inclination = 0.5;
+        let h = 1e-3;
+
+        for &t in &[0.0, 1.0, 2.5, 4.0] {
+            let numeric = (orbit.position_at(t + h) - orbit.position_at(t - h)) / (2.0 * h as f32);
+            let analytic = orbit.velocity_at(t);
+            assert!(length(numeric - analytic) < 0.05, "t = {}: {:?} vs {:?}", t, numeric, analytic);
+        }
+    }
+
+    #[test]
+    fn test_inclination_tilts_orbit_out_of_plane() {
+        let mut orbit = OrbitalElements::circular(10.0, 1.0, 0.0);
+        orbit.inclination = PI / 2.0;
+        let p = orbit.position_at_mean_anomaly(PI / 2.0);
+        assert!((p.y - 10.0).abs() < 1e-3);
+        assert!(p.z.abs() < 1e-3);
+    }
+}
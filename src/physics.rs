@@ -0,0 +1,276 @@
+//! N-body physics - Mutual gravity integrated with Velocity Verlet
+//!
+//! Works on a flat list of `PointMass` so it doesn't depend on how the
+//! solar system nests moons inside planets. `SolarSystem` copies body
+//! state in, advances it with fixed substeps and copies it back.
+
+use raylib::prelude::*;
+
+/// How `SolarSystem::update` moves bodies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationMode {
+    /// Bodies follow their Keplerian elements exactly
+    Kinematic,
+    /// Bodies move under mutual gravity
+    NBody,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PhysicsConfig {
+    /// Gravitational constant (G) in scene units
+    pub gravitational_constant: f32,
+    /// Fixed integration step, independent of the frame dt
+    pub substep: f32,
+    /// Softening length; avoids infinite forces on close encounters
+    pub softening: f32,
+    /// Upper bound of substeps per update so a long frame can't stall the app
+    pub max_substeps_per_update: u32,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        PhysicsConfig {
+            gravitational_constant: 1.0,
+            substep: 1.0 / 480.0,
+            softening: 1.0,
+            max_substeps_per_update: 2000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointMass {
+    pub position: Vector3,
+    pub velocity: Vector3,
+    pub mass: f32,
+}
+
+/// Conserved quantities of the system; useful to check integrator stability
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Diagnostics {
+    pub kinetic_energy: f32,
+    pub potential_energy: f32,
+    pub total_energy: f32,
+    pub linear_momentum: Vector3,
+    pub angular_momentum: Vector3,
+}
+
+fn cross(a: Vector3, b: Vector3) -> Vector3 {
+    Vector3::new(
+        a.y * b.z - a.z * b.y,
+        a.z * b.x - a.x * b.z,
+        a.x * b.y - a.y * b.x,
+    )
+}
+
+fn length_squared(v: Vector3) -> f32 {
+    v.x * v.x + v.y * v.y + v.z * v.z
+}
+
+/// Gravitational acceleration on every body
+pub fn compute_accelerations(bodies: &[PointMass], config: &PhysicsConfig) -> Vec<Vector3> {
+    let mut accelerations = vec![Vector3::zero(); bodies.len()];
+    let softening_sq = config.softening * config.softening;
+
+    // Each pair once; equal and opposite forces keep momentum exact
+    for i in 0..bodies.len() {
+        for j in (i + 1)..bodies.len() {
+            let offset = bodies[j].position - bodies[i].position;
+            let distance_sq = length_squared(offset) + softening_sq;
+            let inv_distance_cubed = 1.0 / (distance_sq * distance_sq.sqrt());
+            let direction = offset * (config.gravitational_constant * inv_distance_cubed);
+
+            accelerations[i] += direction * bodies[j].mass;
+            accelerations[j] -= direction * bodies[i].mass;
+        }
+    }
+
+    accelerations
+}
+
+/// One Velocity Verlet (kick-drift-kick) step of length `h`.
+/// `accelerations` must hold the accelerations at the current positions and
+/// is updated to the accelerations at the new positions.
+pub fn velocity_verlet_step(
+    bodies: &mut [PointMass],
+    accelerations: &mut Vec<Vector3>,
+    h: f32,
+    config: &PhysicsConfig,
+) {
+    for (body, acceleration) in bodies.iter_mut().zip(accelerations.iter()) {
+        body.velocity += *acceleration * (0.5 * h);
+        body.position += body.velocity * h;
+    }
+
+    *accelerations = compute_accelerations(bodies, config);
+
+    for (body, acceleration) in bodies.iter_mut().zip(accelerations.iter()) {
+        body.velocity += *acceleration * (0.5 * h);
+    }
+}
+
+pub fn diagnostics(bodies: &[PointMass], config: &PhysicsConfig) -> Diagnostics {
+    let mut kinetic_energy = 0.0;
+    let mut potential_energy = 0.0;
+    let mut linear_momentum = Vector3::zero();
+    let mut angular_momentum = Vector3::zero();
+    let softening_sq = config.softening * config.softening;
+
+    for (i, body) in bodies.iter().enumerate() {
+        kinetic_energy += 0.5 * body.mass * length_squared(body.velocity);
+        linear_momentum += body.velocity * body.mass;
+        angular_momentum += cross(body.position, body.velocity * body.mass);
+
+        for other in &bodies[(i + 1)..] {
+            let distance = (length_squared(other.position - body.position) + softening_sq).sqrt();
+            potential_energy -= config.gravitational_constant * body.mass * other.mass / distance;
+        }
+    }
+
+    Diagnostics {
+        kinetic_energy,
+        potential_energy,
+        total_energy: kinetic_energy + potential_energy,
+        linear_momentum,
+        angular_momentum,
+    }
+}
+
+/// Fixed-step integrator that consumes arbitrary frame times
+#[derive(Debug, Clone, Default)]
+pub struct Integrator {
+    accumulator: f32,
+    accelerations: Vec<Vector3>,
+}
+
+impl Integrator {
+    /// Forget cached accelerations and leftover time (call after bodies
+    /// are moved by something other than the integrator)
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+        self.accelerations.clear();
+    }
+
+    /// Advance by `dt` using whole substeps; the remainder carries over to
+    /// the next call. Negative `dt` integrates backwards. Returns the part
+    /// of the backlog dropped when `max_substeps_per_update` is hit (0 if
+    /// all of `dt` was taken), with the sign of `dt`.
+    pub fn advance(&mut self, bodies: &mut [PointMass], dt: f32, config: &PhysicsConfig) -> f32 {
+        if self.accelerations.len() != bodies.len() {
+            self.accelerations = compute_accelerations(bodies, config);
+        }
+
+        self.accumulator += dt;
+        let mut steps = 0;
+        while self.accumulator.abs() >= config.substep && steps < config.max_substeps_per_update {
+            let h = config.substep.copysign(self.accumulator);
+            velocity_verlet_step(bodies, &mut self.accelerations, h, config);
+            self.accumulator -= h;
+            steps += 1;
+        }
+
+        // Drop the backlog instead of spiralling when the cap is hit
+        let backlog = self.accumulator;
+        if steps == config.max_substeps_per_update {
+            self.accumulator = self.accumulator.clamp(-config.substep, config.substep);
+        }
+        backlog - self.accumulator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Light body on a circular orbit around a heavy one, total momentum zero
+    fn two_body_system(config: &PhysicsConfig) -> Vec<PointMass> {
+        let (big, small, radius) = (1000.0, 1.0, 20.0);
+        let speed = (config.gravitational_constant * (big + small) / radius).sqrt();
+        vec![
+            PointMass {
+                position: Vector3::zero(),
+                velocity: Vector3::new(0.0, 0.0, -speed * small / (big + small)),
+                mass: big,
+            },
+            PointMass {
+                position: Vector3::new(radius, 0.0, 0.0),
+                velocity: Vector3::new(0.0, 0.0, speed * big / (big + small)),
+                mass: small,
+            },
+        ]
+    }
+
+    fn config() -> PhysicsConfig {
+        // Power-of-two step so frame times split into whole substeps exactly
+        PhysicsConfig {
+            softening: 0.0,
+            substep: 1.0 / 64.0,
+            ..PhysicsConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_energy_and_momentum_are_conserved() {
+        let config = config();
+        let mut bodies = two_body_system(&config);
+        let start = diagnostics(&bodies, &config);
+
+        // Almost three full orbits
+        let mut integrator = Integrator::default();
+        for _ in 0..100 {
+            integrator.advance(&mut bodies, 0.5, &config);
+        }
+        let end = diagnostics(&bodies, &config);
+
+        let energy_drift = ((end.total_energy - start.total_energy) / start.total_energy).abs();
+        assert!(energy_drift < 1e-3, "energy drift {}", energy_drift);
+        assert!(length_squared(end.linear_momentum - start.linear_momentum) < 1e-6);
+        assert!((end.angular_momentum.y - start.angular_momentum.y).abs() / start.angular_momentum.y.abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_circular_orbit_keeps_its_radius() {
+        let config = config();
+        let mut bodies = two_body_system(&config);
+        let mut integrator = Integrator::default();
+
+        for _ in 0..50 {
+            integrator.advance(&mut bodies, 0.3, &config);
+            let separation = length_squared(bodies[1].position - bodies[0].position).sqrt();
+            assert!((separation - 20.0).abs() < 0.05, "separation {}", separation);
+        }
+    }
+
+    #[test]
+    fn test_result_does_not_depend_on_frame_dt() {
+        let config = config();
+        let mut coarse = two_body_system(&config);
+        let mut fine = coarse.clone();
+
+        let mut coarse_integrator = Integrator::default();
+        let mut fine_integrator = Integrator::default();
+        coarse_integrator.advance(&mut coarse, 1.0, &config);
+        for _ in 0..8 {
+            fine_integrator.advance(&mut fine, 0.125, &config);
+        }
+
+        for (a, b) in coarse.iter().zip(fine.iter()) {
+            assert!(length_squared(a.position - b.position) < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_backwards_integration_returns_to_start() {
+        let config = config();
+        let start = two_body_system(&config);
+        let mut bodies = start.clone();
+        let mut integrator = Integrator::default();
+
+        integrator.advance(&mut bodies, 2.0, &config);
+        integrator.advance(&mut bodies, -2.0, &config);
+
+        for (a, b) in start.iter().zip(bodies.iter()) {
+            assert!(length_squared(a.position - b.position) < 1e-4);
+        }
+    }
+}
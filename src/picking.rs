@@ -0,0 +1,288 @@
+//! Picking - Elegir cuerpos con el mouse
+//!
+//! Cada punto de la pantalla corresponde a un rayo en world space: se deshace
+//! la cadena viewport · proyección · vista con su inversa, en el plano cercano
+//! (z = -1 en NDC) y en el lejano (z = 1). El cuerpo elegido es el primero que
+//! atraviesa el rayo, lunas incluidas; los baricentros no se pueden elegir.
+//!
+//! Los cuerpos que ocupan pocos píxeles se pueden elegir pasando cerca: el
+//! rayo tiene un margen angular de `PICK_TOLERANCE_PIXELS`.
+//!
+//! La malla de esfera no mide 1: cada cuerpo se dibuja con radio
+//! `body.radius * mesh_radius` (`LodChain::bounding_radius`), y es ese disco
+//! el que se elige y se resalta.
+//!
+//! El botón izquierdo también gira la cámara al arrastrar, así que un clic
+//! cuenta al soltar el botón y solo si el cursor no se movió más de
+//! `CLICK_MAX_DRAG_PIXELS` desde que se apretó.
+
+use raylib::prelude::*;
+
+use crate::celestial_body::CelestialBody;
+use crate::matrix::Matrix;
+use crate::scene_graph::NodeId;
+use crate::solar_system::SolarSystem;
+
+/// Distancia en píxeles a la que todavía se elige un cuerpo chico
+pub const PICK_TOLERANCE_PIXELS: f32 = 4.0;
+
+/// Un arrastre más largo que esto gira la cámara en lugar de hacer clic
+pub const CLICK_MAX_DRAG_PIXELS: f32 = 4.0;
+
+/// Dos clics sobre el mismo cuerpo a menos de este tiempo son un doble clic
+pub const DOUBLE_CLICK_SECONDS: f64 = 0.4;
+
+/// Rayo desde la cámara a través de un punto de la pantalla
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    /// Punto en el plano cercano
+    pub origin: Vector3,
+    /// Normalizada
+    pub direction: Vector3,
+    /// Margen angular (radianes) equivalente a `PICK_TOLERANCE_PIXELS`
+    pub spread: f32,
+}
+
+impl Ray {
+    /// Rayo que pasa por el punto (x, y) de la pantalla, en píxeles
+    pub fn from_screen(x: f32, y: f32, view: &Matrix, projection: &Matrix, viewport: &Matrix) -> Option<Ray> {
+        let inverse = viewport.multiply(projection).multiply(view).inverse()?;
+        let through = |x: f32, y: f32| {
+            let near = inverse.transform_point(Vector3::new(x, y, -1.0));
+            let far = inverse.transform_point(Vector3::new(x, y, 1.0));
+            (near, far - near)
+        };
+
+        let (origin, direction) = through(x, y);
+        let (_, beside) = through(x + PICK_TOLERANCE_PIXELS, y);
+        if direction.length() <= 0.0 || beside.length() <= 0.0 {
+            return None;
+        }
+        let direction = direction.normalized();
+        let spread = direction.dot(beside.normalized()).clamp(-1.0, 1.0).acos();
+        Some(Ray { origin, direction, spread })
+    }
+
+    /// Distancia a lo largo del rayo hasta `body`, si lo toca o pasa dentro
+    /// del margen
+    pub fn hit_distance(&self, body: &CelestialBody, mesh_radius: f32) -> Option<f32> {
+        if let Some(distance) = body.intersect_ray(self.origin, self.direction, mesh_radius) {
+            return Some(distance);
+        }
+
+        // Punto del rayo más cercano al centro del cuerpo
+        let to_body = body.position - self.origin;
+        let along = to_body.dot(self.direction);
+        if along <= 0.0 {
+            return None;
+        }
+        let miss = (to_body - self.direction * along).length();
+        (miss - body.radius * mesh_radius <= along * self.spread.tan()).then_some(along)
+    }
+}
+
+/// Nodo del cuerpo más cercano que toca el rayo
+pub fn pick(solar_system: &SolarSystem, ray: &Ray, mesh_radius: f32) -> Option<NodeId> {
+    solar_system
+        .graph
+        .iter()
+        .filter(|node| !node.body.is_barycenter())
+        .filter_map(|node| ray.hit_distance(&node.body, mesh_radius).map(|distance| (node.id, distance)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(id, _)| id)
+}
+
+/// Centro y radio en píxeles del disco de `body` en pantalla, o `None` si
+/// está detrás de la cámara
+pub fn screen_circle(
+    body: &CelestialBody,
+    mesh_radius: f32,
+    view: &Matrix,
+    projection: &Matrix,
+    viewport: &Matrix,
+) -> Option<(Vector2, f32)> {
+    let center = view.transform_point(body.position);
+    if center.z >= 0.0 {
+        return None;
+    }
+    let to_screen = viewport.multiply(projection);
+    let middle = to_screen.transform_point(center);
+    let edge = to_screen.transform_point(center + Vector3::new(0.0, body.radius * mesh_radius, 0.0));
+    Some((Vector2::new(middle.x, middle.y), (edge.y - middle.y).abs()))
+}
+
+/// Cuerpo bajo el cursor, cuerpo elegido y el último clic (para el doble clic)
+#[derive(Debug, Default)]
+pub struct PickState {
+    pub hovered: Option<NodeId>,
+    pub selected: Option<NodeId>,
+    last_click: Option<(NodeId, f64)>,
+    /// Dónde se apretó el botón, mientras sigue apretado
+    press: Option<Vector2>,
+}
+
+impl PickState {
+    /// Sigue el botón del mouse y dice si este cuadro terminó un clic: se
+    /// soltó el botón sin haber arrastrado el cursor desde que se apretó
+    pub fn click(&mut self, mouse: Vector2, pressed: bool, released: bool) -> bool {
+        if pressed {
+            self.press = Some(mouse);
+        }
+        if !released {
+            return false;
+        }
+        self.press
+            .take()
+            .is_some_and(|press| (mouse - press).length() <= CLICK_MAX_DRAG_PIXELS)
+    }
+
+    /// Registra el cuerpo bajo el cursor y, si hubo clic, lo elige (un clic
+    /// en el vacío deja de elegir). `now` en segundos. Devuelve el cuerpo al
+    /// que hay que ir cuando el clic completa un doble clic.
+    pub fn update(&mut self, hit: Option<NodeId>, clicked: bool, now: f64) -> Option<NodeId> {
+        self.hovered = hit;
+        if !clicked {
+            return None;
+        }
+
+        self.selected = hit;
+        let double_click = match (hit, self.last_click) {
+            (Some(id), Some((last, time))) => id == last && now - time <= DOUBLE_CLICK_SECONDS,
+            _ => false,
+        };
+        // Un tercer clic empieza otro doble clic
+        self.last_click = if double_click { None } else { hit.map(|id| (id, now)) };
+        if double_click {
+            hit
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::{create_projection_matrix, create_view_matrix, create_viewport_matrix};
+    use crate::renderer::PROCEDURAL_SPHERE_RADIUS;
+
+    const WIDTH: f32 = 1200.0;
+    const HEIGHT: f32 = 800.0;
+    /// Radio de la icosfera procedural: los cuerpos se dibujan a la mitad de `radius`
+    const MESH_RADIUS: f32 = PROCEDURAL_SPHERE_RADIUS;
+
+    fn matrices(eye: Vector3, target: Vector3) -> (Matrix, Matrix, Matrix) {
+        (
+            create_view_matrix(eye, target, Vector3::new(0.0, 1.0, 0.0)),
+            create_projection_matrix(std::f32::consts::PI / 4.0, WIDTH / HEIGHT, 0.5, 1500.0),
+            create_viewport_matrix(0.0, 0.0, WIDTH, HEIGHT),
+        )
+    }
+
+    #[test]
+    fn test_center_ray_looks_at_the_target() {
+        let (eye, target) = (Vector3::new(0.0, 50.0, 100.0), Vector3::zero());
+        let (view, projection, viewport) = matrices(eye, target);
+        let ray = Ray::from_screen(WIDTH / 2.0, HEIGHT / 2.0, &view, &projection, &viewport).unwrap();
+
+        let expected = (target - eye).normalized();
+        assert!((ray.direction - expected).length() < 1e-3);
+        assert!((ray.origin - eye).length() < 1.0);
+        assert!(ray.spread > 0.0 && ray.spread < 0.01);
+    }
+
+    #[test]
+    fn test_picks_the_body_under_the_cursor_including_moons() {
+        let system = SolarSystem::new();
+        let moon = system.graph.iter().find(|node| node.body.body_type == crate::celestial_body::CelestialBodyType::Moon).unwrap();
+
+        // Cámara mirando a la luna desde cerca: el centro de la pantalla la elige
+        let eye = moon.body.position + Vector3::new(0.0, 2.0, 10.0);
+        let (view, projection, viewport) = matrices(eye, moon.body.position);
+        let ray = Ray::from_screen(WIDTH / 2.0, HEIGHT / 2.0, &view, &projection, &viewport).unwrap();
+        assert_eq!(pick(&system, &ray, MESH_RADIUS), Some(moon.id));
+
+        // Sobre su disco en pantalla también, y lejos de él no
+        let (center, radius) = screen_circle(&moon.body, MESH_RADIUS, &view, &projection, &viewport).unwrap();
+        let edge = Ray::from_screen(center.x + radius * 0.9, center.y, &view, &projection, &viewport).unwrap();
+        assert_eq!(pick(&system, &edge, MESH_RADIUS), Some(moon.id));
+        let corner = Ray::from_screen(5.0, 5.0, &view, &projection, &viewport).unwrap();
+        assert_ne!(pick(&system, &corner, MESH_RADIUS), Some(moon.id));
+    }
+
+    #[test]
+    fn test_only_the_drawn_disc_is_picked() {
+        let system = SolarSystem::new();
+        let moon = system.graph.iter().find(|node| node.body.body_type == crate::celestial_body::CelestialBodyType::Moon).unwrap();
+        let eye = moon.body.position + Vector3::new(0.0, 0.0, 10.0);
+        let (view, projection, viewport) = matrices(eye, moon.body.position);
+
+        // Entre el borde del disco dibujado (más el margen) y `body.radius` no hay nada
+        let (center, drawn) = screen_circle(&moon.body, MESH_RADIUS, &view, &projection, &viewport).unwrap();
+        let (_, full) = screen_circle(&moon.body, 1.0, &view, &projection, &viewport).unwrap();
+        let x = center.x + drawn + 2.0 * PICK_TOLERANCE_PIXELS;
+        assert!(x < center.x + full);
+
+        let inside = Ray::from_screen(center.x + drawn - 1.0, center.y, &view, &projection, &viewport).unwrap();
+        assert_eq!(pick(&system, &inside, MESH_RADIUS), Some(moon.id));
+        let outside = Ray::from_screen(x, center.y, &view, &projection, &viewport).unwrap();
+        assert_ne!(pick(&system, &outside, MESH_RADIUS), Some(moon.id));
+    }
+
+    #[test]
+    fn test_nearest_body_wins() {
+        let system = SolarSystem::new();
+        let star = system.graph.find(&system.stars()[0].name).unwrap();
+        let planet = system.graph.find(&system.planets()[0].name).unwrap();
+
+        // Desde afuera, con el planeta entre la cámara y la estrella
+        let planet_position = system.graph.body(planet).position;
+        let eye = planet_position * 2.0;
+        let (view, projection, viewport) = matrices(eye, Vector3::zero());
+        let (center, _) = screen_circle(system.graph.body(planet), MESH_RADIUS, &view, &projection, &viewport).unwrap();
+        let ray = Ray::from_screen(center.x, center.y, &view, &projection, &viewport).unwrap();
+        assert_eq!(pick(&system, &ray, MESH_RADIUS), Some(planet));
+        assert!(ray.hit_distance(system.graph.body(star), MESH_RADIUS).unwrap() > ray.hit_distance(system.graph.body(planet), MESH_RADIUS).unwrap());
+    }
+
+    #[test]
+    fn test_click_selects_and_double_click_warps() {
+        let system = SolarSystem::new();
+        let (a, b) = (system.graph.find(&system.planets()[0].name).unwrap(), system.graph.find(&system.planets()[1].name).unwrap());
+        let mut state = PickState::default();
+
+        assert_eq!(state.update(Some(a), false, 0.0), None);
+        assert_eq!((state.hovered, state.selected), (Some(a), None));
+
+        assert_eq!(state.update(Some(a), true, 1.0), None);
+        assert_eq!(state.selected, Some(a));
+        assert_eq!(state.update(Some(a), true, 1.2), Some(a));
+
+        // Demasiado lento, u otro cuerpo: no es doble clic
+        assert_eq!(state.update(Some(a), true, 2.0), None);
+        assert_eq!(state.update(Some(a), true, 3.0), None);
+        assert_eq!(state.update(Some(b), true, 3.1), None);
+
+        // Clic en el vacío
+        assert_eq!(state.update(None, true, 3.2), None);
+        assert_eq!(state.selected, None);
+    }
+
+    #[test]
+    fn test_dragging_the_camera_is_not_a_click() {
+        let mut state = PickState::default();
+        let at = |x: f32| Vector2::new(x, 100.0);
+
+        // Apretar y soltar casi en el mismo lugar es un clic, al soltar
+        assert!(!state.click(at(10.0), true, false));
+        assert!(!state.click(at(11.0), false, false));
+        assert!(state.click(at(12.0), false, true));
+
+        // Un arrastre para girar la cámara no
+        assert!(!state.click(at(10.0), true, false));
+        assert!(!state.click(at(60.0), false, true));
+
+        // Soltar sin haber apretado (por ejemplo sobre otra ventana) tampoco
+        assert!(!state.click(at(60.0), false, true));
+    }
+}
@@ -149,54 +149,33 @@ pub fn sort_triangles_by_depth(mut triangles: Vec<Triangle>) -> Vec<Triangle> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::varyings::Varyings;
+
+    fn vertex(x: f32, y: f32) -> VertexShaderOutput {
+        VertexShaderOutput {
+            clip_position: Vector4::new(0.0, 0.0, 0.0, 1.0),
+            screen_position: Vector3::new(x, y, 1.0),
+            inv_w: 1.0,
+            varyings: Varyings {
+                world_position: Vector3::zero(),
+                object_position: Vector3::zero(),
+                normal: Vector3::new(0.0, 0.0, 1.0),
+                color: Color::WHITE,
+                uv: Vector2::zero(),
+            },
+        }
+    }
     
     #[test]
     fn test_triangle_backface_culling() {
         // Crear triángulo CCW (frente)
-        let v0 = VertexShaderOutput {
-            screen_position: Vector3::new(0.0, 0.0, 1.0),
-            world_normal: Vector3::new(0.0, 0.0, 1.0),
-            color: Color::WHITE,
-        };
-        let v1 = VertexShaderOutput {
-            screen_position: Vector3::new(1.0, 0.0, 1.0),
-            world_normal: Vector3::new(0.0, 0.0, 1.0),
-            color: Color::WHITE,
-        };
-        let v2 = VertexShaderOutput {
-            screen_position: Vector3::new(0.0, 1.0, 1.0),
-            world_normal: Vector3::new(0.0, 0.0, 1.0),
-            color: Color::WHITE,
-        };
-        
-        let tri = Triangle::new(v0, v1, v2);
+        let tri = Triangle::new(vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(0.0, 1.0));
         assert!(tri.is_front_facing());
     }
     
     #[test]
     fn test_fan_triangulation() {
-        let vertices = vec![
-            VertexShaderOutput {
-                screen_position: Vector3::new(0.0, 0.0, 1.0),
-                world_normal: Vector3::new(0.0, 0.0, 1.0),
-                color: Color::WHITE,
-            },
-            VertexShaderOutput {
-                screen_position: Vector3::new(1.0, 0.0, 1.0),
-                world_normal: Vector3::new(0.0, 0.0, 1.0),
-                color: Color::WHITE,
-            },
-            VertexShaderOutput {
-                screen_position: Vector3::new(1.0, 1.0, 1.0),
-                world_normal: Vector3::new(0.0, 0.0, 1.0),
-                color: Color::WHITE,
-            },
-            VertexShaderOutput {
-                screen_position: Vector3::new(0.0, 1.0, 1.0),
-                world_normal: Vector3::new(0.0, 0.0, 1.0),
-                color: Color::WHITE,
-            },
-        ];
+        let vertices = vec![vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(1.0, 1.0), vertex(0.0, 1.0)];
         
         let triangles = assemble_fan_triangles(&vertices);
         assert_eq!(triangles.len(), 2); // Cuadrado = 2 triángulos
@@ -1,11 +1,37 @@
 //! Rasterizer - Convierte triángulos en fragmentos (píxeles)
 //! 
 //! Esta es la tercera etapa del pipeline gráfico.
-//! Toma triángulos ensamblados y los convierte en fragmentos individuales
-//! usando coordenadas baricéntricas para interpolación.
+//! Toma triángulos ensamblados y entrega cada píxel cubierto a una función
+//! (el sombreado y la escritura en el framebuffer), sin juntar fragmentos
+//! en memoria.
+//!
+//! Las edge functions se evalúan en punto fijo: los vértices se redondean a
+//! 1/256 de píxel y cada función es un entero que se actualiza sumando una
+//! constante al avanzar un píxel o una fila. La suma es exacta, así que el
+//! resultado de un píxel no depende de dónde empiece el rectángulo que se
+//! rasteriza (un tile o toda la pantalla).
+//!
+//! Los píxeles cuyo centro cae justo sobre una arista siguen la regla
+//! top-left: solo los dibuja el triángulo que tiene esa arista arriba o a la
+//! izquierda. Así dos triángulos que comparten una arista no dibujan dos
+//! veces el mismo píxel (lo que en los anillos y la corona, que se mezclan
+//! con alpha, se vería como una costura más brillante).
+//!
+//! La profundidad se interpola linealmente en pantalla (la Z en NDC ya está
+//! dividida por w); los varyings se interpolan con corrección de perspectiva
+//! usando el 1 / w de cada vértice.
 
-use raylib::prelude::*;
 use crate::primitive_assembly::Triangle;
+use crate::varyings::Varyings;
+
+/// Bits de subpíxel de las coordenadas en punto fijo
+const SUBPIXEL_BITS: u32 = 8;
+const SUBPIXEL_SCALE: f32 = (1 << SUBPIXEL_BITS) as f32;
+/// Coordenada máxima (en píxeles) que se puede pasar a punto fijo sin que
+/// las edge functions desborden un i64. El clipping contra la guard band
+/// deja los vértices por debajo; los triángulos que igual la superan (NaN,
+/// o un viewport de más de 2^15 píxeles) se descartan.
+const MAX_COORDINATE: f32 = (1 << 21) as f32;
 
 /// Representa un fragmento (píxel candidato) generado por el rasterizador
 #[derive(Clone, Copy, Debug)]
@@ -15,245 +41,220 @@ pub struct Fragment {
     pub screen_y: i32,
     /// Profundidad interpolada (para Z-buffer)
     pub depth: f32,
-    /// Normal interpolada del fragmento
-    pub normal: Vector3,
-    /// Color interpolado del fragmento
-    pub color: Color,
+    /// Atributos interpolados con corrección de perspectiva
+    pub varyings: Varyings,
+}
+
+/// Edge function en punto fijo: su valor en un píxel y cuánto cambia al
+/// avanzar un píxel a la derecha o una fila hacia abajo
+#[derive(Clone, Copy)]
+struct Edge {
+    value: i64,
+    step_x: i64,
+    step_y: i64,
+}
+
+impl Edge {
+    /// Arista `a`→`b` evaluada en el centro del píxel (x, y)
+    fn new(a: (i64, i64), b: (i64, i64), x: u32, y: u32) -> Self {
+        let half = 1 << (SUBPIXEL_BITS - 1);
+        let px = ((x as i64) << SUBPIXEL_BITS) + half;
+        let py = ((y as i64) << SUBPIXEL_BITS) + half;
+        Edge {
+            value: (px - a.0) * (b.1 - a.1) - (py - a.1) * (b.0 - a.0),
+            step_x: (b.1 - a.1) << SUBPIXEL_BITS,
+            step_y: -((b.0 - a.0) << SUBPIXEL_BITS),
+        }
+    }
+
+    fn negate(self) -> Self {
+        Edge { value: -self.value, step_x: -self.step_x, step_y: -self.step_y }
+    }
+
+    /// Con adentro ≥ 0, resta 1 a las aristas que no son top-left: un píxel
+    /// sobre ellas (valor 0) queda afuera
+    fn with_fill_rule(self) -> Self {
+        if is_top_left(self.step_x, self.step_y) {
+            self
+        } else {
+            Edge { value: self.value - 1, ..self }
+        }
+    }
+}
+
+/// Una arista es izquierda si adentro crece hacia la derecha, y superior si
+/// es horizontal y adentro crece hacia abajo (la Y de pantalla crece hacia
+/// abajo). `gradient_x`, `gradient_y` es hacia dónde crece la edge function.
+#[inline]
+fn is_top_left<T: PartialOrd + Default>(gradient_x: T, gradient_y: T) -> bool {
+    let zero = T::default();
+    gradient_x > zero || (gradient_x == zero && gradient_y > zero)
 }
 
-/// Rasteriza un triángulo en fragmentos usando coordenadas baricéntricas
+/// Rasteriza los píxeles de un rectángulo (extremos incluidos) cubiertos
+/// por el triángulo y llama a `emit` con el fragmento de cada uno
 /// 
 /// # Algoritmo:
-/// 1. Calcula la bounding box del triángulo
-/// 2. Aplica clipping a los límites de pantalla (frustum culling)
-/// 3. Para cada píxel en la bounding box clipeada:
-///    - Calcula coordenadas baricéntricas (u, v, w)
-///    - Si está dentro del triángulo (u,v,w ≥ 0 y u+v+w=1):
-///      - Interpola atributos (profundidad, normal, color)
-///      - Genera un fragmento
+/// 1. Pasa los vértices a punto fijo y calcula el área (con signo)
+/// 2. Evalúa las tres edge functions en la esquina del rectángulo
+/// 3. Recorre el rectángulo fila por fila sumando los incrementos:
+///    - Si las tres son ≥ 0 el centro del píxel está dentro del triángulo
+///    - Las coordenadas baricéntricas son cada edge function / área
+///    - Interpola la profundidad y los varyings y llama a `emit`
 /// 
 /// # Argumentos
 /// * `triangle` - Triángulo a rasterizar
-/// * `screen_width` - Ancho de la pantalla (para clipping)
-/// * `screen_height` - Alto de la pantalla (para clipping)
-/// 
-/// # Returns
-/// Vector de fragmentos que cubren el triángulo (solo dentro de pantalla)
-pub fn rasterize_triangle(triangle: &Triangle, screen_width: f32, screen_height: f32) -> Vec<Fragment> {
-    let mut fragments = Vec::new();
-    
-    let v0 = &triangle.vertices[0];
-    let v1 = &triangle.vertices[1];
-    let v2 = &triangle.vertices[2];
-    
-    // Calcular bounding box del triángulo
-    let min_x = v0.screen_position.x.min(v1.screen_position.x).min(v2.screen_position.x).floor() as i32;
-    let max_x = v0.screen_position.x.max(v1.screen_position.x).max(v2.screen_position.x).ceil() as i32;
-    let min_y = v0.screen_position.y.min(v1.screen_position.y).min(v2.screen_position.y).floor() as i32;
-    let max_y = v0.screen_position.y.max(v1.screen_position.y).max(v2.screen_position.y).ceil() as i32;
-    
-    // ===== OPTIMIZACIÓN: Frustum Culling de Fragmentos =====
-    // Clipear la bounding box a los límites de pantalla
-    // Esto evita procesar millones de fragmentos fuera de pantalla cuando
-    // un planeta está muy cerca de la cámara
-    let screen_min_x = 0;
-    let screen_max_x = screen_width as i32 - 1;
-    let screen_min_y = 0;
-    let screen_max_y = screen_height as i32 - 1;
-    
-    // Aplicar clipping: solo rasterizar dentro de [0, screen_width) x [0, screen_height)
-    let clipped_min_x = min_x.max(screen_min_x);
-    let clipped_max_x = max_x.min(screen_max_x);
-    let clipped_min_y = min_y.max(screen_min_y);
-    let clipped_max_y = max_y.min(screen_max_y);
-    
-    // Si la bounding box clipeada está completamente fuera de pantalla, retornar vacío
-    if clipped_min_x > clipped_max_x || clipped_min_y > clipped_max_y {
-        return fragments;
+/// * `min_x`, `min_y`, `max_x`, `max_y` - Píxeles a recorrer, normalmente la
+///   bounding box del triángulo recortada a la pantalla o a un tile
+/// * `emit` - Recibe cada fragmento, en orden de filas
+pub fn rasterize_triangle(
+    triangle: &Triangle,
+    min_x: u32,
+    min_y: u32,
+    max_x: u32,
+    max_y: u32,
+    mut emit: impl FnMut(&Fragment),
+) {
+    let [v0, v1, v2] = &triangle.vertices;
+    let positions = [v0, v1, v2].map(|v| (v.screen_position.x, v.screen_position.y));
+    if positions.iter().any(|&(x, y)| !(x.abs() <= MAX_COORDINATE && y.abs() <= MAX_COORDINATE)) {
+        return; // Fuera de rango o NaN
     }
-    
-    // Pre-calcular área total del triángulo (para coordenadas baricéntricas)
-    let total_area = edge_function(
-        v0.screen_position.x, v0.screen_position.y,
-        v1.screen_position.x, v1.screen_position.y,
-        v2.screen_position.x, v2.screen_position.y,
-    );
-    
-    // Si el área es muy pequeña o negativa, el triángulo es degenerado
-    if total_area.abs() < 0.001 {
-        return fragments;
+    let [p0, p1, p2] = positions.map(|(x, y)| to_fixed(x, y));
+
+    // Área con signo (el doble, en unidades de subpíxel al cuadrado)
+    let area = (p2.0 - p0.0) * (p1.1 - p0.1) - (p2.1 - p0.1) * (p1.0 - p0.0);
+    if area == 0 {
+        return; // Triángulo degenerado
     }
-    
-    let inv_total_area = 1.0 / total_area;
-    
-    // Iterar sobre cada píxel en la bounding box CLIPEADA (optimización de frustum culling)
-    // Antes iterábamos sobre toda la bounding box del triángulo, incluso fuera de pantalla
-    // Ahora solo procesamos píxeles dentro de [0, screen_width) x [0, screen_height)
-    for y in clipped_min_y..=clipped_max_y {
-        for x in clipped_min_x..=clipped_max_x {
-            let px = x as f32 + 0.5; // Centro del píxel
-            let py = y as f32 + 0.5;
-            
-            // Calcular coordenadas baricéntricas (w0, w1, w2)
-            let w0 = edge_function(
-                v1.screen_position.x, v1.screen_position.y,
-                v2.screen_position.x, v2.screen_position.y,
-                px, py,
-            ) * inv_total_area;
-            
-            let w1 = edge_function(
-                v2.screen_position.x, v2.screen_position.y,
-                v0.screen_position.x, v0.screen_position.y,
-                px, py,
-            ) * inv_total_area;
-            
-            let w2 = edge_function(
-                v0.screen_position.x, v0.screen_position.y,
-                v1.screen_position.x, v1.screen_position.y,
-                px, py,
-            ) * inv_total_area;
-            
-            // Verificar si el píxel está dentro del triángulo
-            // Todas las coordenadas baricéntricas deben ser ≥ 0
-            if w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0 {
-                // Interpolar profundidad (Z)
-                let depth = w0 * v0.screen_position.z +
-                           w1 * v1.screen_position.z +
-                           w2 * v2.screen_position.z;
-                
-                // Interpolar normal
-                let normal = Vector3::new(
-                    w0 * v0.world_normal.x + w1 * v1.world_normal.x + w2 * v2.world_normal.x,
-                    w0 * v0.world_normal.y + w1 * v1.world_normal.y + w2 * v2.world_normal.y,
-                    w0 * v0.world_normal.z + w1 * v1.world_normal.z + w2 * v2.world_normal.z,
+
+    // Coordenadas baricéntricas: w0 opuesta a v0, etc.
+    let mut edges = [
+        Edge::new(p1, p2, min_x, min_y),
+        Edge::new(p2, p0, min_x, min_y),
+        Edge::new(p0, p1, min_x, min_y),
+    ];
+    // Con cualquier sentido de giro, adentro es ≥ 0
+    if area < 0 {
+        edges = edges.map(Edge::negate);
+    }
+    edges = edges.map(Edge::with_fill_rule);
+    let inv_area = 1.0 / area.abs() as f32;
+
+    for y in min_y..=max_y {
+        let mut row = edges.map(|edge| edge.value);
+        let mut entered = false;
+        for x in min_x..=max_x {
+            if row[0] >= 0 && row[1] >= 0 && row[2] >= 0 {
+                entered = true;
+                let weights = row.map(|value| value as f32 * inv_area);
+
+                // La profundidad ya está dividida por w: es lineal en pantalla
+                let depth = weights[0] * v0.screen_position.z
+                    + weights[1] * v1.screen_position.z
+                    + weights[2] * v2.screen_position.z;
+
+                // Los varyings no: se pesan por el 1 / w de cada vértice
+                let varyings = Varyings::interpolate(
+                    [&v0.varyings, &v1.varyings, &v2.varyings],
+                    weights,
+                    [v0.inv_w, v1.inv_w, v2.inv_w],
                 );
-                
-                // Interpolar color
-                let color = interpolate_color(&v0.color, &v1.color, &v2.color, w0, w1, w2);
-                
-                fragments.push(Fragment {
-                    screen_x: x,
-                    screen_y: y,
+
+                emit(&Fragment {
+                    screen_x: x as i32,
+                    screen_y: y as i32,
                     depth,
-                    normal,
-                    color,
+                    varyings,
                 });
+            } else if entered {
+                break; // El triángulo es convexo: no vuelve a entrar en esta fila
             }
+            for (value, edge) in row.iter_mut().zip(&edges) {
+                *value += edge.step_x;
+            }
+        }
+        for edge in &mut edges {
+            edge.value += edge.step_y;
         }
     }
-    
-    fragments
 }
 
-/// Rasteriza un triángulo con LOD (Level of Detail) - omite píxeles para optimización
-/// 
-/// # Argumentos
-/// * `triangle` - Triángulo a rasterizar
-/// * `skip_factor` - Cuántos píxeles omitir (1=todos, 2=la mitad, 4=un cuarto, etc.)
-/// * `screen_width` - Ancho de la pantalla (para clipping)
-/// * `screen_height` - Alto de la pantalla (para clipping)
-/// 
-/// # Returns
-/// Vector de fragmentos (con menor resolución si skip_factor > 1)
-pub fn rasterize_triangle_lod(
-    triangle: &Triangle, 
-    skip_factor: i32, 
-    screen_width: f32, 
-    screen_height: f32
-) -> Vec<Fragment> {
-    if skip_factor <= 1 {
-        return rasterize_triangle(triangle, screen_width, screen_height);
-    }
-    
+/// Coordenadas de pantalla en punto fijo, redondeadas al subpíxel
+#[inline]
+fn to_fixed(x: f32, y: f32) -> (i64, i64) {
+    ((x * SUBPIXEL_SCALE).round() as i64, (y * SUBPIXEL_SCALE).round() as i64)
+}
+
+/// Versión directa del rasterizer: evalúa `edge_function` en punto flotante
+/// en cada píxel y devuelve todos los fragmentos en un `Vec`. Es la que se
+/// usaba antes de rasterizar en streaming; queda como referencia para los
+/// tests y como línea base del benchmark.
+pub fn rasterize_triangle_reference(triangle: &Triangle, min_x: u32, min_y: u32, max_x: u32, max_y: u32) -> Vec<Fragment> {
     let mut fragments = Vec::new();
+    let [v0, v1, v2] = &triangle.vertices;
     
-    let v0 = &triangle.vertices[0];
-    let v1 = &triangle.vertices[1];
-    let v2 = &triangle.vertices[2];
-    
-    let min_x = v0.screen_position.x.min(v1.screen_position.x).min(v2.screen_position.x).floor() as i32;
-    let max_x = v0.screen_position.x.max(v1.screen_position.x).max(v2.screen_position.x).ceil() as i32;
-    let min_y = v0.screen_position.y.min(v1.screen_position.y).min(v2.screen_position.y).floor() as i32;
-    let max_y = v0.screen_position.y.max(v1.screen_position.y).max(v2.screen_position.y).ceil() as i32;
-    
-    // ===== OPTIMIZACIÓN: Frustum Culling de Fragmentos (versión LOD) =====
-    let screen_min_x = 0;
-    let screen_max_x = screen_width as i32 - 1;
-    let screen_min_y = 0;
-    let screen_max_y = screen_height as i32 - 1;
-    
-    let clipped_min_x = min_x.max(screen_min_x);
-    let clipped_max_x = max_x.min(screen_max_x);
-    let clipped_min_y = min_y.max(screen_min_y);
-    let clipped_max_y = max_y.min(screen_max_y);
-    
-    if clipped_min_x > clipped_max_x || clipped_min_y > clipped_max_y {
-        return fragments;
-    }
-    
+    // Pre-calcular área total del triángulo (para coordenadas baricéntricas)
     let total_area = edge_function(
         v0.screen_position.x, v0.screen_position.y,
         v1.screen_position.x, v1.screen_position.y,
         v2.screen_position.x, v2.screen_position.y,
     );
     
+    // Si el área es muy pequeña, el triángulo es degenerado
     if total_area.abs() < 0.001 {
         return fragments;
     }
     
     let inv_total_area = 1.0 / total_area;
+
+    // Misma regla top-left que `rasterize_triangle`, con el gradiente de cada
+    // coordenada baricéntrica (ya normalizado por el signo del área)
+    let top_left = [(v1, v2), (v2, v0), (v0, v1)].map(|(a, b)| {
+        is_top_left(
+            (b.screen_position.y - a.screen_position.y) * inv_total_area,
+            -(b.screen_position.x - a.screen_position.x) * inv_total_area,
+        )
+    });
+    let outside = |w: f32, top_left: bool| w < 0.0 || (w == 0.0 && !top_left);
     
-    // Iterar con saltos (LOD) usando la bounding box CLIPEADA
-    let mut y = clipped_min_y;
-    while y <= clipped_max_y {
-        let mut x = clipped_min_x;
-        while x <= clipped_max_x {
-            let px = x as f32 + 0.5;
+    for y in min_y as i32..=max_y as i32 {
+        for x in min_x as i32..=max_x as i32 {
+            let px = x as f32 + 0.5; // Centro del píxel
             let py = y as f32 + 0.5;
-            
             let w0 = edge_function(
                 v1.screen_position.x, v1.screen_position.y,
                 v2.screen_position.x, v2.screen_position.y,
                 px, py,
             ) * inv_total_area;
-            
             let w1 = edge_function(
                 v2.screen_position.x, v2.screen_position.y,
                 v0.screen_position.x, v0.screen_position.y,
                 px, py,
             ) * inv_total_area;
-            
             let w2 = edge_function(
                 v0.screen_position.x, v0.screen_position.y,
                 v1.screen_position.x, v1.screen_position.y,
                 px, py,
             ) * inv_total_area;
             
-            if w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0 {
-                let depth = w0 * v0.screen_position.z +
-                           w1 * v1.screen_position.z +
-                           w2 * v2.screen_position.z;
-                
-                let normal = Vector3::new(
-                    w0 * v0.world_normal.x + w1 * v1.world_normal.x + w2 * v2.world_normal.x,
-                    w0 * v0.world_normal.y + w1 * v1.world_normal.y + w2 * v2.world_normal.y,
-                    w0 * v0.world_normal.z + w1 * v1.world_normal.z + w2 * v2.world_normal.z,
-                );
-                
-                let color = interpolate_color(&v0.color, &v1.color, &v2.color, w0, w1, w2);
-                
-                fragments.push(Fragment {
-                    screen_x: x,
-                    screen_y: y,
-                    depth,
-                    normal,
-                    color,
-                });
+            // Todas las coordenadas baricéntricas deben ser ≥ 0 (> 0 en las
+            // aristas que no son top-left)
+            if outside(w0, top_left[0]) || outside(w1, top_left[1]) || outside(w2, top_left[2]) {
+                continue;
             }
             
-            x += skip_factor;
+            fragments.push(Fragment {
+                screen_x: x,
+                screen_y: y,
+                depth: w0 * v0.screen_position.z + w1 * v1.screen_position.z + w2 * v2.screen_position.z,
+                varyings: Varyings::interpolate(
+                    [&v0.varyings, &v1.varyings, &v2.varyings],
+                    [w0, w1, w2],
+                    [v0.inv_w, v1.inv_w, v2.inv_w],
+                ),
+            });
         }
-        y += skip_factor;
     }
     
     fragments
@@ -272,19 +273,11 @@ fn edge_function(ax: f32, ay: f32, bx: f32, by: f32, cx: f32, cy: f32) -> f32 {
     (cx - ax) * (by - ay) - (cy - ay) * (bx - ax)
 }
 
-/// Interpola colores RGB usando coordenadas baricéntricas
-fn interpolate_color(c0: &Color, c1: &Color, c2: &Color, w0: f32, w1: f32, w2: f32) -> Color {
-    let r = (w0 * c0.r as f32 + w1 * c1.r as f32 + w2 * c2.r as f32).clamp(0.0, 255.0) as u8;
-    let g = (w0 * c0.g as f32 + w1 * c1.g as f32 + w2 * c2.g as f32).clamp(0.0, 255.0) as u8;
-    let b = (w0 * c0.b as f32 + w1 * c1.b as f32 + w2 * c2.b as f32).clamp(0.0, 255.0) as u8;
-    let a = (w0 * c0.a as f32 + w1 * c1.a as f32 + w2 * c2.a as f32).clamp(0.0, 255.0) as u8;
-    
-    Color { r, g, b, a }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use raylib::prelude::*;
+    use crate::vertex_shader::VertexShaderOutput;
     
     #[test]
     fn test_edge_function_orientation() {
@@ -294,14 +287,112 @@ mod tests {
         assert!(result > 0.0); // Punto a la izquierda
     }
     
+    fn vertex(x: f32, y: f32, w: f32, u: f32) -> VertexShaderOutput {
+        VertexShaderOutput {
+            clip_position: Vector4::new(x * w, y * w, 0.0, w),
+            screen_position: Vector3::new(x, y, 0.0),
+            inv_w: 1.0 / w,
+            varyings: Varyings {
+                world_position: Vector3::new(u, 0.0, w),
+                object_position: Vector3::zero(),
+                normal: Vector3::new(0.0, 0.0, 1.0),
+                color: Color::new((u * 255.0) as u8, 0, 0, 255),
+                uv: Vector2::new(u, 0.0),
+            },
+        }
+    }
+    
+    fn collect(triangle: &Triangle, max_x: u32, max_y: u32) -> Vec<Fragment> {
+        let mut fragments = Vec::new();
+        rasterize_triangle(triangle, 0, 0, max_x, max_y, |fragment| fragments.push(*fragment));
+        fragments
+    }
+    
     #[test]
-    fn test_color_interpolation() {
-        let white = Color::WHITE;
-        let black = Color::BLACK;
-        let gray = Color::new(128, 128, 128, 255);
+    fn test_varyings_are_perspective_correct() {
+        // Triángulo que se aleja hacia la derecha: w = 1 a la izquierda, w = 3
+        // a la derecha, con u de 0 a 1
+        let triangle = Triangle::new(vertex(0.0, 0.0, 1.0, 0.0), vertex(0.0, 100.0, 1.0, 0.0), vertex(100.0, 0.0, 3.0, 1.0));
+        let fragments = collect(&triangle, 199, 199);
+        let fragment = fragments.iter().find(|f| f.screen_x == 49 && f.screen_y == 0).unwrap();
         
-        // 50% blanco, 50% negro = gris
-        let result = interpolate_color(&white, &black, &black, 0.5, 0.25, 0.25);
-        assert!(result.r > 100 && result.r < 200); // Aproximadamente gris
+        // A mitad de camino en pantalla solo se ha recorrido un cuarto del
+        // triángulo; todos los varyings lo reflejan
+        let expected = 0.25;
+        assert!((fragment.varyings.uv.x - expected).abs() < 0.01, "{}", fragment.varyings.uv.x);
+        assert!((fragment.varyings.world_position.x - expected).abs() < 0.01);
+        assert!((fragment.varyings.color.r as f32 - expected * 255.0).abs() <= 2.0);
+    }
+    
+    #[test]
+    fn test_matches_reference_rasterizer() {
+        // Vértices en múltiplos de 1/4 de píxel: el punto fijo no los mueve y
+        // las edge functions en punto flotante son exactas, así que ambos
+        // rasterizers deben cubrir exactamente los mismos píxeles
+        let triangles = [
+            Triangle::new(vertex(3.25, 2.5, 1.0, 0.0), vertex(90.75, 20.0, 2.0, 1.0), vertex(40.0, 77.5, 1.5, 0.5)),
+            // Sentido de giro opuesto
+            Triangle::new(vertex(3.25, 2.5, 1.0, 0.0), vertex(40.0, 77.5, 1.5, 0.5), vertex(90.75, 20.0, 2.0, 1.0)),
+            // Aristas sobre centros de píxel
+            Triangle::new(vertex(10.5, 10.5, 1.0, 0.0), vertex(30.5, 10.5, 1.0, 1.0), vertex(10.5, 30.5, 1.0, 0.0)),
+        ];
+        for triangle in &triangles {
+            let streamed = collect(triangle, 99, 79);
+            let reference = rasterize_triangle_reference(triangle, 0, 0, 99, 79);
+            assert!(!streamed.is_empty());
+            assert_eq!(streamed.len(), reference.len());
+            for (a, b) in streamed.iter().zip(&reference) {
+                assert_eq!((a.screen_x, a.screen_y), (b.screen_x, b.screen_y));
+                assert!((a.varyings.uv.x - b.varyings.uv.x).abs() < 1e-4);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_shared_edges_are_drawn_once() {
+        // Un cuadrado con las esquinas en centros de píxel partido en dos por
+        // la diagonal, y otro triángulo pegado a su arista derecha: todas las
+        // aristas compartidas pasan por centros de píxel
+        let (a, b, c, d) = ((10.5, 10.5), (30.5, 10.5), (30.5, 30.5), (10.5, 30.5));
+        let e = (50.5, 20.5);
+        let v = |(x, y): (f32, f32)| vertex(x, y, 1.0, 0.0);
+        let triangles = [
+            Triangle::new(v(a), v(b), v(c)),
+            // Sentido de giro opuesto al de los otros
+            Triangle::new(v(a), v(d), v(c)),
+            Triangle::new(v(b), v(e), v(c)),
+        ];
+
+        let mut counts = vec![0u32; 64 * 48];
+        for triangle in &triangles {
+            for fragment in collect(triangle, 63, 47) {
+                counts[fragment.screen_y as usize * 64 + fragment.screen_x as usize] += 1;
+            }
+        }
+        assert!(counts.iter().all(|&count| count <= 1), "píxeles dibujados dos veces");
+        // Sin agujeros: el interior del cuadrado queda cubierto entero
+        for y in 11..30 {
+            for x in 11..30 {
+                assert_eq!(counts[y * 64 + x], 1, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_result_does_not_depend_on_the_rect() {
+        // Los mismos píxeles rasterizados de una vez o en pedazos dan los
+        // mismos fragmentos, bit a bit
+        let triangle = Triangle::new(vertex(3.3, 2.7, 1.0, 0.0), vertex(90.1, 20.9, 2.0, 1.0), vertex(40.6, 77.2, 1.5, 0.5));
+        let whole = collect(&triangle, 99, 79);
+        let mut pieces = Vec::new();
+        for (min_x, max_x) in [(0, 36), (37, 99)] {
+            rasterize_triangle(&triangle, min_x, 0, max_x, 79, |fragment| pieces.push(*fragment));
+        }
+        pieces.sort_by_key(|f| (f.screen_y, f.screen_x));
+        assert_eq!(whole.len(), pieces.len());
+        for (a, b) in whole.iter().zip(&pieces) {
+            assert_eq!((a.screen_x, a.screen_y, a.depth), (b.screen_x, b.screen_y, b.depth));
+            assert_eq!(a.varyings, b.varyings);
+        }
     }
 }
@@ -1,17 +1,99 @@
 #![allow(dead_code)]
 use crate::camera::Camera;
 use crate::celestial_body::CelestialBody;
-use crate::matrix::{create_projection_matrix, create_viewport_matrix, create_translation_matrix, create_scale_matrix, Matrix};
+use crate::matrix::{create_projection_matrix, create_viewport_matrix, Matrix};
+use crate::scene_graph::NodeId;
 use crate::solar_system::SolarSystem;
 use crate::framebuffer::Framebuffer;
-use crate::obj_loader::ObjModel;
+use crate::asset_error::AssetError;
+use crate::clipping::clip_triangle;
+use crate::lod::{screen_radius, LodChain, LodChoice};
+use crate::mesh::Mesh;
+use crate::obj_loader::{ObjModel, Vertex};
 use crate::vertex_shader::{vertex_shader, VertexShaderOutput};
-use crate::fragment_shader::{fragment_shader, LightingConfig};
-use crate::primitive_assembly::Triangle;
-use crate::rasterizer::rasterize_triangle;
-use rand::Rng;
+use crate::fragment_shader::{
+    corona_intensity, fragment_shader_with_shadows, shade_fragment, surface_color, LightingConfig, Material, PointLight,
+};
+use crate::rasterizer::Fragment;
+use crate::tiles::{BlendMode, DrawCommand, DrawList, PixelRect, Primitive};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use raylib::prelude::*;
 use std::f32::consts::PI;
+use std::thread;
+
+/// Subdivisiones angulares del anillo
+const RING_SEGMENTS: usize = 96;
+/// Bandas concéntricas del anillo (cada una con su propio brillo)
+const RING_BANDS: usize = 4;
+/// Radio de las esferas generadas por código: el mismo que el de
+/// sphere.obj (aprox.), para que los cuerpos no cambien de tamaño
+pub const PROCEDURAL_SPHERE_RADIUS: f32 = 0.5;
+/// Campo de visión vertical: 45 grados
+const FOV_Y: f32 = PI / 4.0;
+/// Near plane: los triángulos que lo cruzan se recortan, así que puede estar
+/// cerca de la cámara y se puede volar hasta la superficie de un cuerpo
+const NEAR_PLANE: f32 = 0.5;
+/// Far plane: alcance de la vista
+const FAR_PLANE: f32 = 1500.0;
+
+/// Cómo se sombrean los fragmentos de un comando de dibujo
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Shading<'a> {
+    /// Superficie de un cuerpo: textura o patrón procedural y su material.
+    /// `part` es (nivel de LOD, índice en `Mesh::parts`) si el triángulo
+    /// usa un material del MTL en lugar del cuerpo.
+    Surface { body: &'a CelestialBody, part: Option<(usize, usize)>, texture_lod: f32 },
+    /// Anillo translúcido con la sombra del planeta. `mesh_radius` es el
+    /// radio de la malla de esfera, para que la sombra coincida con el disco dibujado.
+    Ring { body: &'a CelestialBody, mesh_radius: f32 },
+    /// Color ya calculado (impostores)
+    Flat(Color),
+    /// Halo alrededor del disco de un cuerpo emisivo
+    Corona { body: &'a CelestialBody, center: Vector2, disc_radius: f32, corona: f32 },
+}
+
+/// Malla de un anillo plano (annulus) en el plano ecuatorial XZ del cuerpo
+struct RingMesh {
+    vertices: Vec<Vector3>,
+    /// Factor de brillo por vértice para dibujar bandas
+    shades: Vec<f32>,
+    triangles: Vec<[usize; 3]>,
+}
+
+impl RingMesh {
+    fn new(inner_radius: f32, outer_radius: f32, segments: usize, bands: usize) -> Self {
+        let mut vertices = Vec::with_capacity(segments * (bands + 1));
+        let mut shades = Vec::with_capacity(segments * (bands + 1));
+        let mut triangles = Vec::with_capacity(segments * bands * 2);
+
+        // Anillos concéntricos de vértices: índice = banda * segments + segmento
+        for band in 0..=bands {
+            let t = band as f32 / bands as f32;
+            let radius = inner_radius + (outer_radius - inner_radius) * t;
+            let shade = 0.8 + 0.2 * (band as f32 * 2.3).sin().abs();
+            for segment in 0..segments {
+                let angle = (segment as f32 / segments as f32) * 2.0 * PI;
+                vertices.push(Vector3::new(radius * angle.cos(), 0.0, radius * angle.sin()));
+                shades.push(shade);
+            }
+        }
+
+        for band in 0..bands {
+            for segment in 0..segments {
+                let next = (segment + 1) % segments;
+                let inner_a = band * segments + segment;
+                let inner_b = band * segments + next;
+                let outer_a = (band + 1) * segments + segment;
+                let outer_b = (band + 1) * segments + next;
+                triangles.push([inner_a, outer_a, inner_b]);
+                triangles.push([inner_b, outer_a, outer_b]);
+            }
+        }
+
+        RingMesh { vertices, shades, triangles }
+    }
+}
 
 pub struct Renderer {
     pub screen_width: f32,
@@ -21,31 +103,54 @@ pub struct Renderer {
     pub stars: Vec<Vector3>,
     pub show_wireframe: bool,
     pub lighting_config: LightingConfig,
-    pub sphere_model: Option<ObjModel>,
+    /// Mallas con las que se dibujan los cuerpos (escaladas por su radio),
+    /// de la más detallada a la más simple
+    pub sphere_lods: LodChain,
+    /// Nivel de detalle elegido para cada cuerpo en el cuadro anterior
+    /// (para la histéresis), indexado por `NodeId::index`. Se vacía cuando
+    /// cambia la escena.
+    lod_choices: Vec<Option<LodChoice>>,
+    /// `SolarSystem::scene_id` de la escena a la que corresponde `lod_choices`
+    lod_scene: Option<u64>,
+    /// Ordenar cuerpos de atrás hacia adelante antes de dibujarlos.
+    /// Con el z-buffer ya no es necesario para la visibilidad correcta.
+    pub sort_bodies_by_distance: bool,
+    /// Hilos para rasterizar los tiles; con 1 se dibuja todo en el hilo
+    /// actual. El resultado es el mismo con cualquier cantidad.
+    pub threads: usize,
 }
 
 impl Renderer {
-    pub fn new(screen_width: f32, screen_height: f32) -> Self {
+    /// Crea el renderer con la esfera de `assets/sphere.obj`. Si el modelo no
+    /// se puede cargar, el error se devuelve al llamador, que puede usar
+    /// `with_procedural_sphere` en su lugar.
+    pub fn new(screen_width: f32, screen_height: f32) -> Result<Self, AssetError> {
+        let mut model = ObjModel::load("assets/sphere.obj")?;
+        for warning in &model.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        // Los vt de sphere.obj asignan a cada triángulo las esquinas del
+        // cuadrado unitario; los mapas de planetas son equirectangulares
+        model.generate_spherical_uvs();
+        println!("Sphere model loaded: {} vertices", model.vertices.len());
+        Ok(Self::with_sphere_mesh(screen_width, screen_height, Mesh::from_obj(&model)))
+    }
+
+    /// Crea el renderer con una icosfera generada por código, sin leer archivos
+    pub fn with_procedural_sphere(screen_width: f32, screen_height: f32) -> Self {
+        Self::with_sphere_mesh(screen_width, screen_height, Mesh::icosphere(PROCEDURAL_SPHERE_RADIUS, 3))
+    }
+
+    pub fn with_sphere_mesh(screen_width: f32, screen_height: f32, sphere_mesh: Mesh) -> Self {
         let projection_matrix = create_projection_matrix(
-            PI / 4.0,                    // FOV: 45 grados (campo de visión)
+            FOV_Y,                       // FOV: 45 grados (campo de visión)
             screen_width / screen_height, // Aspect ratio
-            5.0,                         // Near plane: 5.0 - evita que planetas atraviesen
-            1500.0,                      // Far plane: 1500.0 (antes 1000.0) - alcance más lejano
+            NEAR_PLANE,
+            FAR_PLANE,
         );
 
         let viewport_matrix = create_viewport_matrix(0.0, 0.0, screen_width, screen_height);
 
-        let sphere_model = match ObjModel::load("assets/sphere.obj") {
-            Ok(model) => {
-                println!("Sphere model loaded: {} vertices", model.vertices.len());
-                Some(model)
-            }
-            Err(e) => {
-                eprintln!("Error loading sphere.obj: {}", e);
-                None
-            }
-        };
-
         let mut renderer = Renderer {
             screen_width,
             screen_height,
@@ -54,7 +159,11 @@ impl Renderer {
             stars: Vec::new(),
             show_wireframe: false,
             lighting_config: LightingConfig::default(),
-            sphere_model,
+            sphere_lods: LodChain::for_sphere(sphere_mesh),
+            lod_choices: Vec::new(),
+            lod_scene: None,
+            sort_bodies_by_distance: false,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
         };
 
         renderer.generate_stars(1500);
@@ -63,6 +172,16 @@ impl Renderer {
 
     fn generate_stars(&mut self, count: usize) {
         let mut rng = rand::thread_rng();
+        self.generate_stars_with_rng(count, &mut rng);
+    }
+
+    /// Regenera el fondo de estrellas con una semilla fija (render determinista)
+    pub fn generate_stars_seeded(&mut self, count: usize, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.generate_stars_with_rng(count, &mut rng);
+    }
+
+    fn generate_stars_with_rng<R: Rng>(&mut self, count: usize, rng: &mut R) {
         self.stars.clear();
 
         for _ in 0..count {
@@ -81,13 +200,14 @@ impl Renderer {
     // ===== MAIN FRAMEBUFFER RENDERING =====
     
     pub fn render_solar_system_to_framebuffer(
-        &self,
+        &mut self,
         framebuffer: &mut Framebuffer,
         solar_system: &SolarSystem,
         camera: &Camera,
         show_orbits: bool,
     ) {
         let view_matrix = camera.get_view_matrix();
+        let lighting = self.scene_lighting(solar_system);
 
         // 1. Background gradient
         self.render_space_gradient_to_framebuffer(framebuffer);
@@ -95,21 +215,91 @@ impl Renderer {
         // 2. Stars
         self.render_stars_to_framebuffer(framebuffer, &view_matrix);
 
-        // 3. Celestial bodies (sorted by distance)
-        let bodies = solar_system.get_bodies_sorted_by_distance(camera.get_position());
-        for body in bodies {
-            self.render_celestial_body_to_framebuffer(framebuffer, body, &view_matrix);
-            for moon in &body.moons {
-                self.render_celestial_body_to_framebuffer(framebuffer, moon, &view_matrix);
-            }
-        }
+        // 3-5. Cuerpos, coronas y anillos, rasterizados juntos por tiles
+        let mut draw_list = self.record_scene(solar_system, camera, &view_matrix, &lighting);
+        draw_list.execute(framebuffer, self.threads, |shading, fragment| {
+            self.shade(shading, fragment, camera.get_position(), &lighting)
+        });
 
-        // 4. Orbits
+        // 6. Orbits
         if show_orbits {
             self.render_orbits_to_framebuffer(framebuffer, solar_system, &view_matrix);
         }
     }
 
+    /// Graba los comandos de dibujo de los cuerpos, coronas y anillos del
+    /// cuadro, en el orden en que se dibujan
+    pub(crate) fn record_scene<'a>(
+        &mut self,
+        solar_system: &'a SolarSystem,
+        camera: &Camera,
+        view_matrix: &Matrix,
+        lighting: &LightingConfig,
+    ) -> DrawList<Shading<'a>> {
+        // 3. Celestial bodies (visibility resolved by the depth buffer)
+        let ids = if self.sort_bodies_by_distance {
+            solar_system.body_ids_sorted_by_distance(camera.get_position())
+        } else {
+            solar_system.body_ids()
+        };
+        let bodies: Vec<&CelestialBody> = ids.iter().map(|&id| solar_system.graph.body(id)).collect();
+
+        // Otra escena: lo elegido para los nodos de la anterior no sirve,
+        // aunque tenga la misma cantidad de nodos
+        if self.lod_scene != Some(solar_system.scene_id()) || self.lod_choices.len() != solar_system.graph.len() {
+            self.lod_choices = vec![None; solar_system.graph.len()];
+            self.lod_scene = Some(solar_system.scene_id());
+        }
+        let lods: Vec<LodChoice> = ids
+            .iter()
+            .zip(&bodies)
+            .map(|(&id, body)| self.select_lod(id, body, camera.get_position()))
+            .collect();
+
+        let mut draw_list = DrawList::new();
+        for (body, &lod) in bodies.iter().zip(&lods) {
+            self.record_celestial_body(&mut draw_list, body, lod, camera.get_position(), view_matrix, lighting);
+        }
+
+        // 4. Coronas de los cuerpos emisivos (halo translúcido alrededor del disco)
+        self.record_coronas(&mut draw_list, &bodies, view_matrix);
+
+        // 5. Rings (translúcidos: después de todos los cuerpos opacos)
+        self.record_rings(&mut draw_list, solar_system, camera.get_position(), view_matrix);
+
+        draw_list
+    }
+
+    /// Iluminación del frame: la primera luz configurada es la plantilla de
+    /// las estrellas. Cada estrella la reemplaza por una luz en su posición,
+    /// teñida con su `light_color` y escalada por su `light_intensity`, de
+    /// modo que el terminador de cada planeta mira a cada sol. Las demás
+    /// luces configuradas se mantienen.
+    pub(crate) fn scene_lighting(&self, solar_system: &SolarSystem) -> LightingConfig {
+        let mut lighting = self.lighting_config.clone();
+        if lighting.lights.is_empty() {
+            return lighting;
+        }
+
+        let template = lighting.lights.remove(0);
+        let tint = |a: u8, b: u8| (a as u16 * b as u16 / 255) as u8;
+        let star_lights: Vec<PointLight> = solar_system
+            .stars()
+            .into_iter()
+            .map(|star| {
+                let color = Color::new(
+                    tint(template.color.r, star.light_color.r),
+                    tint(template.color.g, star.light_color.g),
+                    tint(template.color.b, star.light_color.b),
+                    255,
+                );
+                PointLight::new(star.position, color, template.intensity * star.light_intensity, template.falloff)
+            })
+            .collect();
+        lighting.lights.splice(0..0, star_lights);
+        lighting
+    }
+
     // ===== BACKGROUND & STARS =====
 
     fn render_space_gradient_to_framebuffer(&self, framebuffer: &mut Framebuffer) {
@@ -183,147 +373,347 @@ impl Renderer {
 
     // ===== CELESTIAL BODIES =====
 
-    fn render_celestial_body_to_framebuffer(
+    /// Elige la malla de un cuerpo según su radio en pantalla, visto desde
+    /// la cámara, y la recuerda para el cuadro siguiente
+    fn select_lod(&mut self, id: NodeId, body: &CelestialBody, camera_position: Vector3) -> LodChoice {
+        let radius = self.screen_radius(body, camera_position);
+        let previous = self.lod_choices[id.index()];
+        let choice = self.sphere_lods.select(radius, previous);
+        self.lod_choices[id.index()] = Some(choice);
+        choice
+    }
+
+    /// Radio en píxeles con que se ve un cuerpo desde la cámara
+    fn screen_radius(&self, body: &CelestialBody, camera_position: Vector3) -> f32 {
+        let focal_length = self.screen_height * 0.5 / (FOV_Y * 0.5).tan();
+        let radius = body.radius * self.sphere_lods.bounding_radius();
+        screen_radius(radius, (body.position - camera_position).length(), focal_length)
+    }
+
+    fn record_celestial_body<'a>(
         &self,
-        framebuffer: &mut Framebuffer,
-        body: &CelestialBody,
+        draw_list: &mut DrawList<Shading<'a>>,
+        body: &'a CelestialBody,
+        lod: LodChoice,
+        camera_position: Vector3,
         view_matrix: &Matrix,
+        lighting: &LightingConfig,
     ) {
-        if let Some(ref model) = self.sphere_model {
-            self.render_obj_model_to_framebuffer(framebuffer, model, body, view_matrix);
+        match lod {
+            LodChoice::Mesh(level) => {
+                self.record_mesh(draw_list, level, body, view_matrix);
+            }
+            LodChoice::Impostor => {
+                self.record_impostor(draw_list, body, camera_position, view_matrix, lighting);
+            }
+        }
+    }
+
+    fn record_mesh<'a>(&self, draw_list: &mut DrawList<Shading<'a>>, level: usize, body: &'a CelestialBody, view_matrix: &Matrix) {
+        let mesh = self.sphere_lods.mesh(level);
+
+        // Model matrix: traslación * inclinación axial * giro * escala
+        let model_matrix = body.get_model_matrix();
+
+        // Vertex shader: cada vértice se transforma una sola vez, aunque lo
+        // compartan varios triángulos
+        let transformed: Vec<VertexShaderOutput> = mesh
+            .vertices
+            .iter()
+            .map(|vertex| {
+                vertex_shader(
+                    &vertex.shader_input(), body.color,
+                    &model_matrix, view_matrix, &self.projection_matrix, &self.viewport_matrix
+                )
+            })
+            .collect();
+
+        // Process each triangle
+        for (index, indices) in mesh.triangles.iter().enumerate() {
+            let part = mesh.part_of(index);
+            let texture = match part {
+                Some(part) => mesh.parts[part].texture.as_ref(),
+                None => body.texture.as_ref(),
+            };
+
+            // Los triángulos que cruzan el near o el far plane se recortan
+            // en clip space; los que quedan fuera del frustum se descartan
+            clip_triangle(indices.map(|i| transformed[i]), &self.viewport_matrix, |triangle| {
+                // Backface culling
+                let [v0, v1, v2] = triangle.vertices.map(|v| v.screen_position);
+                let cross_z = (v1.x - v0.x) * (v2.y - v0.y) - (v1.y - v0.y) * (v2.x - v0.x);
+
+                // El viewport invierte el eje Y, así que las caras frontales
+                // (antihorarias en NDC) quedan en sentido horario en pantalla
+                if cross_z >= 0.0 {
+                    return; // Back-facing
+                }
+
+                // Nivel de mipmap según cuántos texels caen en cada píxel del triángulo
+                let texture_lod = texture.map_or(0.0, |texture| {
+                    texture.triangle_lod(
+                        triangle.vertices.map(|v| v.varyings.uv),
+                        triangle.vertices.map(|v| Vector2::new(v.screen_position.x, v.screen_position.y)),
+                    )
+                });
+
+                draw_list.push(DrawCommand {
+                    primitive: Primitive::Triangle(triangle),
+                    shading: Shading::Surface { body, part: part.map(|part| (level, part)), texture_lod },
+                    blend: BlendMode::Opaque,
+                    depth_write: true,
+                });
+            });
         }
     }
 
-    fn render_obj_model_to_framebuffer(
+    /// Color de un fragmento según el sombreado de su comando, o `None` si
+    /// no se dibuja
+    fn shade(&self, shading: &Shading, fragment: &Fragment, camera_position: Vector3, lighting: &LightingConfig) -> Option<Color> {
+        match *shading {
+            Shading::Surface { body, part, texture_lod } => {
+                // Un material del MTL reemplaza el color, la textura y el
+                // material del cuerpo
+                let part = part.map(|(level, part)| &self.sphere_lods.mesh(level).parts[part]);
+                let (color, texture, material) = match part {
+                    Some(part) => (part.color, &part.texture, &part.material),
+                    None => (fragment.varyings.color, &body.texture, &body.material),
+                };
+
+                // Color de la superficie: patrón procedural en object space,
+                // así gira junto con el cuerpo
+                let albedo = match texture {
+                    Some(texture) => texture.sample_lod(fragment.varyings.uv, texture_lod),
+                    None => color,
+                };
+                let base_color = surface_color(body.surface, fragment.varyings.object_position, albedo, body.surface_seed);
+
+                // Fragment shader: el material decide cómo se ilumina
+                Some(shade_fragment(
+                    material,
+                    base_color,
+                    fragment.varyings.world_position,
+                    fragment.varyings.normal,
+                    camera_position,
+                    lighting,
+                ))
+            }
+            Shading::Ring { body, mesh_radius } => Some(Self::shade_ring_fragment(fragment, body, mesh_radius, lighting)),
+            Shading::Flat(color) => Some(color),
+            Shading::Corona { body, center, disc_radius, corona } => {
+                let dx = fragment.screen_x as f32 + 0.5 - center.x;
+                let dy = fragment.screen_y as f32 + 0.5 - center.y;
+                let intensity = corona_intensity(dx.hypot(dy) / disc_radius, corona);
+                if intensity <= 0.0 {
+                    return None;
+                }
+                let alpha = (intensity * 0.6 * 255.0) as u8;
+                Some(Color::new(body.color.r, body.color.g, body.color.b, alpha))
+            }
+        }
+    }
+
+    /// Dibuja un cuerpo demasiado chico para una malla como un punto.
+    ///
+    /// El color es el de la cara que mira a la cámara, y su opacidad es la
+    /// fracción del píxel que cubriría el disco, así el cuerpo se desvanece
+    /// al alejarse en lugar de desaparecer de golpe.
+    fn record_impostor(
         &self,
-        framebuffer: &mut Framebuffer,
-        model: &ObjModel,
+        draw_list: &mut DrawList<Shading>,
         body: &CelestialBody,
+        camera_position: Vector3,
         view_matrix: &Matrix,
+        lighting: &LightingConfig,
     ) {
-        // Transformation matrices
-        let scale_matrix = create_scale_matrix(body.radius, body.radius, body.radius);
-        let translation_matrix = create_translation_matrix(body.position.x, body.position.y, body.position.z);
-        let model_matrix = translation_matrix.multiply(&scale_matrix);
-        
-        // LOD calculation
-        let distance_to_camera = Self::vector_length(body.position);
-        let apparent_radius = (body.radius * self.screen_width) / (distance_to_camera * 2.0);
-        
-        if apparent_radius < 0.3 {
-            return; // Too small, skip
+        let center = self.world_to_screen(body.position, view_matrix);
+        if center.z < -1.0 || center.z > 1.0 {
+            return; // Detrás de la cámara o más allá del far plane
         }
-        
-        // Adaptive LOD
-        let face_skip = if apparent_radius < 3.0 { 8 } 
-                       else if apparent_radius < 8.0 { 4 } 
-                       else if apparent_radius < 20.0 { 2 } 
-                       else { 1 };
-
-        // Process each face
-        for (face_idx, face) in model.faces.iter().enumerate() {
-            if face_skip > 1 && face_idx % face_skip != 0 {
-                continue; // LOD skip
-            }
-            
-            if face.vertices.len() < 3 {
-                continue;
-            }
+        if center.x < 0.0 || center.y < 0.0 || center.x >= self.screen_width || center.y >= self.screen_height {
+            return;
+        }
+
+        let to_camera = (camera_position - body.position).normalized();
+        let radius = body.radius * self.sphere_lods.bounding_radius();
+        let color = shade_fragment(
+            &body.material,
+            body.color,
+            body.position + to_camera * radius,
+            to_camera,
+            camera_position,
+            lighting,
+        );
+
+        let coverage = (PI * self.screen_radius(body, camera_position).powi(2)).min(1.0);
+        let color = Color::new(color.r, color.g, color.b, (coverage * 255.0).round() as u8);
+        let (x, y) = (center.x as u32, center.y as u32);
+        draw_list.push(DrawCommand {
+            primitive: Primitive::Rect { rect: PixelRect { min_x: x, min_y: y, max_x: x, max_y: y }, depth: center.z },
+            shading: Shading::Flat(color),
+            blend: BlendMode::Alpha,
+            depth_write: true,
+        });
+    }
 
-            // Triangulate face
-            for i in 1..face.vertices.len() - 1 {
-                let v0_idx = face.vertices[0];
-                let v1_idx = face.vertices[i];
-                let v2_idx = face.vertices[i + 1];
+    // ===== CORONAS =====
 
-                if v0_idx >= model.vertices.len() || v1_idx >= model.vertices.len() || v2_idx >= model.vertices.len() {
-                    continue;
+    /// Dibuja el halo de cada cuerpo emisivo que tiene corona.
+    ///
+    /// El halo es un anillo en pantalla alrededor del disco proyectado, a la
+    /// profundidad del centro de la estrella: los cuerpos que están delante
+    /// lo tapan y los de atrás quedan cubiertos por el brillo.
+    fn record_coronas<'a>(&self, draw_list: &mut DrawList<Shading<'a>>, bodies: &[&'a CelestialBody], view_matrix: &Matrix) {
+        for body in bodies {
+            if let Material::Emissive { corona, .. } = body.material {
+                if corona > 0.0 {
+                    self.record_corona(draw_list, body, corona, view_matrix);
                 }
+            }
+        }
+    }
 
-                let v0 = model.vertices[v0_idx];
-                let v1 = model.vertices[v1_idx];
-                let v2 = model.vertices[v2_idx];
+    fn record_corona<'a>(
+        &self,
+        draw_list: &mut DrawList<Shading<'a>>,
+        body: &'a CelestialBody,
+        corona: f32,
+        view_matrix: &Matrix,
+    ) {
+        // Radio del disco en pantalla: proyectar el centro y un punto del
+        // borde desplazado en el plano de la cámara. La malla de la esfera
+        // no mide exactamente 1, así que se usa su radio real.
+        let model_radius = self.sphere_lods.bounding_radius();
+        let view_center = view_matrix.transform_point(body.position);
+        let edge = view_center + Vector3::new(body.radius * model_radius, 0.0, 0.0);
+        let center = self.viewport_matrix.transform_point(self.projection_matrix.transform_point(view_center));
+        let edge = self.viewport_matrix.transform_point(self.projection_matrix.transform_point(edge));
+        if center.z < -1.0 || center.z > 1.0 {
+            return; // Fuera del rango de profundidad (detrás de la cámara o muy lejos)
+        }
 
-                // Vertex shader: transform vertices
-                let v0_out = vertex_shader(
-                    v0, Vector3::new(v0.x, v0.y, v0.z).normalized(), body.color,
-                    &model_matrix, view_matrix, &self.projection_matrix, &self.viewport_matrix
-                );
-                let v1_out = vertex_shader(
-                    v1, Vector3::new(v1.x, v1.y, v1.z).normalized(), body.color,
-                    &model_matrix, view_matrix, &self.projection_matrix, &self.viewport_matrix
-                );
-                let v2_out = vertex_shader(
-                    v2, Vector3::new(v2.x, v2.y, v2.z).normalized(), body.color,
-                    &model_matrix, view_matrix, &self.projection_matrix, &self.viewport_matrix
-                );
+        let disc_radius = (edge.x - center.x).hypot(edge.y - center.y);
+        if disc_radius < 0.5 {
+            return;
+        }
+        let halo_radius = disc_radius * (1.0 + corona);
+
+        let min_x = (center.x - halo_radius).floor().max(0.0) as u32;
+        let max_x = (center.x + halo_radius).ceil().min(self.screen_width - 1.0).max(0.0) as u32;
+        let min_y = (center.y - halo_radius).floor().max(0.0) as u32;
+        let max_y = (center.y + halo_radius).ceil().min(self.screen_height - 1.0).max(0.0) as u32;
+
+        // Sin escribir profundidad: el halo es translúcido
+        draw_list.push(DrawCommand {
+            primitive: Primitive::Rect { rect: PixelRect { min_x, min_y, max_x, max_y }, depth: center.z },
+            shading: Shading::Corona { body, center: Vector2::new(center.x, center.y), disc_radius, corona },
+            blend: BlendMode::Alpha,
+            depth_write: false,
+        });
+    }
 
-                // Backface culling
-                let edge1_x = v1_out.screen_position.x - v0_out.screen_position.x;
-                let edge1_y = v1_out.screen_position.y - v0_out.screen_position.y;
-                let edge2_x = v2_out.screen_position.x - v0_out.screen_position.x;
-                let edge2_y = v2_out.screen_position.y - v0_out.screen_position.y;
-                let cross_z = edge1_x * edge2_y - edge1_y * edge2_x;
-                
-                if cross_z <= 0.0 {
-                    continue; // Back-facing
-                }
+    // ===== RINGS =====
 
-                // Rasterize triangle usando el módulo rasterizer optimizado
-                // Este módulo ahora incluye frustum culling de fragmentos
-                self.rasterize_triangle_to_framebuffer(
-                    framebuffer,
-                    v0_out,
-                    v1_out,
-                    v2_out,
-                );
-            }
+    /// Dibuja los anillos de todos los cuerpos que los tienen.
+    ///
+    /// Los anillos son translúcidos, así que se dibujan después de los cuerpos
+    /// opacos, del más lejano al más cercano, con depth test pero sin escribir
+    /// profundidad: el planeta tapa la parte de atrás del anillo y la parte
+    /// de adelante se mezcla sobre el planeta.
+    fn record_rings<'a>(
+        &self,
+        draw_list: &mut DrawList<Shading<'a>>,
+        solar_system: &'a SolarSystem,
+        camera_position: Vector3,
+        view_matrix: &Matrix,
+    ) {
+        let mut ringed_bodies: Vec<&CelestialBody> = solar_system
+            .get_all_bodies()
+            .into_iter()
+            .filter(|body| body.has_rings)
+            .collect();
+        ringed_bodies.sort_by(|a, b| {
+            let dist_a = a.get_distance_from_camera(camera_position);
+            let dist_b = b.get_distance_from_camera(camera_position);
+            dist_b.partial_cmp(&dist_a).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mesh_radius = self.sphere_lods.bounding_radius();
+        for body in ringed_bodies {
+            self.record_ring(draw_list, body, mesh_radius, view_matrix);
         }
     }
 
-    /// Rasteriza un triángulo al framebuffer
-    /// 
-    /// Esta función usa el módulo rasterizer optimizado que incluye:
-    /// - Frustum culling de fragmentos (no genera fragmentos fuera de pantalla)
-    /// - Interpolación baricéntrica de atributos
-    /// - Fragment shader para calcular colores finales
-    fn rasterize_triangle_to_framebuffer(
+    fn record_ring<'a>(
         &self,
-        framebuffer: &mut Framebuffer,
-        v0: VertexShaderOutput,
-        v1: VertexShaderOutput,
-        v2: VertexShaderOutput,
+        draw_list: &mut DrawList<Shading<'a>>,
+        body: &'a CelestialBody,
+        mesh_radius: f32,
+        view_matrix: &Matrix,
     ) {
-        // Crear el triángulo desde los vértices transformados
-        let triangle = Triangle::new(v0, v1, v2);
-        
-        // Rasterizar usando el módulo optimizado con frustum culling
-        // Ahora pasamos screen_width y screen_height para que el rasterizer
-        // pueda clipear fragmentos fuera de pantalla ANTES de generarlos
-        let fragments = rasterize_triangle(
-            &triangle, 
-            self.screen_width, 
-            self.screen_height
-        );
-        
-        // Procesar cada fragmento generado (todos están garantizados dentro de pantalla)
-        for fragment in fragments {
-            // Fragment shader: calcular color final con iluminación
-            let final_color = fragment_shader(
-                fragment.color, 
-                fragment.normal, 
-                &self.lighting_config
-            );
-            
-            // Escribir al framebuffer (sin necesidad de verificar límites)
-            framebuffer.set_pixel_color(
-                fragment.screen_x as u32, 
-                fragment.screen_y as u32, 
-                final_color
-            );
+        let mesh = RingMesh::new(body.ring_inner_radius, body.ring_outer_radius, RING_SEGMENTS, RING_BANDS);
+        // Plano ecuatorial inclinado igual que el planeta (sin el giro)
+        let model_matrix = body.get_ring_model_matrix();
+
+        // Cada vértice se transforma una sola vez; los triángulos comparten vértices
+        let transformed: Vec<VertexShaderOutput> = mesh
+            .vertices
+            .iter()
+            .zip(&mesh.shades)
+            .map(|(&position, &shade)| {
+                let color = Color::new(
+                    (body.ring_color.r as f32 * shade) as u8,
+                    (body.ring_color.g as f32 * shade) as u8,
+                    (body.ring_color.b as f32 * shade) as u8,
+                    body.ring_color.a,
+                );
+                vertex_shader(
+                    &Vertex::new(position, Vector3::new(0.0, 1.0, 0.0), Vector2::zero()), color,
+                    &model_matrix, view_matrix, &self.projection_matrix, &self.viewport_matrix
+                )
+            })
+            .collect();
+
+        for indices in &mesh.triangles {
+            // Sin backface culling: el anillo se ve por ambas caras
+            clip_triangle(indices.map(|i| transformed[i]), &self.viewport_matrix, |triangle| {
+                draw_list.push(DrawCommand {
+                    primitive: Primitive::Triangle(triangle),
+                    shading: Shading::Ring { body, mesh_radius },
+                    blend: BlendMode::Alpha,
+                    depth_write: false,
+                });
+            });
         }
     }
 
+    /// Iluminación del anillo con la sombra del planeta.
+    ///
+    /// El anillo es delgado, así que se ilumina igual por ambas caras. Si el
+    /// rayo desde el fragmento hacia una luz choca con el planeta, esa luz
+    /// no aporta; sin ninguna luz el fragmento solo recibe luz ambiental. La
+    /// sombra usa el disco dibujado (`body.radius * mesh_radius`), no `body.radius`.
+    fn shade_ring_fragment(
+        fragment: &Fragment,
+        body: &CelestialBody,
+        mesh_radius: f32,
+        lighting: &LightingConfig,
+    ) -> Color {
+        let lit = fragment_shader_with_shadows(
+            fragment.varyings.color,
+            fragment.varyings.world_position,
+            fragment.varyings.normal,
+            lighting,
+            true,
+            |light| {
+                let to_light = (light.position - fragment.varyings.world_position).normalized();
+                body.intersect_ray(fragment.varyings.world_position, to_light, mesh_radius).is_some()
+            },
+        );
+
+        Color::new(lit.r, lit.g, lit.b, fragment.varyings.color.a)
+    }
+
     // ===== ORBITS =====
 
     fn render_orbits_to_framebuffer(
@@ -332,9 +722,13 @@ impl Renderer {
         solar_system: &SolarSystem,
         view_matrix: &Matrix,
     ) {
-        for planet in &solar_system.planets {
-            let radius = planet.orbital_radius;
-            let segments = 120;
+        // Todas las órbitas del grafo: planetas, lunas y estrellas de un sistema binario
+        for planet in solar_system.get_all_bodies() {
+            if planet.orbit.is_none() {
+                continue;
+            }
+            // Elipse real de la órbita (cerrada: el último punto conecta con el primero)
+            let points = planet.get_orbit_points(120);
             
             let orbit_color = Color::new(
                 (planet.color.r as f32 * 0.5) as u8,
@@ -343,12 +737,9 @@ impl Renderer {
                 160
             );
 
-            for i in 0..segments {
-                let angle1 = (i as f32 / segments as f32) * 2.0 * PI;
-                let angle2 = ((i + 1) as f32 / segments as f32) * 2.0 * PI;
-
-                let p1 = Vector3::new(radius * angle1.cos(), 0.0, radius * angle1.sin());
-                let p2 = Vector3::new(radius * angle2.cos(), 0.0, radius * angle2.sin());
+            for i in 0..points.len() {
+                let p1 = points[i];
+                let p2 = points[(i + 1) % points.len()];
 
                 let screen1 = self.world_to_screen(p1, view_matrix);
                 let screen2 = self.world_to_screen(p2, view_matrix);
@@ -376,10 +767,6 @@ impl Renderer {
         self.viewport_matrix.transform_point(proj_pos)
     }
 
-    fn vector_length(v: Vector3) -> f32 {
-        (v.x * v.x + v.y * v.y + v.z * v.z).sqrt()
-    }
-
     pub fn toggle_wireframe(&mut self) {
         self.show_wireframe = !self.show_wireframe;
     }
@@ -388,11 +775,129 @@ impl Renderer {
         self.screen_width = new_width;
         self.screen_height = new_height;
         self.projection_matrix = create_projection_matrix(
-            PI / 4.0, 
-            new_width / new_height, 
-            5.0,      // Near plane consistente con el constructor
-            1500.0    // Far plane consistente con el constructor
+            FOV_Y,
+            new_width / new_height,
+            NEAR_PLANE,
+            FAR_PLANE,
         );
         self.viewport_matrix = create_viewport_matrix(0.0, 0.0, new_width, new_height);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orbit::OrbitalElements;
+    use crate::varyings::Varyings;
+
+    #[test]
+    fn test_lod_history_follows_nodes_and_resets_with_the_scene() {
+        let mut renderer = Renderer::with_procedural_sphere(640.0, 480.0);
+        let camera = Camera::new(Vector3::new(0.0, 50.0, 100.0), Vector3::zero(), Vector3::new(0.0, 1.0, 0.0));
+        let view_matrix = camera.get_view_matrix();
+        let lighting = LightingConfig::default();
+
+        let system = SolarSystem::new();
+        renderer.record_scene(&system, &camera, &view_matrix, &lighting);
+        assert_eq!(renderer.lod_choices.len(), system.graph.len());
+        for id in system.body_ids() {
+            assert!(renderer.lod_choices[id.index()].is_some());
+        }
+
+        // Otra escena: nada de lo elegido para la anterior pasa a sus nodos
+        let binary = SolarSystem::from_file("assets/systems/binary.toml").unwrap();
+        renderer.record_scene(&binary, &camera, &view_matrix, &lighting);
+        assert_eq!(renderer.lod_choices.len(), binary.graph.len());
+        for node in binary.graph.iter() {
+            assert_eq!(renderer.lod_choices[node.id.index()].is_some(), !node.body.is_barycenter());
+        }
+
+        // Otra escena con la misma cantidad de nodos también empieza de cero:
+        // lo que quede en los baricentros (que no se dibujan) se tiene que borrar
+        let barycenters: Vec<NodeId> = binary.graph.iter().filter(|node| node.body.is_barycenter()).map(|node| node.id).collect();
+        assert!(!barycenters.is_empty());
+        for id in &barycenters {
+            renderer.lod_choices[id.index()] = Some(LodChoice::Impostor);
+        }
+        let reloaded = SolarSystem::from_file("assets/systems/binary.toml").unwrap();
+        assert_eq!(reloaded.graph.len(), binary.graph.len());
+        renderer.record_scene(&reloaded, &camera, &view_matrix, &lighting);
+        for id in &barycenters {
+            assert_eq!(renderer.lod_choices[id.index()], None);
+        }
+    }
+
+    #[test]
+    fn test_every_star_casts_its_own_tinted_light() {
+        let renderer = Renderer::with_procedural_sphere(64.0, 64.0);
+        let single = renderer.scene_lighting(&SolarSystem::new());
+        assert_eq!(single.lights.len(), 1);
+        assert_eq!(single.lights[0].color, Color::WHITE);
+
+        let binary = SolarSystem::from_file("assets/systems/binary.toml").unwrap();
+        let lighting = renderer.scene_lighting(&binary);
+        let stars = binary.stars();
+        assert_eq!(lighting.lights.len(), stars.len());
+        for (light, star) in lighting.lights.iter().zip(&stars) {
+            assert_eq!(light.position, star.position);
+            assert_eq!(light.color, star.light_color);
+            assert_eq!(light.intensity, star.light_intensity);
+        }
+    }
+
+    #[test]
+    fn test_ring_shadow_matches_the_drawn_disc() {
+        let renderer = Renderer::with_procedural_sphere(64.0, 64.0);
+        let mesh_radius = renderer.sphere_lods.bounding_radius();
+        let body = CelestialBody::new_planet(
+            "Anillado".to_string(),
+            Vector3::zero(),
+            OrbitalElements::circular(50.0, 1.0, 0.0),
+            10.0,
+            Color::WHITE,
+            1.0,
+        );
+        let mut lighting = LightingConfig::default();
+        lighting.lights[0].position = body.position + Vector3::new(1000.0, 0.0, 0.0);
+
+        // Fragmento detrás del planeta, desplazado `offset` del eje hacia la luz
+        let shade_at = |offset: f32| {
+            let fragment = Fragment {
+                screen_x: 0,
+                screen_y: 0,
+                depth: 0.5,
+                varyings: Varyings {
+                    world_position: body.position + Vector3::new(-30.0, offset, 0.0),
+                    normal: Vector3::new(1.0, 0.0, 0.0),
+                    color: Color::WHITE,
+                    ..Default::default()
+                },
+            };
+            Renderer::shade_ring_fragment(&fragment, &body, mesh_radius, &lighting)
+        };
+
+        let shadowed = shade_at(0.0);
+        let unshadowed = shade_at(100.0);
+        assert!(shadowed.r < unshadowed.r);
+        // Fuera del disco dibujado pero dentro de `body.radius`: sigue iluminado
+        let just_outside = shade_at(body.radius * mesh_radius * 1.1);
+        assert!(body.radius * mesh_radius * 1.1 < body.radius);
+        assert_eq!(just_outside.r, unshadowed.r);
+    }
+
+    #[test]
+    fn test_ring_mesh_is_flat_annulus() {
+        let mesh = RingMesh::new(10.0, 15.0, 32, 4);
+        assert_eq!(mesh.vertices.len(), 32 * 5);
+        assert_eq!(mesh.triangles.len(), 32 * 4 * 2);
+
+        for vertex in &mesh.vertices {
+            let radius = (vertex.x * vertex.x + vertex.z * vertex.z).sqrt();
+            assert!(vertex.y == 0.0);
+            assert!(radius > 10.0 - 1e-3 && radius < 15.0 + 1e-3);
+        }
+        for triangle in &mesh.triangles {
+            assert!(triangle.iter().all(|&i| i < mesh.vertices.len()));
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! Scene graph - The tree of bodies and their parent/child transforms
+//!
+//! Every body lives in a node. A node's transform is relative to its parent:
+//! its Keplerian orbit around the parent, or a fixed offset for bodies that
+//! don't orbit. World states are composed from the roots down, so satellites
+//! can nest to any depth (moons of moons), several stars can share a scene,
+//! and barycenter nodes can be the invisible center two stars orbit.
+//!
+//! Nodes are never removed, so a `NodeId` stays valid for the life of the
+//! graph. Children are always added after their parent, which keeps every
+//! parent before its children in `iter` order.
+
+use raylib::prelude::*;
+
+use crate::celestial_body::{BodyState, CelestialBody};
+
+/// Stable identifier of a node in a `SceneGraph`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// Position in `SceneGraph::iter` and in `SceneGraph::states_at`
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneNode {
+    pub id: NodeId,
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+    pub body: CelestialBody,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SceneGraph {
+    nodes: Vec<SceneNode>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a node without a parent; its transform is relative to the origin
+    pub fn add_root(&mut self, body: CelestialBody) -> NodeId {
+        self.push(None, body)
+    }
+
+    /// Add a node whose transform is relative to `parent`
+    pub fn add_child(&mut self, parent: NodeId, body: CelestialBody) -> NodeId {
+        let id = self.push(Some(parent), body);
+        self.nodes[parent.0].children.push(id);
+        id
+    }
+
+    fn push(&mut self, parent: Option<NodeId>, body: CelestialBody) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(SceneNode { id, parent, children: Vec::new(), body });
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn body(&self, id: NodeId) -> &CelestialBody {
+        &self.nodes[id.0].body
+    }
+
+    pub fn body_mut(&mut self, id: NodeId) -> &mut CelestialBody {
+        &mut self.nodes[id.0].body
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id.0].children
+    }
+
+    #[cfg(test)]
+    pub fn roots(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes.iter().filter(|node| node.parent.is_none()).map(|node| node.id)
+    }
+
+    /// Every node, parents before their children
+    pub fn iter(&self) -> impl Iterator<Item = &SceneNode> {
+        self.nodes.iter()
+    }
+
+    /// Every body, parents before their children
+    pub fn bodies_mut(&mut self) -> impl Iterator<Item = &mut CelestialBody> {
+        self.nodes.iter_mut().map(|node| &mut node.body)
+    }
+
+    /// Node of the body called `name`
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().find(|node| node.body.name == name).map(|node| node.id)
+    }
+
+    /// Number of ancestors (0 for roots)
+    #[cfg(test)]
+    pub fn depth(&self, id: NodeId) -> usize {
+        std::iter::successors(self.parent(id), |&parent| self.parent(parent)).count()
+    }
+
+    /// Every node below `id`, parents before their children
+    pub fn descendants(&self, id: NodeId) -> Vec<NodeId> {
+        let mut descendants = Vec::new();
+        let mut pending = vec![id];
+        while let Some(node) = pending.pop() {
+            // Reversed so the first child comes out first
+            for &child in self.children(node).iter().rev() {
+                pending.push(child);
+            }
+            if node != id {
+                descendants.push(node);
+            }
+        }
+        descendants.sort();
+        descendants
+    }
+
+    /// Mass of the body at `id` and everything below it
+    pub fn subtree_mass(&self, id: NodeId) -> f32 {
+        self.body(id).mass + self.descendants(id).iter().map(|&node| self.body(node).mass).sum::<f32>()
+    }
+
+    /// World state of every node `time` units after the epoch, following the
+    /// Keplerian orbits. Indexed by `NodeId::index`.
+    pub fn states_at(&self, time: f64) -> Vec<BodyState> {
+        let mut states: Vec<BodyState> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let parent = node.parent.map_or(BodyState::at_rest(Vector3::zero()), |parent| states[parent.0]);
+            states.push(node.body.state_at(time, &parent));
+        }
+        states
+    }
+
+    /// Move every body to where it is `time` units after the epoch
+    pub fn set_time(&mut self, time: f64) {
+        let mut states: Vec<BodyState> = Vec::with_capacity(self.nodes.len());
+        for node in &mut self.nodes {
+            let parent = node.parent.map_or(BodyState::at_rest(Vector3::zero()), |parent| states[parent.0]);
+            states.push(node.body.set_time(time, &parent));
+        }
+    }
+
+    /// Point each body's `orbital_center` at its parent's current position
+    pub fn update_orbital_centers(&mut self) {
+        for index in 0..self.nodes.len() {
+            if let Some(parent) = self.nodes[index].parent {
+                self.nodes[index].body.orbital_center = self.nodes[parent.0].body.position;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orbit::OrbitalElements;
+
+    fn satellite(name: &str, semi_major_axis: f32, mean_motion: f32) -> CelestialBody {
+        CelestialBody::new_moon(
+            name.to_string(),
+            Vector3::zero(),
+            OrbitalElements::circular(semi_major_axis, mean_motion, 0.0),
+            0.1,
+            Color::WHITE,
+            0.0,
+        )
+    }
+
+    #[test]
+    fn test_moons_of_moons_compose_through_every_level() {
+        let mut graph = SceneGraph::new();
+        let star = graph.add_root(CelestialBody::new_star("Star".to_string(), Vector3::new(5.0, 0.0, 0.0), 2.0, Color::YELLOW, 0.0));
+        let planet = graph.add_child(star, satellite("Planet", 40.0, 0.5));
+        let moon = graph.add_child(planet, satellite("Moon", 4.0, 2.0));
+        let submoon = graph.add_child(moon, satellite("Submoon", 0.5, 7.0));
+
+        let time = 1.7;
+        let states = graph.states_at(time);
+        let expected = Vector3::new(5.0, 0.0, 0.0)
+            + graph.body(planet).orbit.unwrap().position_at(time)
+            + graph.body(moon).orbit.unwrap().position_at(time)
+            + graph.body(submoon).orbit.unwrap().position_at(time);
+        assert!((states[submoon.index()].position - expected).length() < 1e-4);
+
+        graph.set_time(time);
+        assert_eq!(graph.body(submoon).position, states[submoon.index()].position);
+        assert_eq!(graph.body(submoon).orbital_center, states[moon.index()].position);
+        assert_eq!(graph.depth(submoon), 3);
+        assert_eq!(graph.descendants(planet), vec![moon, submoon]);
+    }
+
+    #[test]
+    fn test_ids_are_stable_and_parents_come_first() {
+        let mut graph = SceneGraph::new();
+        let first = graph.add_root(CelestialBody::new_star("A".to_string(), Vector3::zero(), 1.0, Color::WHITE, 0.0));
+        let second = graph.add_root(CelestialBody::new_star("B".to_string(), Vector3::zero(), 1.0, Color::WHITE, 0.0));
+        // Children added later don't move the nodes that already exist
+        let child = graph.add_child(first, satellite("A1", 10.0, 1.0));
+        assert_eq!(graph.find("B"), Some(second));
+        assert_eq!(graph.find("A1"), Some(child));
+        assert_eq!(graph.children(first), &[child]);
+        assert_eq!(graph.roots().collect::<Vec<_>>(), vec![first, second]);
+
+        for node in graph.iter() {
+            if let Some(parent) = node.parent {
+                assert!(parent < node.id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_binary_stars_around_a_barycenter() {
+        let mut graph = SceneGraph::new();
+        let barycenter = graph.add_root(CelestialBody::new_barycenter("AB".to_string(), Vector3::zero()));
+        let mut orbit = OrbitalElements::circular(10.0, 0.3, 0.0);
+        let a = graph.add_child(barycenter, CelestialBody::new_orbiting_star("A".to_string(), Vector3::zero(), orbit, 2.0, Color::WHITE, 0.0));
+        orbit.mean_anomaly_at_epoch = std::f32::consts::PI;
+        let b = graph.add_child(barycenter, CelestialBody::new_orbiting_star("B".to_string(), Vector3::zero(), orbit, 2.0, Color::WHITE, 0.0));
+
+        // Always on opposite sides of the barycenter
+        for time in [0.0, 2.5, 11.0] {
+            let states = graph.states_at(time);
+            let midpoint = (states[a.index()].position + states[b.index()].position) * 0.5;
+            assert!(midpoint.length() < 1e-4);
+        }
+        assert_eq!(graph.subtree_mass(barycenter), graph.body(a).mass + graph.body(b).mass);
+    }
+}
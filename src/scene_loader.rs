@@ -0,0 +1,1139 @@
+//! Scene loader - Builds a `SolarSystem` from a TOML scene description
+//!
+//! The format describes the stars, planets, moons and rings. Orbits are
+//! given as Keplerian elements. Bodies form a tree (see `SceneGraph`):
+//! moons nest inside their parent to any depth, and `parent` attaches a star,
+//! planet or barycenter to any other named body. Planets without a `parent`
+//! orbit the first star. Angles are written in degrees, distances
+//! and radii in world units and speeds in radians per simulation time unit.
+//! Masses and the optional `[physics]` table configure the N-body mode.
+//! Any body may override its default shading with a `material` table and
+//! pick a procedural `surface` pattern; stars tint the light they cast with
+//! a `light` table. Textures are loaded relative to the
+//! scene file.
+//! See `assets/systems/default.toml`.
+
+use raylib::prelude::*;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::celestial_body::{CelestialBody, CelestialBodyType};
+use crate::fragment_shader::{Material, SurfacePreset};
+use crate::orbit::OrbitalElements;
+use crate::physics::{PhysicsConfig, SimulationMode};
+use crate::scene_graph::{NodeId, SceneGraph};
+use crate::solar_system::SolarSystem;
+use crate::texture::{Texture, TextureFilter, WrapMode};
+
+#[derive(Debug)]
+pub enum SceneError {
+    /// The scene file could not be read
+    Io { path: String, message: String },
+    /// The file is not valid TOML or does not match the scene format
+    Parse { message: String },
+    /// The scene parsed but contains impossible values
+    Invalid { body: String, message: String },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io { path, message } => write!(f, "cannot read scene '{}': {}", path, message),
+            SceneError::Parse { message } => write!(f, "invalid scene file: {}", message),
+            SceneError::Invalid { body, message } => write!(f, "invalid body '{}': {}", body, message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+// ===== FILE FORMAT =====
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    /// Shorthand for a scene with a single star; goes before `stars`
+    star: Option<StarDef>,
+    #[serde(default)]
+    stars: Vec<StarDef>,
+    #[serde(default)]
+    barycenters: Vec<BarycenterDef>,
+    #[serde(default)]
+    planets: Vec<SatelliteDef>,
+    physics: Option<PhysicsDef>,
+}
+
+impl SceneFile {
+    fn stars(&self) -> impl Iterator<Item = &StarDef> {
+        self.star.iter().chain(&self.stars)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PhysicsDef {
+    /// "kinematic" (default) or "nbody"
+    mode: Option<String>,
+    gravitational_constant: Option<f32>,
+    substep: Option<f32>,
+    softening: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StarDef {
+    name: String,
+    /// Name of the body this star is attached to; roots have none
+    parent: Option<String>,
+    /// Offset from the parent (or the origin) when there is no orbit
+    #[serde(default)]
+    position: [f32; 3],
+    orbit: Option<OrbitDef>,
+    radius: f32,
+    color: ColorDef,
+    #[serde(default)]
+    rotation_speed: f32,
+    /// Degrees between the spin axis and the world up axis
+    #[serde(default)]
+    axial_tilt: f32,
+    /// Defaults to radius³ (unit density)
+    mass: Option<f32>,
+    material: Option<MaterialDef>,
+    /// Procedural surface preset name (see `SurfacePreset::parse`)
+    surface: Option<String>,
+    /// Defaults to a hash of the name
+    surface_seed: Option<u32>,
+    texture: Option<TextureDef>,
+    light: Option<LightDef>,
+}
+
+/// Light a star casts on the rest of the scene
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LightDef {
+    /// Tint of the light; defaults to white
+    color: Option<ColorDef>,
+    /// Relative to the base light; defaults to 1
+    intensity: Option<f32>,
+}
+
+/// Invisible, massless point that other bodies orbit
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BarycenterDef {
+    name: String,
+    parent: Option<String>,
+    /// Offset from the parent (or the origin) when there is no orbit
+    #[serde(default)]
+    position: [f32; 3],
+    orbit: Option<OrbitDef>,
+}
+
+/// A planet, or a moon when nested in `moons`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SatelliteDef {
+    name: String,
+    /// Planets only: name of the body they orbit (defaults to the first star)
+    parent: Option<String>,
+    radius: f32,
+    color: ColorDef,
+    #[serde(default)]
+    rotation_speed: f32,
+    /// Degrees between the spin axis and the world up axis
+    #[serde(default)]
+    axial_tilt: f32,
+    mass: Option<f32>,
+    material: Option<MaterialDef>,
+    /// Procedural surface preset name (see `SurfacePreset::parse`)
+    surface: Option<String>,
+    /// Defaults to a hash of the name
+    surface_seed: Option<u32>,
+    texture: Option<TextureDef>,
+    orbit: OrbitDef,
+    rings: Option<RingDef>,
+    #[serde(default)]
+    moons: Vec<SatelliteDef>,
+}
+
+/// Keplerian elements; angles in degrees
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OrbitDef {
+    semi_major_axis: f32,
+    #[serde(default)]
+    eccentricity: f32,
+    #[serde(default)]
+    inclination: f32,
+    #[serde(default)]
+    longitude_of_ascending_node: f32,
+    #[serde(default)]
+    argument_of_periapsis: f32,
+    /// Mean anomaly at epoch
+    #[serde(default)]
+    mean_anomaly: f32,
+    /// Radians per time unit
+    mean_motion: f32,
+}
+
+impl OrbitDef {
+    fn to_elements(&self) -> OrbitalElements {
+        OrbitalElements {
+            semi_major_axis: self.semi_major_axis,
+            eccentricity: self.eccentricity,
+            inclination: self.inclination.to_radians(),
+            longitude_of_ascending_node: self.longitude_of_ascending_node.to_radians(),
+            argument_of_periapsis: self.argument_of_periapsis.to_radians(),
+            mean_anomaly_at_epoch: self.mean_anomaly.to_radians(),
+            mean_motion: self.mean_motion,
+        }
+    }
+}
+
+/// Equirectangular albedo map
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TextureDef {
+    /// Relative to the scene file
+    path: String,
+    /// "nearest", "bilinear" or "trilinear" (default)
+    filter: Option<String>,
+    /// "repeat", "mirror" or "clamp"; defaults to repeat in u and clamp in v
+    wrap: Option<String>,
+}
+
+/// Surface shading; omitted fields take the `Material` defaults
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum MaterialDef {
+    Lambert,
+    Phong {
+        #[serde(default = "default_specular")]
+        specular: f32,
+        #[serde(default = "default_shininess")]
+        shininess: f32,
+    },
+    Emissive {
+        #[serde(default = "default_limb_darkening")]
+        limb_darkening: f32,
+        #[serde(default = "default_corona")]
+        corona: f32,
+    },
+}
+
+fn default_specular() -> f32 {
+    0.5
+}
+
+fn default_shininess() -> f32 {
+    32.0
+}
+
+fn default_limb_darkening() -> f32 {
+    Material::DEFAULT_LIMB_DARKENING
+}
+
+fn default_corona() -> f32 {
+    Material::DEFAULT_CORONA
+}
+
+impl MaterialDef {
+    fn to_material(&self) -> Material {
+        match *self {
+            MaterialDef::Lambert => Material::Lambert,
+            MaterialDef::Phong { specular, shininess } => Material::Phong { specular, shininess },
+            MaterialDef::Emissive { limb_darkening, corona } => Material::Emissive { limb_darkening, corona },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RingDef {
+    inner_radius: f32,
+    outer_radius: f32,
+    color: ColorDef,
+}
+
+/// `[r, g, b]` or `[r, g, b, a]`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ColorDef {
+    Rgb([u8; 3]),
+    Rgba([u8; 4]),
+}
+
+impl ColorDef {
+    fn to_color(&self) -> Color {
+        match *self {
+            ColorDef::Rgb([r, g, b]) => Color::new(r, g, b, 255),
+            ColorDef::Rgba([r, g, b, a]) => Color::new(r, g, b, a),
+        }
+    }
+}
+
+// ===== LOADING =====
+
+pub fn load_from_file(path: &str) -> Result<SolarSystem, SceneError> {
+    let source = std::fs::read_to_string(path).map_err(|e| SceneError::Io {
+        path: path.to_string(),
+        message: e.to_string(),
+    })?;
+    load(&source, Path::new(path).parent().unwrap_or(Path::new("")))
+}
+
+/// Texture paths are resolved against the working directory
+pub fn load_from_str(source: &str) -> Result<SolarSystem, SceneError> {
+    load(source, Path::new(""))
+}
+
+fn load(source: &str, base_dir: &Path) -> Result<SolarSystem, SceneError> {
+    let scene: SceneFile = toml::from_str(source).map_err(|e| SceneError::Parse {
+        message: e.to_string(),
+    })?;
+    validate(&scene)?;
+    let textures = load_textures(&scene, base_dir)?;
+    Ok(build(&scene, &textures))
+}
+
+fn invalid(body: &str, message: impl Into<String>) -> SceneError {
+    SceneError::Invalid {
+        body: body.to_string(),
+        message: message.into(),
+    }
+}
+
+fn require_positive(body: &str, field: &str, value: f32) -> Result<(), SceneError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(invalid(body, format!("{} must be a positive number, got {}", field, value)))
+    }
+}
+
+fn require_finite(body: &str, field: &str, value: f32) -> Result<(), SceneError> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(invalid(body, format!("{} must be a finite number", field)))
+    }
+}
+
+fn validate_orbit(body: &str, orbit: &OrbitDef) -> Result<(), SceneError> {
+    require_positive(body, "orbit.semi_major_axis", orbit.semi_major_axis)?;
+    if !(0.0..1.0).contains(&orbit.eccentricity) {
+        return Err(invalid(
+            body,
+            format!("orbit.eccentricity must be in [0, 1), got {}", orbit.eccentricity),
+        ));
+    }
+    require_finite(body, "orbit.inclination", orbit.inclination)?;
+    require_finite(body, "orbit.longitude_of_ascending_node", orbit.longitude_of_ascending_node)?;
+    require_finite(body, "orbit.argument_of_periapsis", orbit.argument_of_periapsis)?;
+    require_finite(body, "orbit.mean_anomaly", orbit.mean_anomaly)?;
+    require_finite(body, "orbit.mean_motion", orbit.mean_motion)
+}
+
+fn validate_mass(body: &str, mass: Option<f32>) -> Result<(), SceneError> {
+    match mass {
+        Some(mass) => require_positive(body, "mass", mass),
+        None => Ok(()),
+    }
+}
+
+fn validate_material(body: &str, material: &Option<MaterialDef>) -> Result<(), SceneError> {
+    match material {
+        Some(MaterialDef::Phong { specular, shininess }) => {
+            require_finite(body, "material.specular", *specular)?;
+            if *specular < 0.0 {
+                return Err(invalid(body, "material.specular cannot be negative"));
+            }
+            require_positive(body, "material.shininess", *shininess)
+        }
+        Some(MaterialDef::Emissive { limb_darkening, corona }) => {
+            if !(0.0..=1.0).contains(limb_darkening) {
+                return Err(invalid(
+                    body,
+                    format!("material.limb_darkening must be in [0, 1], got {}", limb_darkening),
+                ));
+            }
+            require_finite(body, "material.corona", *corona)?;
+            if *corona < 0.0 {
+                return Err(invalid(body, "material.corona cannot be negative"));
+            }
+            Ok(())
+        }
+        Some(MaterialDef::Lambert) | None => Ok(()),
+    }
+}
+
+fn validate_surface(body: &str, surface: &Option<String>) -> Result<(), SceneError> {
+    match surface {
+        Some(name) if SurfacePreset::parse(name).is_none() => Err(invalid(
+            body,
+            format!(
+                "surface must be one of \"flat\", \"gas_giant\", \"rocky\", \"ice_caps\", \"continents\" or \"lava\", got \"{}\"",
+                name
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn apply_surface(body: &mut CelestialBody, surface: &Option<String>, surface_seed: Option<u32>) {
+    if let Some(preset) = surface.as_deref().and_then(SurfacePreset::parse) {
+        body.surface = preset;
+    }
+    if let Some(seed) = surface_seed {
+        body.surface_seed = seed;
+    }
+}
+
+fn validate_texture(body: &str, texture: &Option<TextureDef>) -> Result<(), SceneError> {
+    let Some(texture) = texture else {
+        return Ok(());
+    };
+    if texture.path.trim().is_empty() {
+        return Err(invalid(body, "texture.path cannot be empty"));
+    }
+    if let Some(filter) = &texture.filter {
+        if TextureFilter::parse(filter).is_none() {
+            return Err(invalid(
+                body,
+                format!("texture.filter must be \"nearest\", \"bilinear\" or \"trilinear\", got \"{}\"", filter),
+            ));
+        }
+    }
+    if let Some(wrap) = &texture.wrap {
+        if WrapMode::parse(wrap).is_none() {
+            return Err(invalid(
+                body,
+                format!("texture.wrap must be \"repeat\", \"mirror\" or \"clamp\", got \"{}\"", wrap),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn load_texture(body: &str, def: &TextureDef, base_dir: &Path) -> Result<Arc<Texture>, SceneError> {
+    let path = base_dir.join(&def.path);
+    let mut texture = Texture::load(&path.to_string_lossy())
+        .map_err(|e| invalid(body, format!("cannot load texture: {}", e)))?;
+    if let Some(filter) = def.filter.as_deref().and_then(TextureFilter::parse) {
+        texture.filter = filter;
+    }
+    if let Some(wrap) = def.wrap.as_deref().and_then(WrapMode::parse) {
+        texture.wrap_u = wrap;
+        texture.wrap_v = wrap;
+    }
+    Ok(Arc::new(texture))
+}
+
+/// Loads every texture in the scene, keyed by body name
+fn load_textures(scene: &SceneFile, base_dir: &Path) -> Result<HashMap<String, Arc<Texture>>, SceneError> {
+    let mut bodies: Vec<_> = scene.stars().map(|star| (&star.name, &star.texture)).collect();
+    bodies.extend(satellites(scene).into_iter().map(|(satellite, _)| (&satellite.name, &satellite.texture)));
+
+    let mut textures = HashMap::new();
+    for (name, def) in bodies {
+        if let Some(def) = def {
+            textures.insert(name.clone(), load_texture(name, def, base_dir)?);
+        }
+    }
+    Ok(textures)
+}
+
+/// Every planet and moon, depth-first, with the satellite each moon is
+/// nested in
+fn satellites(scene: &SceneFile) -> Vec<(&SatelliteDef, Option<&SatelliteDef>)> {
+    fn visit<'a>(
+        satellite: &'a SatelliteDef,
+        nested_in: Option<&'a SatelliteDef>,
+        out: &mut Vec<(&'a SatelliteDef, Option<&'a SatelliteDef>)>,
+    ) {
+        out.push((satellite, nested_in));
+        for moon in &satellite.moons {
+            visit(moon, Some(satellite), out);
+        }
+    }
+
+    let mut out = Vec::new();
+    for planet in &scene.planets {
+        visit(planet, None, &mut out);
+    }
+    out
+}
+
+/// A body listed at the top level of the scene, which may name its parent
+#[derive(Clone, Copy)]
+enum TopLevel<'a> {
+    Star(&'a StarDef),
+    Barycenter(&'a BarycenterDef),
+    Planet(&'a SatelliteDef),
+}
+
+impl<'a> TopLevel<'a> {
+    fn all(scene: &'a SceneFile) -> Vec<TopLevel<'a>> {
+        scene
+            .stars()
+            .map(TopLevel::Star)
+            .chain(scene.barycenters.iter().map(TopLevel::Barycenter))
+            .chain(scene.planets.iter().map(TopLevel::Planet))
+            .collect()
+    }
+
+    fn name(&self) -> &'a str {
+        match self {
+            TopLevel::Star(star) => &star.name,
+            TopLevel::Barycenter(barycenter) => &barycenter.name,
+            TopLevel::Planet(planet) => &planet.name,
+        }
+    }
+
+    fn parent(&self, scene: &'a SceneFile) -> Option<&'a str> {
+        match self {
+            TopLevel::Star(star) => star.parent.as_deref(),
+            TopLevel::Barycenter(barycenter) => barycenter.parent.as_deref(),
+            TopLevel::Planet(planet) => planet
+                .parent
+                .as_deref()
+                .or_else(|| scene.stars().next().map(|star| star.name.as_str())),
+        }
+    }
+}
+
+/// Top-level bodies in an order where every parent comes before the bodies
+/// attached to it. Bodies keep the file order (stars, barycenters, planets)
+/// unless their parent comes later.
+fn placement_order(scene: &SceneFile) -> Result<Vec<TopLevel<'_>>, SceneError> {
+    let mut pending = TopLevel::all(scene);
+    let mut placed: HashSet<&str> = HashSet::new();
+    let mut order = Vec::new();
+
+    while !pending.is_empty() {
+        let mut waiting = Vec::new();
+        for entry in &pending {
+            match entry.parent(scene) {
+                Some(parent) if !placed.contains(parent) => waiting.push(*entry),
+                _ => {
+                    placed.insert(entry.name());
+                    if let TopLevel::Planet(planet) = entry {
+                        let mut moons: Vec<&SatelliteDef> = planet.moons.iter().collect();
+                        while let Some(moon) = moons.pop() {
+                            placed.insert(&moon.name);
+                            moons.extend(&moon.moons);
+                        }
+                    }
+                    order.push(*entry);
+                }
+            }
+        }
+
+        if waiting.len() == pending.len() {
+            let entry = waiting[0];
+            return Err(invalid(
+                entry.name(),
+                format!("parent '{}' is attached back to this body", entry.parent(scene).unwrap_or_default()),
+            ));
+        }
+        pending = waiting;
+    }
+    Ok(order)
+}
+
+fn parse_mode(mode: &str) -> Option<SimulationMode> {
+    match mode {
+        "kinematic" => Some(SimulationMode::Kinematic),
+        "nbody" => Some(SimulationMode::NBody),
+        _ => None,
+    }
+}
+
+fn validate_physics(physics: &PhysicsDef) -> Result<(), SceneError> {
+    if let Some(mode) = &physics.mode {
+        if parse_mode(mode).is_none() {
+            return Err(invalid("physics", format!("mode must be \"kinematic\" or \"nbody\", got \"{}\"", mode)));
+        }
+    }
+    if let Some(g) = physics.gravitational_constant {
+        require_positive("physics", "gravitational_constant", g)?;
+    }
+    if let Some(substep) = physics.substep {
+        require_positive("physics", "substep", substep)?;
+    }
+    if let Some(softening) = physics.softening {
+        require_finite("physics", "softening", softening)?;
+        if softening < 0.0 {
+            return Err(invalid("physics", "softening cannot be negative"));
+        }
+    }
+    Ok(())
+}
+
+fn validate_star(star: &StarDef) -> Result<(), SceneError> {
+    require_positive(&star.name, "radius", star.radius)?;
+    require_finite(&star.name, "rotation_speed", star.rotation_speed)?;
+    require_finite(&star.name, "axial_tilt", star.axial_tilt)?;
+    validate_mass(&star.name, star.mass)?;
+    validate_material(&star.name, &star.material)?;
+    validate_surface(&star.name, &star.surface)?;
+    validate_texture(&star.name, &star.texture)?;
+    for value in star.position {
+        require_finite(&star.name, "position", value)?;
+    }
+    if let Some(intensity) = star.light.as_ref().and_then(|light| light.intensity) {
+        require_finite(&star.name, "light.intensity", intensity)?;
+        if intensity < 0.0 {
+            return Err(invalid(&star.name, "light.intensity cannot be negative"));
+        }
+    }
+    match &star.orbit {
+        Some(orbit) => validate_orbit(&star.name, orbit),
+        None => Ok(()),
+    }
+}
+
+fn validate_barycenter(barycenter: &BarycenterDef) -> Result<(), SceneError> {
+    for value in barycenter.position {
+        require_finite(&barycenter.name, "position", value)?;
+    }
+    match &barycenter.orbit {
+        Some(orbit) => validate_orbit(&barycenter.name, orbit),
+        None => Ok(()),
+    }
+}
+
+fn validate_satellite(satellite: &SatelliteDef, nested: bool) -> Result<(), SceneError> {
+    require_positive(&satellite.name, "radius", satellite.radius)?;
+    require_finite(&satellite.name, "rotation_speed", satellite.rotation_speed)?;
+    require_finite(&satellite.name, "axial_tilt", satellite.axial_tilt)?;
+    validate_mass(&satellite.name, satellite.mass)?;
+    validate_material(&satellite.name, &satellite.material)?;
+    validate_surface(&satellite.name, &satellite.surface)?;
+    validate_texture(&satellite.name, &satellite.texture)?;
+    validate_orbit(&satellite.name, &satellite.orbit)?;
+
+    if nested && satellite.parent.is_some() {
+        return Err(invalid(&satellite.name, "moons orbit the body they are nested in and cannot set a parent"));
+    }
+
+    if let Some(rings) = &satellite.rings {
+        require_positive(&satellite.name, "rings.inner_radius", rings.inner_radius)?;
+        require_positive(&satellite.name, "rings.outer_radius", rings.outer_radius)?;
+        if rings.inner_radius >= rings.outer_radius {
+            return Err(invalid(&satellite.name, "rings.inner_radius must be smaller than rings.outer_radius"));
+        }
+    }
+    Ok(())
+}
+
+fn validate(scene: &SceneFile) -> Result<(), SceneError> {
+    let mut names = HashSet::new();
+    let mut check_name = |name: &str| {
+        if name.trim().is_empty() {
+            return Err(invalid(name, "name cannot be empty"));
+        }
+        if !names.insert(name.to_string()) {
+            return Err(invalid(name, "name is used by more than one body"));
+        }
+        Ok(())
+    };
+
+    if scene.stars().next().is_none() {
+        return Err(invalid("scene", "the scene needs at least one star"));
+    }
+
+    // Kind and radius of every body, to check the orbits around it
+    let mut bodies: HashMap<&str, (&str, f32)> = HashMap::new();
+    for star in scene.stars() {
+        check_name(&star.name)?;
+        validate_star(star)?;
+        bodies.insert(&star.name, ("star", star.radius));
+    }
+    for barycenter in &scene.barycenters {
+        check_name(&barycenter.name)?;
+        validate_barycenter(barycenter)?;
+        bodies.insert(&barycenter.name, ("barycenter", 0.0));
+    }
+    let satellites = satellites(scene);
+    for (satellite, nested_in) in &satellites {
+        check_name(&satellite.name)?;
+        validate_satellite(satellite, nested_in.is_some())?;
+        let kind = if nested_in.is_some() { "moon" } else { "planet" };
+        bodies.insert(&satellite.name, (kind, satellite.radius));
+    }
+
+    // Every parent exists and no orbit dips into it
+    let mut orbits: Vec<(&str, f32, Option<&OrbitDef>, &str)> = Vec::new();
+    for entry in TopLevel::all(scene) {
+        let Some(parent) = entry.parent(scene) else {
+            continue;
+        };
+        let (radius, orbit) = match entry {
+            TopLevel::Star(star) => (star.radius, star.orbit.as_ref()),
+            TopLevel::Barycenter(barycenter) => (0.0, barycenter.orbit.as_ref()),
+            TopLevel::Planet(planet) => (planet.radius, Some(&planet.orbit)),
+        };
+        orbits.push((entry.name(), radius, orbit, parent));
+    }
+    for (satellite, nested_in) in &satellites {
+        if let Some(parent) = nested_in {
+            orbits.push((&satellite.name, satellite.radius, Some(&satellite.orbit), &parent.name));
+        }
+    }
+    for (name, radius, orbit, parent) in orbits {
+        let Some(&(kind, parent_radius)) = bodies.get(parent) else {
+            return Err(invalid(name, format!("parent '{}' is not a body in the scene", parent)));
+        };
+        if let Some(orbit) = orbit {
+            let periapsis = orbit.to_elements().periapsis();
+            if periapsis <= parent_radius + radius {
+                return Err(invalid(
+                    name,
+                    format!("periapsis {} is inside the {} '{}'", periapsis, kind, parent),
+                ));
+            }
+        }
+    }
+    placement_order(scene)?;
+
+    if let Some(physics) = &scene.physics {
+        validate_physics(physics)?;
+    }
+
+    Ok(())
+}
+
+fn to_vector(position: [f32; 3]) -> Vector3 {
+    Vector3::new(position[0], position[1], position[2])
+}
+
+fn build_star(star_def: &StarDef, textures: &HashMap<String, Arc<Texture>>) -> CelestialBody {
+    let mut star = match &star_def.orbit {
+        Some(orbit) => CelestialBody::new_orbiting_star(
+            star_def.name.clone(),
+            Vector3::zero(),
+            orbit.to_elements(),
+            star_def.radius,
+            star_def.color.to_color(),
+            star_def.rotation_speed,
+        ),
+        None => CelestialBody::new_star(
+            star_def.name.clone(),
+            to_vector(star_def.position),
+            star_def.radius,
+            star_def.color.to_color(),
+            star_def.rotation_speed,
+        ),
+    };
+    star.axial_tilt = star_def.axial_tilt.to_radians();
+    if let Some(mass) = star_def.mass {
+        star.mass = mass;
+    }
+    if let Some(material) = &star_def.material {
+        star.material = material.to_material();
+    }
+    apply_surface(&mut star, &star_def.surface, star_def.surface_seed);
+    star.texture = textures.get(&star_def.name).cloned();
+    if let Some(light) = &star_def.light {
+        if let Some(color) = &light.color {
+            star.light_color = color.to_color();
+        }
+        if let Some(intensity) = light.intensity {
+            star.light_intensity = intensity;
+        }
+    }
+    star
+}
+
+fn build_barycenter(barycenter_def: &BarycenterDef) -> CelestialBody {
+    let mut barycenter = CelestialBody::new_barycenter(barycenter_def.name.clone(), to_vector(barycenter_def.position));
+    if let Some(orbit) = &barycenter_def.orbit {
+        barycenter.orbit = Some(orbit.to_elements());
+        barycenter.offset = Vector3::zero();
+    }
+    barycenter
+}
+
+/// Adds a planet or moon under `parent`, followed by its own moons
+fn add_satellite(
+    graph: &mut SceneGraph,
+    parent: Option<NodeId>,
+    def: &SatelliteDef,
+    body_type: CelestialBodyType,
+    textures: &HashMap<String, Arc<Texture>>,
+) {
+    let build = match body_type {
+        CelestialBodyType::Planet => CelestialBody::new_planet,
+        _ => CelestialBody::new_moon,
+    };
+    let mut body = build(
+        def.name.clone(),
+        Vector3::zero(),
+        def.orbit.to_elements(),
+        def.radius,
+        def.color.to_color(),
+        def.rotation_speed,
+    );
+    body.axial_tilt = def.axial_tilt.to_radians();
+    if let Some(mass) = def.mass {
+        body.mass = mass;
+    }
+    if let Some(material) = &def.material {
+        body.material = material.to_material();
+    }
+    apply_surface(&mut body, &def.surface, def.surface_seed);
+    body.texture = textures.get(&def.name).cloned();
+
+    if let Some(rings) = &def.rings {
+        body.add_rings(rings.inner_radius, rings.outer_radius, rings.color.to_color());
+    }
+
+    let id = match parent {
+        Some(parent) => graph.add_child(parent, body),
+        None => graph.add_root(body),
+    };
+    for moon in &def.moons {
+        add_satellite(graph, Some(id), moon, CelestialBodyType::Moon, textures);
+    }
+}
+
+fn build(scene: &SceneFile, textures: &HashMap<String, Arc<Texture>>) -> SolarSystem {
+    let mut graph = SceneGraph::new();
+    let order = placement_order(scene).expect("validated scene has no parent loops");
+    for entry in order {
+        let parent = entry.parent(scene).and_then(|name| graph.find(name));
+        match entry {
+            TopLevel::Planet(planet) => add_satellite(&mut graph, parent, planet, CelestialBodyType::Planet, textures),
+            TopLevel::Star(star) => {
+                let body = build_star(star, textures);
+                match parent {
+                    Some(parent) => graph.add_child(parent, body),
+                    None => graph.add_root(body),
+                };
+            }
+            TopLevel::Barycenter(barycenter) => {
+                let body = build_barycenter(barycenter);
+                match parent {
+                    Some(parent) => graph.add_child(parent, body),
+                    None => graph.add_root(body),
+                };
+            }
+        }
+    }
+
+    let mut system = SolarSystem::with_graph(graph);
+
+    if let Some(physics_def) = &scene.physics {
+        let defaults = PhysicsConfig::default();
+        system.physics = PhysicsConfig {
+            gravitational_constant: physics_def.gravitational_constant.unwrap_or(defaults.gravitational_constant),
+            substep: physics_def.substep.unwrap_or(defaults.substep),
+            softening: physics_def.softening.unwrap_or(defaults.softening),
+            ..defaults
+        };
+        if let Some(mode) = physics_def.mode.as_deref().and_then(parse_mode) {
+            system.set_simulation_mode(mode);
+        }
+    }
+
+    system
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framebuffer::Framebuffer;
+
+    const MINIMAL: &str = r#"
+        [star]
+        name = "Sol"
+        radius = 10.0
+        color = [255, 255, 0]
+
+        [[planets]]
+        name = "Uno"
+        radius = 2.0
+        color = [10, 20, 30, 200]
+
+        [planets.orbit]
+        semi_major_axis = 50.0
+        mean_motion = 1.0
+        mean_anomaly = 90.0
+
+        [[planets.moons]]
+        name = "Luna"
+        radius = 0.5
+        color = [200, 200, 200]
+
+        [planets.moons.orbit]
+        semi_major_axis = 5.0
+        mean_motion = 3.0
+    "#;
+
+    #[test]
+    fn test_load_minimal_scene() {
+        let system = load_from_str(MINIMAL).unwrap();
+        assert_eq!(system.stars()[0].name, "Sol");
+        assert_eq!(system.planets().len(), 1);
+
+        let planet = system.planets()[0];
+        assert_eq!(planet.color.a, 200);
+        let moons = system.graph.children(system.graph.find("Uno").unwrap());
+        assert_eq!(moons.len(), 1);
+        let moon = system.graph.body(moons[0]);
+        // Position must match the mean anomaly at epoch (90°)
+        assert!(planet.position.x.abs() < 1e-3);
+        assert!((planet.position.z - 50.0).abs() < 1e-3);
+        // The moon orbits around the planet's initial position
+        assert_eq!(moon.orbital_center, planet.position);
+    }
+
+    #[test]
+    fn test_axial_tilt_is_read_in_degrees() {
+        let source = MINIMAL.replace("color = [10, 20, 30, 200]", "color = [10, 20, 30, 200]\n        axial_tilt = 90.0");
+        let system = load_from_str(&source).unwrap();
+        let planet = system.planets()[0];
+        assert!((planet.axial_tilt - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(system.body("Luna").unwrap().axial_tilt, 0.0);
+    }
+
+    #[test]
+    fn test_default_scene_is_valid() {
+        let system = load_from_file("assets/systems/default.toml").unwrap();
+        assert_eq!(system.planets().len(), 5);
+        assert_eq!(system.get_total_body_count(), 9);
+    }
+
+    #[test]
+    fn test_rejects_unknown_field() {
+        let source = MINIMAL.replace("mean_motion = 3.0", "mean_moton = 3.0");
+        assert!(matches!(load_from_str(&source), Err(SceneError::Parse { .. })));
+    }
+
+    #[test]
+    fn test_rejects_moon_inside_planet() {
+        let source = MINIMAL.replace("semi_major_axis = 5.0", "semi_major_axis = 1.0");
+        match load_from_str(&source) {
+            Err(SceneError::Invalid { body, .. }) => assert_eq!(body, "Luna"),
+            other => panic!("expected validation error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_rejects_open_orbit() {
+        let source = MINIMAL.replace("mean_anomaly = 90.0", "mean_anomaly = 90.0\n        eccentricity = 1.0");
+        match load_from_str(&source) {
+            Err(SceneError::Invalid { body, message }) => {
+                assert_eq!(body, "Uno");
+                assert!(message.contains("eccentricity"));
+            }
+            other => panic!("expected validation error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_physics_table() {
+        let source = format!(
+            "{}\n[physics]\nmode = \"nbody\"\ngravitational_constant = 2.5\n",
+            MINIMAL.replace("radius = 10.0", "radius = 10.0\n        mass = 5000.0")
+        );
+        let system = load_from_str(&source).unwrap();
+        assert_eq!(system.simulation_mode(), SimulationMode::NBody);
+        assert_eq!(system.physics.gravitational_constant, 2.5);
+        assert_eq!(system.stars()[0].mass, 5000.0);
+
+        let bad = format!("{}\n[physics]\nmode = \"magic\"\n", MINIMAL);
+        assert!(matches!(load_from_str(&bad), Err(SceneError::Invalid { .. })));
+    }
+
+    #[test]
+    fn test_material_tables() {
+        let system = load_from_str(MINIMAL).unwrap();
+        assert!(matches!(system.stars()[0].material, Material::Emissive { .. }));
+        assert_eq!(system.planets()[0].material, Material::Lambert);
+
+        let source = MINIMAL
+            .replace("color = [255, 255, 0]", "color = [255, 255, 0]\n        material = { type = \"emissive\", corona = 0.0 }")
+            .replace("color = [10, 20, 30, 200]", "color = [10, 20, 30, 200]\n        material = { type = \"phong\", shininess = 8.0 }");
+        let system = load_from_str(&source).unwrap();
+        assert_eq!(
+            system.stars()[0].material,
+            Material::Emissive { limb_darkening: Material::DEFAULT_LIMB_DARKENING, corona: 0.0 }
+        );
+        assert_eq!(system.planets()[0].material, Material::Phong { specular: 0.5, shininess: 8.0 });
+
+        let bad = MINIMAL.replace("color = [255, 255, 0]", "color = [255, 255, 0]\n        material = { type = \"emissive\", limb_darkening = 2.0 }");
+        assert!(matches!(load_from_str(&bad), Err(SceneError::Invalid { .. })));
+        let unknown = MINIMAL.replace("color = [255, 255, 0]", "color = [255, 255, 0]\n        material = { type = \"chrome\" }");
+        assert!(matches!(load_from_str(&unknown), Err(SceneError::Parse { .. })));
+    }
+
+    #[test]
+    fn test_surface_presets() {
+        let system = load_from_str(MINIMAL).unwrap();
+        assert_eq!(system.planets()[0].surface, SurfacePreset::Flat);
+        // Without an explicit seed, bodies get different patterns
+        assert_ne!(system.planets()[0].surface_seed, system.body("Luna").unwrap().surface_seed);
+
+        let source = MINIMAL.replace(
+            "color = [10, 20, 30, 200]",
+            "color = [10, 20, 30, 200]\n        surface = \"gas_giant\"\n        surface_seed = 7",
+        );
+        let system = load_from_str(&source).unwrap();
+        assert_eq!(system.planets()[0].surface, SurfacePreset::GasGiant);
+        assert_eq!(system.planets()[0].surface_seed, 7);
+
+        let bad = MINIMAL.replace("color = [200, 200, 200]", "color = [200, 200, 200]\n        surface = \"plasma\"");
+        match load_from_str(&bad) {
+            Err(SceneError::Invalid { body, message }) => {
+                assert_eq!(body, "Luna");
+                assert!(message.contains("plasma"));
+            }
+            other => panic!("expected validation error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_texture_tables() {
+        let path = std::env::temp_dir().join("scene_loader_test_texture.ppm");
+        Framebuffer::new(4, 2).save_ppm(&path.to_string_lossy()).unwrap();
+
+        let source = MINIMAL.replace(
+            "color = [10, 20, 30, 200]",
+            &format!(
+                "color = [10, 20, 30, 200]\n        texture = {{ path = {:?}, filter = \"nearest\" }}",
+                path.to_string_lossy()
+            ),
+        );
+        let system = load_from_str(&source).unwrap();
+        let texture = system.planets()[0].texture.as_ref().unwrap();
+        assert_eq!((texture.width(), texture.height()), (4, 2));
+        assert_eq!(texture.filter, TextureFilter::Nearest);
+        assert!(system.stars()[0].texture.is_none());
+
+        let missing = MINIMAL.replace(
+            "color = [200, 200, 200]",
+            "color = [200, 200, 200]\n        texture = { path = \"no_such_texture.png\" }",
+        );
+        match load_from_str(&missing) {
+            Err(SceneError::Invalid { body, message }) => {
+                assert_eq!(body, "Luna");
+                assert!(message.contains("no_such_texture.png"));
+            }
+            other => panic!("expected texture error, got {:?}", other.map(|_| ())),
+        }
+
+        let bad_filter = source.replace("\"nearest\"", "\"anisotropic\"");
+        assert!(matches!(load_from_str(&bad_filter), Err(SceneError::Invalid { .. })));
+    }
+
+    #[test]
+    fn test_rejects_duplicate_names() {
+        let source = MINIMAL.replace("name = \"Luna\"", "name = \"Uno\"");
+        assert!(matches!(load_from_str(&source), Err(SceneError::Invalid { .. })));
+    }
+
+    #[test]
+    fn test_star_light_table() {
+        let system = load_from_str(MINIMAL).unwrap();
+        assert_eq!(system.stars()[0].light_color, Color::WHITE);
+        assert_eq!(system.stars()[0].light_intensity, 1.0);
+
+        let source = MINIMAL.replace(
+            "color = [255, 255, 0]",
+            "color = [255, 255, 0]\n        light = { color = [255, 180, 120], intensity = 0.6 }",
+        );
+        let system = load_from_str(&source).unwrap();
+        assert_eq!(system.stars()[0].light_color, Color::new(255, 180, 120, 255));
+        assert_eq!(system.stars()[0].light_intensity, 0.6);
+
+        let negative = source.replace("intensity = 0.6", "intensity = -1.0");
+        assert!(matches!(load_from_str(&negative), Err(SceneError::Invalid { .. })));
+    }
+
+    #[test]
+    fn test_moons_of_moons_nest_to_any_depth() {
+        let source = format!(
+            "{}\n{}",
+            MINIMAL,
+            r#"
+        [[planets.moons.moons]]
+        name = "Lunita"
+        radius = 0.1
+        color = [150, 150, 150]
+        orbit = { semi_major_axis = 1.0, mean_motion = 9.0 }
+            "#
+        );
+        let system = load_from_str(&source).unwrap();
+        let id = system.graph.find("Lunita").unwrap();
+        assert_eq!(system.graph.depth(id), 3);
+        assert_eq!(system.graph.parent(id), system.graph.find("Luna"));
+        let (moon, submoon) = (system.body("Luna").unwrap(), system.body("Lunita").unwrap());
+        assert_eq!(submoon.orbital_center, moon.position);
+        assert_eq!(system.get_total_body_count(), 4);
+
+        let inside = source.replace("semi_major_axis = 1.0", "semi_major_axis = 0.5");
+        match load_from_str(&inside) {
+            Err(SceneError::Invalid { body, message }) => {
+                assert_eq!(body, "Lunita");
+                assert!(message.contains("inside the moon 'Luna'"));
+            }
+            other => panic!("expected validation error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_parents_and_barycenters() {
+        // Listed before the barycenter it orbits: placed after it anyway
+        let source = r#"
+            [[stars]]
+            name = "A"
+            parent = "AB"
+            radius = 2.0
+            color = [255, 255, 255]
+            orbit = { semi_major_axis = 10.0, mean_motion = 0.5 }
+
+            [[stars]]
+            name = "B"
+            parent = "AB"
+            radius = 2.0
+            color = [255, 200, 200]
+            orbit = { semi_major_axis = 10.0, mean_motion = 0.5, mean_anomaly = 180.0 }
+
+            [[barycenters]]
+            name = "AB"
+            position = [0.0, 5.0, 0.0]
+
+            [[planets]]
+            name = "Tight"
+            parent = "B"
+            radius = 0.5
+            color = [0, 0, 255]
+            orbit = { semi_major_axis = 4.0, mean_motion = 2.0 }
+        "#;
+        let system = load_from_str(source).unwrap();
+        let (a, b) = (system.body("A").unwrap(), system.body("B").unwrap());
+        assert!(((a.position + b.position) * 0.5 - Vector3::new(0.0, 5.0, 0.0)).length() < 1e-4);
+        assert_eq!(system.body("Tight").unwrap().orbital_center, b.position);
+        assert_eq!(system.stars().len(), 2);
+
+        let unknown = source.replace("parent = \"B\"", "parent = \"C\"");
+        match load_from_str(&unknown) {
+            Err(SceneError::Invalid { body, message }) => {
+                assert_eq!(body, "Tight");
+                assert!(message.contains("'C'"));
+            }
+            other => panic!("expected validation error, got {:?}", other.map(|_| ())),
+        }
+
+        let looped = source.replace("position = [0.0, 5.0, 0.0]", "parent = \"A\"");
+        assert!(matches!(load_from_str(&looped), Err(SceneError::Invalid { .. })));
+
+        let no_star = "[[barycenters]]\nname = \"Empty\"\n";
+        assert!(matches!(load_from_str(no_star), Err(SceneError::Invalid { .. })));
+    }
+}
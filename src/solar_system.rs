@@ -1,238 +1,357 @@
 #![allow(dead_code)]
 use raylib::prelude::*;
-use crate::celestial_body::CelestialBody;
-use std::f32::consts::PI;
+use crate::celestial_body::{BodyState, CelestialBody, CelestialBodyType};
+use crate::physics::{self, Diagnostics, Integrator, PhysicsConfig, PointMass, SimulationMode};
+use crate::scene_graph::{NodeId, SceneGraph};
+use crate::scene_loader::{self, SceneError};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const DEFAULT_SYSTEM: &str = include_str!("../assets/systems/default.toml");
+
+// Source of `SolarSystem::scene_id`
+static NEXT_SCENE_ID: AtomicU64 = AtomicU64::new(0);
 
 pub struct SolarSystem {
-    pub star: CelestialBody,
-    pub planets: Vec<CelestialBody>,
-    pub time_scale: f32,
+    /// Every body, from the stars down to the moons of moons
+    pub graph: SceneGraph,
+    /// Different for every system built, so caches keyed by `NodeId` can
+    /// tell when the scene was replaced
+    scene_id: u64,
+    /// Time units since the epoch of the current state
+    time: f64,
+
+    // Physics
+    pub physics: PhysicsConfig,
+    mode: SimulationMode,
+    integrator: Integrator,
+    // Conserved quantities when the N-body run started
+    initial_diagnostics: Option<Diagnostics>,
 }
 
 impl SolarSystem {
-    pub fn new() -> Self {
-        let mut solar_system = SolarSystem {
-            star: CelestialBody::new_star(
-                "Sol".to_string(),
-                Vector3::new(0.0, 0.0, 0.0),
-                15.0,
-                Color::YELLOW,
-                0.5,
-            ),
-            planets: Vec::new(),
-            time_scale: 1.0,
+    /// A system at the epoch, with every body placed by the graph
+    pub fn with_graph(graph: SceneGraph) -> Self {
+        let mut system = SolarSystem {
+            graph,
+            scene_id: NEXT_SCENE_ID.fetch_add(1, Ordering::Relaxed),
+            time: 0.0,
+            physics: PhysicsConfig::default(),
+            mode: SimulationMode::Kinematic,
+            integrator: Integrator::default(),
+            initial_diagnostics: None,
         };
+        system.set_time(0.0);
+        system
+    }
 
-        // Create an imaginative solar system with diverse planets
-        solar_system.create_planets();
-        solar_system
-    }
-
-    fn create_planets(&mut self) {
-        // Planet 1: Rocky inner planet (Mercury-like) - at 0 degrees
-        let mut planet1 = CelestialBody::new_planet(
-            "Pyrion".to_string(),
-            Vector3::new(0.0, 0.0, 0.0),
-            40.0,   // orbital radius
-            2.0,    // orbital speed (not used in static mode)
-            3.0,    // radius
-            Color::new(210, 105, 30, 255), // Chocolate brown - more vibrant
-            3.0,    // rotation speed
-            0.1,    // orbital inclination
-        );
-
-        // Planet 2: Earth-like planet with moon - at 72 degrees
-        let mut planet2 = CelestialBody::new_planet(
-            "Verdania".to_string(),
-            Vector3::new(0.0, 0.0, 0.0),
-            70.0,   // orbital radius
-            1.5,    // orbital speed
-            5.0,    // radius
-            Color::new(34, 139, 34, 255), // Forest green
-            2.0,    // rotation speed
-            0.05,   // orbital inclination
-        );
-        // Manually set position for planet2 at 72 degrees
-        let angle2 = 72.0_f32.to_radians();
-        planet2.position = Vector3::new(70.0 * angle2.cos(), 0.0, 70.0 * angle2.sin());
-
-        // Add moon to planet2
-        let moon1 = CelestialBody::new_moon(
-            "Luna Verde".to_string(),
-            planet2.position,
-            12.0,   // orbital radius around planet
-            8.0,    // orbital speed
-            1.5,    // radius
-            Color::new(192, 192, 192, 255), // Silver
-            1.0,    // rotation speed
-        );
-        planet2.add_moon(moon1);
-
-        // Planet 3: Gas giant with rings - at 144 degrees
-        let mut planet3 = CelestialBody::new_planet(
-            "Gigantus".to_string(),
-            Vector3::new(0.0, 0.0, 0.0),
-            120.0,  // orbital radius
-            1.0,    // orbital speed
-            8.0,    // radius
-            Color::new(255, 200, 50, 255), // Bright gold
-            1.5,    // rotation speed
-            0.15,   // orbital inclination
-        );
-        // Manually set position for planet3 at 144 degrees
-        let angle3 = 144.0_f32.to_radians();
-        planet3.position = Vector3::new(120.0 * angle3.cos(), 0.0, 120.0 * angle3.sin());
-
-        // Add rings to planet3
-        planet3.add_rings(10.0, 15.0, Color::new(220, 220, 180, 120));
-
-        // Add multiple moons to planet3
-        let moon2 = CelestialBody::new_moon(
-            "Titan Dorado".to_string(),
-            planet3.position,
-            20.0,   // orbital radius
-            4.0,    // orbital speed
-            2.0,    // radius
-            Color::new(255, 140, 0, 255), // Dark orange
-            2.0,    // rotation speed
-        );
-        planet3.add_moon(moon2);
-
-        let moon3 = CelestialBody::new_moon(
-            "Io Menor".to_string(),
-            planet3.position,
-            25.0,   // orbital radius
-            3.0,    // orbital speed
-            1.0,    // radius
-            Color::new(220, 20, 60, 255), // Crimson
-            3.0,    // rotation speed
-        );
-        planet3.add_moon(moon3);
-
-        // Planet 4: Ice planet - at 216 degrees
-        let mut planet4 = CelestialBody::new_planet(
-            "Glacialis".to_string(),
-            Vector3::new(0.0, 0.0, 0.0),
-            180.0,  // orbital radius
-            0.7,    // orbital speed
-            6.0,    // radius
-            Color::new(135, 206, 250, 255), // Light sky blue - icy look
-            1.0,    // rotation speed
-            0.2,    // orbital inclination
-        );
-        // Manually set position for planet4 at 216 degrees
-        let angle4 = 216.0_f32.to_radians();
-        planet4.position = Vector3::new(180.0 * angle4.cos(), 0.0, 180.0 * angle4.sin());
-
-        // Planet 5: Distant dwarf planet - at 288 degrees
-        let mut planet5 = CelestialBody::new_planet(
-            "Plutonix".to_string(),
-            Vector3::new(0.0, 0.0, 0.0),
-            250.0,  // orbital radius
-            0.4,    // orbital speed
-            2.5,    // radius
-            Color::new(186, 85, 211, 255), // Medium orchid purple
-            0.8,    // rotation speed
-            0.3,    // orbital inclination
-        );
-        // Manually set position for planet5 at 288 degrees
-        let angle5 = 288.0_f32.to_radians();
-        planet5.position = Vector3::new(250.0 * angle5.cos(), 0.0, 250.0 * angle5.sin());
-
-        // Add planets to the system
-        self.planets.push(planet1);
-        self.planets.push(planet2);
-        self.planets.push(planet3);
-        self.planets.push(planet4);
-        self.planets.push(planet5);
+    /// Default system, embedded from `assets/systems/default.toml`
+    pub fn new() -> Self {
+        Self::from_toml_str(DEFAULT_SYSTEM).expect("embedded default system must be valid")
+    }
+
+    /// Load a system from a TOML scene file
+    pub fn from_file(path: &str) -> Result<Self, SceneError> {
+        scene_loader::load_from_file(path)
     }
 
+    /// Load a system from TOML source text
+    pub fn from_toml_str(source: &str) -> Result<Self, SceneError> {
+        scene_loader::load_from_str(source)
+    }
+
+    /// Identifies this system among every system built in the process.
+    /// `NodeId`s from one scene mean nothing in another, even of the same size.
+    pub fn scene_id(&self) -> u64 {
+        self.scene_id
+    }
+
+    /// Advance the simulation by `dt` time units (negative runs it
+    /// backwards). The caller scales real time, see `SimulationClock`.
     pub fn update(&mut self, dt: f32) {
-        let scaled_dt = dt * self.time_scale;
-        
-        // Update star
-        self.star.update(scaled_dt);
-        
-        // Update all planets
-        for planet in &mut self.planets {
-            planet.update(scaled_dt);
+        self.advance_to(self.time + dt as f64);
+    }
+
+    /// Advance the simulation to `time` units after the epoch. Unlike
+    /// `set_time`, an N-body run integrates up to `time` instead of
+    /// restarting there. Driving the system with `SimulationClock::time`
+    /// keeps the clock the only accumulator of simulated time.
+    ///
+    /// An N-body run can stop short of `time` when the step is longer than
+    /// `max_substeps_per_update` substeps; `time()` is where it got to, and
+    /// the clock should be brought back there (`SimulationClock::set_time`).
+    pub fn advance_to(&mut self, time: f64) {
+        match self.mode {
+            SimulationMode::Kinematic => self.set_time(time),
+            SimulationMode::NBody => {
+                let dt = (time - self.time) as f32;
+
+                let mut bodies = self.point_masses();
+                let dropped = self.integrator.advance(&mut bodies, dt, &self.physics);
+                self.apply_point_masses(&bodies);
+
+                // Only the spin is analytic; positions are integrated
+                self.time = time - dropped as f64;
+                let reached = self.time;
+                for body in self.graph.bodies_mut() {
+                    body.rotation_angle = body.rotation_at(reached);
+                }
+            }
         }
     }
 
-    pub fn get_all_bodies(&self) -> Vec<&CelestialBody> {
-        let mut bodies = vec![&self.star];
-        for planet in &self.planets {
-            bodies.push(planet);
-            for moon in &planet.moons {
-                bodies.push(moon);
+    // ===== TIME =====
+
+    /// Time units since the epoch of the current state
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Jump to `time` units after the epoch: every body moves to its
+    /// Keplerian position at that time. An N-body run restarts from there.
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+        self.graph.set_time(time);
+
+        if self.mode == SimulationMode::NBody {
+            self.initialize_velocities_from_orbits();
+            self.initial_diagnostics = Some(self.diagnostics());
+            self.integrator.reset();
+        }
+    }
+
+    /// State of every node `time` units after the epoch, following the
+    /// Keplerian orbits, indexed by `NodeId::index`. Doesn't change the system.
+    pub fn states_at(&self, time: f64) -> Vec<BodyState> {
+        self.graph.states_at(time)
+    }
+
+    /// State of the body called `name` `time` units after the epoch
+    pub fn state_at(&self, name: &str, time: f64) -> Option<BodyState> {
+        let id = self.graph.find(name)?;
+        Some(self.states_at(time)[id.index()])
+    }
+
+    pub fn position_at(&self, name: &str, time: f64) -> Option<Vector3> {
+        self.state_at(name, time).map(|state| state.position)
+    }
+
+    // ===== N-BODY PHYSICS =====
+
+    pub fn simulation_mode(&self) -> SimulationMode {
+        self.mode
+    }
+
+    /// Switch between scripted orbits and mutual gravity. Entering N-body
+    /// mode derives velocities from the current orbital elements; leaving it
+    /// puts every body back on its Keplerian orbit.
+    pub fn set_simulation_mode(&mut self, mode: SimulationMode) {
+        if mode == self.mode {
+            return;
+        }
+
+        match mode {
+            SimulationMode::NBody => {
+                self.initialize_velocities_from_orbits();
+                self.initial_diagnostics = Some(self.diagnostics());
+            }
+            SimulationMode::Kinematic => {
+                for body in self.graph.bodies_mut() {
+                    body.velocity = Vector3::zero();
+                }
+                self.initial_diagnostics = None;
             }
         }
-        bodies
+
+        self.integrator.reset();
+        self.mode = mode;
+
+        // Back on the Keplerian orbits, where they are at the current time
+        if mode == SimulationMode::Kinematic {
+            self.set_time(self.time);
+        }
     }
 
-    pub fn get_all_bodies_mut(&mut self) -> Vec<&mut CelestialBody> {
-        let mut bodies = vec![&mut self.star];
-        for planet in &mut self.planets {
-            bodies.push(planet);
-            // Note: Can't easily return mutable references to moons due to borrowing rules
-            // Moons are updated through their parent planets
+    pub fn toggle_simulation_mode(&mut self) {
+        let next = match self.mode {
+            SimulationMode::Kinematic => SimulationMode::NBody,
+            SimulationMode::NBody => SimulationMode::Kinematic,
+        };
+        self.set_simulation_mode(next);
+    }
+
+    /// Energy and momentum of the whole system
+    pub fn diagnostics(&self) -> Diagnostics {
+        physics::diagnostics(&self.point_masses(), &self.physics)
+    }
+
+    /// Relative change of total energy since N-body mode was enabled
+    pub fn energy_drift(&self) -> Option<f32> {
+        let initial = self.initial_diagnostics?;
+        let current = self.diagnostics();
+        Some((current.total_energy - initial.total_energy) / initial.total_energy.abs())
+    }
+
+    // Orbital velocities from the Keplerian elements, composed down the
+    // graph, with the system's total momentum removed so the barycenter
+    // stays put
+    fn initialize_velocities_from_orbits(&mut self) {
+        let g = self.physics.gravitational_constant;
+        let mut velocities: Vec<Vector3> = Vec::with_capacity(self.graph.len());
+        for node in self.graph.iter() {
+            let parent_velocity = node.parent.map_or(Vector3::zero(), |parent| velocities[parent.index()]);
+            let relative = match (&node.body.orbit, node.parent) {
+                (Some(orbit), Some(parent)) => {
+                    let mu = g * self.central_mass(node.id, parent);
+                    orbit.velocity_at_mean_anomaly(orbit.mean_anomaly_at(self.time), mu)
+                }
+                _ => Vector3::zero(),
+            };
+            velocities.push(parent_velocity + relative);
+        }
+        for (body, velocity) in self.graph.bodies_mut().zip(velocities) {
+            body.velocity = velocity;
+        }
+
+        let bodies = self.point_masses();
+        let total_mass: f32 = bodies.iter().map(|b| b.mass).sum();
+        let momentum = physics::diagnostics(&bodies, &self.physics).linear_momentum;
+        let drift = momentum / total_mass;
+
+        for body in self.graph.bodies_mut() {
+            body.velocity -= drift;
         }
-        bodies
     }
 
-    pub fn get_planet_by_index(&self, index: usize) -> Option<&CelestialBody> {
-        self.planets.get(index)
+    // Mass whose gravity keeps `id` on its orbit around `parent`. Around a
+    // body it's the two-body sum. Around a barycenter the orbit is measured
+    // from the center of mass, so only the rest of the barycenter's system
+    // pulls, reduced by the share of the orbit the other side takes up.
+    fn central_mass(&self, id: NodeId, parent: NodeId) -> f32 {
+        let body = self.graph.body(id);
+        let parent_body = self.graph.body(parent);
+        if !parent_body.is_barycenter() {
+            return parent_body.mass + body.mass;
+        }
+        let own = self.graph.subtree_mass(id);
+        let others = self.graph.subtree_mass(parent) - own;
+        if others <= 0.0 {
+            return 0.0;
+        }
+        others * others * others / ((others + own) * (others + own))
+    }
+
+    // Flatten bodies in get_all_bodies order (barycenters have no mass)
+    fn point_masses(&self) -> Vec<PointMass> {
+        self.get_all_bodies()
+            .into_iter()
+            .map(|body| PointMass {
+                position: body.position,
+                velocity: body.velocity,
+                mass: body.mass,
+            })
+            .collect()
+    }
+
+    fn apply_point_masses(&mut self, bodies: &[PointMass]) {
+        for (body, point) in self.get_all_bodies_mut().into_iter().zip(bodies) {
+            body.position = point.position;
+            body.velocity = point.velocity;
+        }
+
+        // Barycenters follow the center of mass of everything below them
+        let barycenters: Vec<NodeId> = self.graph.iter().filter(|node| node.body.is_barycenter()).map(|node| node.id).collect();
+        for id in barycenters {
+            let mut weighted = Vector3::zero();
+            let mut mass = 0.0;
+            for node in self.graph.descendants(id) {
+                let body = self.graph.body(node);
+                weighted += body.position * body.mass;
+                mass += body.mass;
+            }
+            if mass > 0.0 {
+                self.graph.body_mut(id).position = weighted / mass;
+            }
+        }
+        self.graph.update_orbital_centers();
     }
 
-    pub fn set_time_scale(&mut self, scale: f32) {
-        self.time_scale = scale.max(0.1).min(10.0); // Clamp between 0.1x and 10x
+    // ===== QUERIES =====
+
+    /// Every visible body (barycenters are left out), parents before their
+    /// children
+    pub fn get_all_bodies(&self) -> Vec<&CelestialBody> {
+        self.graph.iter().map(|node| &node.body).filter(|body| !body.is_barycenter()).collect()
+    }
+
+    pub fn get_all_bodies_mut(&mut self) -> Vec<&mut CelestialBody> {
+        self.graph.bodies_mut().filter(|body| !body.is_barycenter()).collect()
+    }
+
+    pub fn stars(&self) -> Vec<&CelestialBody> {
+        self.bodies_of_type(CelestialBodyType::Star)
+    }
+
+    pub fn planets(&self) -> Vec<&CelestialBody> {
+        self.bodies_of_type(CelestialBodyType::Planet)
+    }
+
+    fn bodies_of_type(&self, body_type: CelestialBodyType) -> Vec<&CelestialBody> {
+        self.graph.iter().map(|node| &node.body).filter(|body| body.body_type == body_type).collect()
+    }
+
+    /// The body called `name`
+    pub fn body(&self, name: &str) -> Option<&CelestialBody> {
+        self.graph.find(name).map(|id| self.graph.body(id))
+    }
+
+    pub fn get_planet_by_index(&self, index: usize) -> Option<&CelestialBody> {
+        self.planets().get(index).copied()
     }
 
     pub fn get_system_bounds(&self) -> (Vector3, Vector3) {
         let mut min_bounds = Vector3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
         let mut max_bounds = Vector3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
 
-        // Check star
-        let star_bounds = self.star.radius;
-        min_bounds.x = min_bounds.x.min(self.star.position.x - star_bounds);
-        min_bounds.y = min_bounds.y.min(self.star.position.y - star_bounds);
-        min_bounds.z = min_bounds.z.min(self.star.position.z - star_bounds);
-        max_bounds.x = max_bounds.x.max(self.star.position.x + star_bounds);
-        max_bounds.y = max_bounds.y.max(self.star.position.y + star_bounds);
-        max_bounds.z = max_bounds.z.max(self.star.position.z + star_bounds);
-
-        // Check planets and their orbits
-        for planet in &self.planets {
-            let orbit_bounds = planet.orbital_radius + planet.radius;
-            min_bounds.x = min_bounds.x.min(planet.orbital_center.x - orbit_bounds);
-            min_bounds.y = min_bounds.y.min(planet.orbital_center.y - orbit_bounds);
-            min_bounds.z = min_bounds.z.min(planet.orbital_center.z - orbit_bounds);
-            max_bounds.x = max_bounds.x.max(planet.orbital_center.x + orbit_bounds);
-            max_bounds.y = max_bounds.y.max(planet.orbital_center.y + orbit_bounds);
-            max_bounds.z = max_bounds.z.max(planet.orbital_center.z + orbit_bounds);
+        // Orbiting bodies cover their whole orbit, the rest just their sphere
+        for body in self.get_all_bodies() {
+            let (center, extent) = match body.orbit {
+                Some(_) => (body.orbital_center, body.get_orbit_extent() + body.radius),
+                None => (body.position, body.radius),
+            };
+            min_bounds.x = min_bounds.x.min(center.x - extent);
+            min_bounds.y = min_bounds.y.min(center.y - extent);
+            min_bounds.z = min_bounds.z.min(center.z - extent);
+            max_bounds.x = max_bounds.x.max(center.x + extent);
+            max_bounds.y = max_bounds.y.max(center.y + extent);
+            max_bounds.z = max_bounds.z.max(center.z + extent);
         }
 
         (min_bounds, max_bounds)
     }
 
     pub fn get_total_body_count(&self) -> usize {
-        let mut count = 1; // Star
-        count += self.planets.len();
-        for planet in &self.planets {
-            count += planet.moons.len();
-        }
-        count
+        self.get_all_bodies().len()
     }
 
     // Get bodies sorted by distance from camera (for proper rendering order)
     pub fn get_bodies_sorted_by_distance(&self, camera_pos: Vector3) -> Vec<&CelestialBody> {
-        let mut bodies = self.get_all_bodies();
-        bodies.sort_by(|a, b| {
-            let dist_a = a.get_distance_from_camera(camera_pos);
-            let dist_b = b.get_distance_from_camera(camera_pos);
+        self.body_ids_sorted_by_distance(camera_pos).into_iter().map(|id| self.graph.body(id)).collect()
+    }
+
+    /// Nodes of every body that is drawn (not barycenters), parents first
+    pub fn body_ids(&self) -> Vec<NodeId> {
+        self.graph.iter().filter(|node| !node.body.is_barycenter()).map(|node| node.id).collect()
+    }
+
+    /// Same as `body_ids`, farthest from `camera_pos` first
+    pub fn body_ids_sorted_by_distance(&self, camera_pos: Vector3) -> Vec<NodeId> {
+        let mut ids = self.body_ids();
+        ids.sort_by(|&a, &b| {
+            let dist_a = self.graph.body(a).get_distance_from_camera(camera_pos);
+            let dist_b = self.graph.body(b).get_distance_from_camera(camera_pos);
             dist_b.partial_cmp(&dist_a).unwrap_or(std::cmp::Ordering::Equal)
         });
-        bodies
+        ids
     }
 
     // Find the closest body to a given position
@@ -246,4 +365,185 @@ impl SolarSystem {
             })
             .copied()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nbody_mode_conserves_energy() {
+        let mut system = SolarSystem::new();
+        system.set_simulation_mode(SimulationMode::NBody);
+        for _ in 0..40 {
+            system.update(0.25);
+        }
+
+        let drift = system.energy_drift().unwrap();
+        assert!(drift.abs() < 1e-4, "energy drift {}", drift);
+        let momentum = system.diagnostics().linear_momentum;
+        assert!(momentum.x.abs() < 1.0 && momentum.y.abs() < 1.0 && momentum.z.abs() < 1.0);
+    }
+
+    #[test]
+    fn test_leaving_nbody_mode_restores_kepler_orbits() {
+        let mut reference = SolarSystem::new();
+        reference.update(3.0);
+        let mut system = SolarSystem::new();
+
+        system.set_simulation_mode(SimulationMode::NBody);
+        system.update(3.0);
+        system.set_simulation_mode(SimulationMode::Kinematic);
+
+        for (body, expected) in system.get_all_bodies().iter().zip(reference.get_all_bodies()) {
+            assert_eq!(body.position, expected.position, "{}", body.name);
+            assert_eq!(body.velocity, Vector3::zero());
+        }
+    }
+
+    #[test]
+    fn test_stepping_matches_direct_evaluation() {
+        // Many small steps, forwards and then backwards, end up where direct
+        // evaluation puts every body, moons included
+        let mut system = SolarSystem::new();
+        for _ in 0..500 {
+            system.update(0.017);
+        }
+        for _ in 0..200 {
+            system.update(-0.013);
+        }
+        let time = 500.0 * 0.017f32 as f64 - 200.0 * 0.013f32 as f64;
+        assert!((system.time() - time).abs() < 1e-9);
+
+        let expected = SolarSystem::new().states_at(time);
+        assert_eq!(system.graph.len(), expected.len());
+        for (body, state) in system.graph.iter().map(|node| &node.body).zip(&expected) {
+            assert!((body.position - state.position).length() < 1e-3, "{}", body.name);
+            assert!((body.rotation_angle - state.rotation_angle).abs() < 1e-4, "{}", body.name);
+        }
+    }
+
+    #[test]
+    fn test_clock_time_is_the_system_time() {
+        // At years per second the f32 step of each frame loses precision; a
+        // system driven by the clock's time still shows exactly that time
+        let mut clock = crate::clock::SimulationClock::default();
+        for _ in 0..8 {
+            clock.faster();
+        }
+        let mut system = SolarSystem::new();
+        for _ in 0..1000 {
+            clock.tick(1.0 / 60.0);
+            system.advance_to(clock.time());
+        }
+        assert_eq!(system.time(), clock.time());
+    }
+
+    #[test]
+    fn test_nbody_time_stops_where_the_integrator_did() {
+        let mut system = SolarSystem::new();
+        system.set_simulation_mode(SimulationMode::NBody);
+        system.physics.max_substeps_per_update = 10;
+        let substep = system.physics.substep as f64;
+
+        // More than 10 substeps: the system stays where the integrator got to
+        system.advance_to(100.0 * substep);
+        assert!(system.time() < 100.0 * substep);
+        assert!((system.time() - 11.0 * substep).abs() < 1e-6);
+        for body in system.get_all_bodies() {
+            assert_eq!(body.rotation_angle, body.rotation_at(system.time()));
+        }
+
+        // Within the cap it gets there exactly
+        let target = system.time() + 5.0 * substep;
+        system.advance_to(target);
+        assert_eq!(system.time(), target);
+    }
+
+    #[test]
+    fn test_set_time_jumps_to_any_epoch() {
+        let mut system = SolarSystem::new();
+        let far_future = 10_000.25;
+        system.set_time(far_future);
+        assert_eq!(system.time(), far_future);
+
+        for (node, state) in system.graph.iter().zip(system.states_at(far_future)) {
+            let body = &node.body;
+            assert_eq!(body.position, state.position);
+        }
+        // Before the epoch and back to it
+        system.set_time(-3.0);
+        system.set_time(0.0);
+        for (body, initial) in system.get_all_bodies().iter().zip(SolarSystem::new().get_all_bodies()) {
+            assert!((body.position - initial.position).length() < 1e-4, "{}", body.name);
+        }
+    }
+
+    #[test]
+    fn test_moons_are_composed_through_their_planet() {
+        let system = SolarSystem::new();
+        let moon = system.graph.iter().find(|node| node.body.body_type == CelestialBodyType::Moon).unwrap();
+        let planet = system.graph.body(moon.parent.unwrap());
+        let moon = &moon.body;
+        let time = 7.3;
+
+        let planet_state = system.state_at(&planet.name, time).unwrap();
+        let moon_state = system.state_at(&moon.name, time).unwrap();
+        let orbit = moon.orbit.unwrap();
+        assert!((moon_state.position - (planet_state.position + orbit.position_at(time))).length() < 1e-4);
+        assert!((moon_state.velocity - (planet_state.velocity + orbit.velocity_at(time))).length() < 1e-4);
+        assert_eq!(system.position_at(&moon.name, time), Some(moon_state.position));
+        assert!(system.state_at("Nowhere", time).is_none());
+    }
+
+    const BINARY: &str = r#"
+        [[barycenters]]
+        name = "AB"
+
+        [[stars]]
+        name = "A"
+        parent = "AB"
+        radius = 4.0
+        color = [255, 220, 180]
+        mass = 4000.0
+        orbit = { semi_major_axis = 10.0, mean_motion = 0.2 }
+
+        [[stars]]
+        name = "B"
+        parent = "AB"
+        radius = 3.0
+        color = [255, 160, 120]
+        mass = 2000.0
+        orbit = { semi_major_axis = 20.0, mean_motion = 0.2, mean_anomaly = 180.0 }
+
+        [[planets]]
+        name = "Far"
+        parent = "AB"
+        radius = 1.0
+        color = [100, 150, 255]
+        mass = 1.0
+        orbit = { semi_major_axis = 150.0, mean_motion = 0.01 }
+    "#;
+
+    #[test]
+    fn test_binary_stars_share_a_barycenter() {
+        let mut system = SolarSystem::from_toml_str(BINARY).unwrap();
+        assert_eq!(system.stars().len(), 2);
+        // The barycenter is not a body you can see or collide with
+        assert_eq!(system.get_total_body_count(), 3);
+        assert!(system.get_all_bodies().iter().all(|body| !body.is_barycenter()));
+
+        system.set_simulation_mode(SimulationMode::NBody);
+        for _ in 0..20 {
+            system.update(0.5);
+        }
+        let drift = system.energy_drift().unwrap();
+        assert!(drift.abs() < 1e-3, "energy drift {}", drift);
+
+        // Still the center of mass of the pair (and the light planet)
+        let (a, b) = (system.body("A").unwrap(), system.body("B").unwrap());
+        let center = (a.position * a.mass + b.position * b.mass) / (a.mass + b.mass);
+        assert!((system.body("AB").unwrap().position - center).length() < 0.1);
+        assert!(center.length() < 0.1);
+    }
+}
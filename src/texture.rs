@@ -0,0 +1,359 @@
+//! Texturas - Imágenes muestreadas por el fragment shader
+//!
+//! Una textura guarda su imagen y la cadena de mipmaps (cada nivel con la
+//! mitad de resolución que el anterior). Las coordenadas de textura siguen
+//! la convención de OBJ: u crece hacia la derecha y v hacia arriba, así que
+//! v = 1 es la primera fila de la imagen. Los mapas equirectangulares de los
+//! planetas tienen el polo norte arriba y la longitud 0 en el borde izquierdo.
+
+
+use raylib::prelude::*;
+use std::fmt;
+
+use crate::asset_error::AssetError;
+use crate::framebuffer::read_ppm;
+
+/// Cómo se combinan los texels al muestrear
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFilter {
+    /// El texel más cercano (se ve pixelado de cerca)
+    Nearest,
+    /// Interpolación entre los 4 texels más cercanos
+    Bilinear,
+    /// Bilinear en los dos niveles de mipmap más cercanos, mezclados
+    /// según el tamaño en pantalla (evita el aliasing de lejos)
+    Trilinear,
+}
+
+impl TextureFilter {
+    /// Nombre usado en los archivos de escena
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(TextureFilter::Nearest),
+            "bilinear" => Some(TextureFilter::Bilinear),
+            "trilinear" => Some(TextureFilter::Trilinear),
+            _ => None,
+        }
+    }
+}
+
+/// Qué pasa con las coordenadas fuera de [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WrapMode {
+    /// La imagen se repite
+    Repeat,
+    /// La imagen se repite reflejada
+    MirroredRepeat,
+    /// Se usa el texel del borde
+    Clamp,
+}
+
+impl WrapMode {
+    /// Nombre usado en los archivos de escena
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "repeat" => Some(WrapMode::Repeat),
+            "mirror" => Some(WrapMode::MirroredRepeat),
+            "clamp" => Some(WrapMode::Clamp),
+            _ => None,
+        }
+    }
+
+    /// Convierte un índice de texel cualquiera en uno dentro de [0, size)
+    fn apply(self, index: i32, size: u32) -> u32 {
+        let size = size as i32;
+        match self {
+            WrapMode::Repeat => index.rem_euclid(size) as u32,
+            WrapMode::MirroredRepeat => {
+                let period = index.rem_euclid(2 * size);
+                (if period < size { period } else { 2 * size - 1 - period }) as u32
+            }
+            WrapMode::Clamp => index.clamp(0, size - 1) as u32,
+        }
+    }
+}
+
+/// Un nivel de la cadena de mipmaps
+#[derive(Clone)]
+struct MipLevel {
+    width: u32,
+    height: u32,
+    /// Fila por fila, empezando por la de arriba
+    texels: Vec<Color>,
+}
+
+impl MipLevel {
+    fn texel(&self, x: i32, y: i32, wrap_u: WrapMode, wrap_v: WrapMode) -> [f32; 4] {
+        let x = wrap_u.apply(x, self.width);
+        let y = wrap_v.apply(y, self.height);
+        let c = self.texels[(y * self.width + x) as usize];
+        [c.r as f32, c.g as f32, c.b as f32, c.a as f32]
+    }
+
+    /// Reduce a la mitad promediando bloques de 2x2
+    /// (en dimensiones impares el último texel se repite)
+    fn downsample(&self) -> MipLevel {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut texels = Vec::with_capacity((width * height) as usize);
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0.0; 4];
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let texel = self.texel(
+                        (2 * x + dx) as i32,
+                        (2 * y + dy) as i32,
+                        WrapMode::Clamp,
+                        WrapMode::Clamp,
+                    );
+                    for (total, channel) in sum.iter_mut().zip(texel) {
+                        *total += channel;
+                    }
+                }
+                texels.push(to_color(sum.map(|channel| channel / 4.0)));
+            }
+        }
+
+        MipLevel { width, height, texels }
+    }
+}
+
+/// Imagen RGBA con mipmaps, filtro y modo de repetición
+#[derive(Clone)]
+pub struct Texture {
+    levels: Vec<MipLevel>,
+    pub filter: TextureFilter,
+    pub wrap_u: WrapMode,
+    pub wrap_v: WrapMode,
+}
+
+impl fmt::Debug for Texture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Texture")
+            .field("width", &self.width())
+            .field("height", &self.height())
+            .field("mip_levels", &self.mip_levels())
+            .field("filter", &self.filter)
+            .field("wrap_u", &self.wrap_u)
+            .field("wrap_v", &self.wrap_v)
+            .finish()
+    }
+}
+
+impl Texture {
+    /// Crea una textura a partir de sus texels (fila por fila, la primera es
+    /// la de arriba). Por defecto filtra con trilinear, repite en u y se
+    /// detiene en los bordes en v, que es lo que necesita un mapa equirectangular.
+    pub fn from_texels(width: u32, height: u32, texels: Vec<Color>) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err("texture must not be empty".to_string());
+        }
+        if texels.len() != (width * height) as usize {
+            return Err(format!(
+                "expected {} texels for a {}x{} texture, got {}",
+                width * height,
+                width,
+                height,
+                texels.len()
+            ));
+        }
+
+        let mut levels = vec![MipLevel { width, height, texels }];
+        while let Some(last) = levels.last().filter(|level| level.width > 1 || level.height > 1) {
+            let next = last.downsample();
+            levels.push(next);
+        }
+
+        Ok(Texture {
+            levels,
+            filter: TextureFilter::Trilinear,
+            wrap_u: WrapMode::Repeat,
+            wrap_v: WrapMode::Clamp,
+        })
+    }
+
+    /// Carga una textura desde un archivo PPM (P6) o cualquier formato que
+    /// soporte raylib (PNG, JPG, BMP, ...)
+    pub fn load(path: &str) -> Result<Self, AssetError> {
+        let image_error = |message: String| AssetError::Image { path: path.to_string(), message };
+        let is_ppm = std::path::Path::new(path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("ppm"));
+
+        if is_ppm {
+            let (width, height, texels) = read_ppm(path).map_err(|e| match e.kind() {
+                std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof => image_error(e.to_string()),
+                _ => AssetError::Io { path: path.to_string(), message: e.to_string() },
+            })?;
+            return Self::from_texels(width, height, texels).map_err(image_error);
+        }
+
+        // raylib no distingue un archivo inexistente de uno que no sabe decodificar
+        if let Err(e) = std::fs::metadata(path) {
+            return Err(AssetError::Io { path: path.to_string(), message: e.to_string() });
+        }
+        let image = Image::load_image(path).map_err(|e| image_error(e.to_string()))?;
+        let texels = image.get_image_data().to_vec();
+        Self::from_texels(image.width() as u32, image.height() as u32, texels).map_err(image_error)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.levels[0].width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.levels[0].height
+    }
+
+    /// Número de niveles de mipmap, incluyendo la imagen original
+    pub fn mip_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Muestrea la textura en resolución completa (nivel 0)
+    #[cfg(test)]
+    pub fn sample(&self, uv: Vector2) -> Color {
+        self.sample_lod(uv, 0.0)
+    }
+
+    /// Muestrea la textura con un nivel de detalle dado
+    ///
+    /// `lod` es log2 de cuántos texels cubre un píxel de lado: 0 usa la
+    /// imagen original, 1 el primer mipmap, etc. Solo el filtro trilinear lo usa.
+    pub fn sample_lod(&self, uv: Vector2, lod: f32) -> Color {
+        match self.filter {
+            TextureFilter::Nearest => self.sample_nearest(&self.levels[0], uv),
+            TextureFilter::Bilinear => to_color(self.sample_bilinear(&self.levels[0], uv)),
+            TextureFilter::Trilinear => {
+                let max_level = (self.levels.len() - 1) as f32;
+                let lod = lod.clamp(0.0, max_level);
+                let lower = lod.floor() as usize;
+                let upper = lod.ceil() as usize;
+                let a = self.sample_bilinear(&self.levels[lower], uv);
+                if upper == lower {
+                    return to_color(a);
+                }
+                let b = self.sample_bilinear(&self.levels[upper], uv);
+                let t = lod - lower as f32;
+                to_color([0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t))
+            }
+        }
+    }
+
+    /// Nivel de detalle para un triángulo: compara el área que ocupa en la
+    /// textura (en texels) con el área que ocupa en pantalla (en píxeles)
+    ///
+    /// Es un valor por triángulo, no por píxel; con las mallas de esfera los
+    /// triángulos son pequeños y la diferencia no se nota.
+    pub fn triangle_lod(&self, uvs: [Vector2; 3], screen: [Vector2; 3]) -> f32 {
+        let texel_area = triangle_area(uvs) * (self.width() * self.height()) as f32;
+        let pixel_area = triangle_area(screen);
+        if pixel_area <= f32::EPSILON || texel_area <= f32::EPSILON {
+            return 0.0;
+        }
+        (0.5 * (texel_area / pixel_area).log2()).max(0.0)
+    }
+
+    fn sample_nearest(&self, level: &MipLevel, uv: Vector2) -> Color {
+        let x = (uv.x * level.width as f32).floor() as i32;
+        let y = ((1.0 - uv.y) * level.height as f32).floor() as i32;
+        to_color(level.texel(x, y, self.wrap_u, self.wrap_v))
+    }
+
+    fn sample_bilinear(&self, level: &MipLevel, uv: Vector2) -> [f32; 4] {
+        // Los centros de los texels están en (i + 0.5) / tamaño
+        let x = uv.x * level.width as f32 - 0.5;
+        let y = (1.0 - uv.y) * level.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i32, y0 as i32);
+
+        let c00 = level.texel(x0, y0, self.wrap_u, self.wrap_v);
+        let c10 = level.texel(x0 + 1, y0, self.wrap_u, self.wrap_v);
+        let c01 = level.texel(x0, y0 + 1, self.wrap_u, self.wrap_v);
+        let c11 = level.texel(x0 + 1, y0 + 1, self.wrap_u, self.wrap_v);
+
+        [0, 1, 2, 3].map(|i| {
+            let top = c00[i] + (c10[i] - c00[i]) * tx;
+            let bottom = c01[i] + (c11[i] - c01[i]) * tx;
+            top + (bottom - top) * ty
+        })
+    }
+}
+
+fn to_color(channels: [f32; 4]) -> Color {
+    let [r, g, b, a] = channels.map(|channel| channel.round().clamp(0.0, 255.0) as u8);
+    Color::new(r, g, b, a)
+}
+
+fn triangle_area(points: [Vector2; 3]) -> f32 {
+    let [a, b, c] = points;
+    0.5 * ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tablero de 2x2: negro y blanco alternados
+    fn checkerboard() -> Texture {
+        Texture::from_texels(2, 2, vec![Color::BLACK, Color::WHITE, Color::WHITE, Color::BLACK]).unwrap()
+    }
+
+    #[test]
+    fn test_nearest_and_wrap_modes() {
+        let mut texture = checkerboard();
+        texture.filter = TextureFilter::Nearest;
+
+        // v = 1 es la fila de arriba
+        assert_eq!(texture.sample(Vector2::new(0.25, 0.75)), Color::BLACK);
+        assert_eq!(texture.sample(Vector2::new(0.75, 0.75)), Color::WHITE);
+
+        // Repeat: u = 1.25 equivale a u = 0.25
+        texture.wrap_v = WrapMode::Repeat;
+        assert_eq!(texture.sample(Vector2::new(1.25, 0.75)), Color::BLACK);
+        // Mirror: u = 1.25 se refleja a 0.75
+        texture.wrap_u = WrapMode::MirroredRepeat;
+        assert_eq!(texture.sample(Vector2::new(1.25, 0.75)), Color::WHITE);
+        // Clamp: todo lo que pasa del borde usa el último texel
+        texture.wrap_u = WrapMode::Clamp;
+        assert_eq!(texture.sample(Vector2::new(7.0, 0.75)), Color::WHITE);
+    }
+
+    #[test]
+    fn test_bilinear_blends_neighbours() {
+        let mut texture = checkerboard();
+        texture.filter = TextureFilter::Bilinear;
+        texture.wrap_v = WrapMode::Repeat;
+
+        // En el centro exacto de un texel se obtiene ese texel
+        assert_eq!(texture.sample(Vector2::new(0.25, 0.75)), Color::BLACK);
+        // Entre los 4 texels: promedio gris
+        let center = texture.sample(Vector2::new(0.5, 0.5));
+        assert_eq!(center.r, 128);
+    }
+
+    #[test]
+    fn test_mipmaps_average_down_to_one_texel() {
+        let texture = Texture::from_texels(4, 2, vec![Color::WHITE; 8]).unwrap();
+        assert_eq!(texture.mip_levels(), 3);
+
+        let texture = checkerboard();
+        assert_eq!(texture.mip_levels(), 2);
+        // El último nivel es el promedio de toda la imagen
+        let far = texture.sample_lod(Vector2::new(0.25, 0.75), 10.0);
+        assert_eq!(far.r, 128);
+
+        // Un triángulo que cubre toda la textura en un solo píxel de lado usa el nivel más pequeño
+        let uvs = [Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0), Vector2::new(0.0, 1.0)];
+        let screen = [Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0), Vector2::new(0.0, 1.0)];
+        assert!((texture.triangle_lod(uvs, screen) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rejects_wrong_texel_count() {
+        assert!(Texture::from_texels(2, 2, vec![Color::BLACK; 3]).is_err());
+        assert!(Texture::from_texels(0, 0, Vec::new()).is_err());
+    }
+}
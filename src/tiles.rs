@@ -0,0 +1,374 @@
+//! Tiles - Rasterización por tiles en varios hilos
+//!
+//! El renderer no rasteriza en el momento: graba cada primitiva en una
+//! `DrawList`, en el mismo orden en que la dibujaría. Al ejecutar la lista,
+//! la pantalla se divide en tiles de `TILE_SIZE` x `TILE_SIZE` píxeles, cada
+//! comando se asigna (binning) a los tiles que toca su bounding box y los
+//! tiles se rasterizan y sombrean en paralelo, cada uno sobre una copia de su
+//! región del framebuffer.
+//!
+//! Cada tile ejecuta sus comandos en el orden en que se grabaron y cada
+//! píxel pertenece a un solo tile: todo píxel recibe la misma secuencia de
+//! depth tests, escrituras y mezclas que con un solo hilo. El resultado es
+//! idéntico píxel a píxel sin importar cuántos hilos se usen ni en qué orden
+//! terminen.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use raylib::prelude::*;
+
+use crate::framebuffer::Framebuffer;
+use crate::primitive_assembly::Triangle;
+use crate::rasterizer::{rasterize_triangle, Fragment};
+use crate::varyings::Varyings;
+
+/// Lado de un tile en píxeles
+pub const TILE_SIZE: u32 = 64;
+
+/// Rectángulo de píxeles, con ambos extremos incluidos
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PixelRect {
+    pub min_x: u32,
+    pub min_y: u32,
+    pub max_x: u32,
+    pub max_y: u32,
+}
+
+impl PixelRect {
+    /// Toda la pantalla (que no puede estar vacía)
+    pub fn screen(width: u32, height: u32) -> Self {
+        PixelRect { min_x: 0, min_y: 0, max_x: width - 1, max_y: height - 1 }
+    }
+
+    fn width(&self) -> u32 {
+        self.max_x - self.min_x + 1
+    }
+
+    fn height(&self) -> u32 {
+        self.max_y - self.min_y + 1
+    }
+
+    fn intersect(&self, other: &PixelRect) -> Option<PixelRect> {
+        let rect = PixelRect {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        };
+        (rect.min_x <= rect.max_x && rect.min_y <= rect.max_y).then_some(rect)
+    }
+}
+
+/// Qué cubre un comando en pantalla
+///
+/// Casi todos los comandos son triángulos, así que se guardan sin `Box`
+/// aunque la otra variante sea mucho más chica.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Copy, Debug)]
+pub enum Primitive {
+    Triangle(Triangle),
+    /// Todos los píxeles del rectángulo a una misma profundidad (impostores y
+    /// halos); el sombreado decide cuáles se dibujan
+    Rect { rect: PixelRect, depth: f32 },
+}
+
+impl Primitive {
+    /// Píxeles que puede tocar la primitiva dentro de `screen`
+    pub fn bounds(&self, screen: &PixelRect) -> Option<PixelRect> {
+        match self {
+            Primitive::Triangle(triangle) => {
+                let xs = triangle.vertices.map(|v| v.screen_position.x);
+                let ys = triangle.vertices.map(|v| v.screen_position.y);
+                let min_x = xs[0].min(xs[1]).min(xs[2]).floor().max(0.0);
+                let min_y = ys[0].min(ys[1]).min(ys[2]).floor().max(0.0);
+                let max_x = xs[0].max(xs[1]).max(xs[2]).ceil();
+                let max_y = ys[0].max(ys[1]).max(ys[2]).ceil();
+                if !(max_x >= min_x && max_y >= min_y) {
+                    return None; // Fuera de pantalla o con coordenadas NaN
+                }
+                let rect = PixelRect {
+                    min_x: min_x as u32,
+                    min_y: min_y as u32,
+                    max_x: max_x.min(u32::MAX as f32) as u32,
+                    max_y: max_y.min(u32::MAX as f32) as u32,
+                };
+                rect.intersect(screen)
+            }
+            Primitive::Rect { rect, .. } => rect.intersect(screen),
+        }
+    }
+}
+
+/// Cómo se combina el color de un fragmento con el framebuffer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Reemplaza el píxel (`set_pixel_with_depth`)
+    Opaque,
+    /// Mezcla según el alpha del fragmento (`blend_pixel_with_depth`)
+    Alpha,
+}
+
+/// Una primitiva con su sombreado y su estado de salida
+#[derive(Clone, Copy, Debug)]
+pub struct DrawCommand<S> {
+    pub primitive: Primitive,
+    /// Datos que necesita la función de sombreado (material, cuerpo, etc.)
+    pub shading: S,
+    pub blend: BlendMode,
+    pub depth_write: bool,
+}
+
+/// Comandos de dibujo de un cuadro, en orden de envío
+pub struct DrawList<S> {
+    commands: Vec<DrawCommand<S>>,
+}
+
+impl<S: Sync> DrawList<S> {
+    pub fn new() -> Self {
+        DrawList { commands: Vec::new() }
+    }
+
+    pub fn push(&mut self, command: DrawCommand<S>) {
+        self.commands.push(command);
+    }
+
+    /// Triángulos grabados, en orden de envío
+    pub fn triangles(&self) -> impl Iterator<Item = &Triangle> {
+        self.commands.iter().filter_map(|command| match &command.primitive {
+            Primitive::Triangle(triangle) => Some(triangle),
+            Primitive::Rect { .. } => None,
+        })
+    }
+
+    /// Dibuja todos los comandos en el framebuffer y vacía la lista.
+    ///
+    /// `shade` calcula el color de cada fragmento, o `None` para descartarlo.
+    /// Con `threads` ≤ 1 los comandos se dibujan directamente sobre todo el
+    /// framebuffer; con más, por tiles en paralelo.
+    pub fn execute<F>(&mut self, framebuffer: &mut Framebuffer, threads: usize, shade: F)
+    where
+        F: Fn(&S, &Fragment) -> Option<Color> + Sync,
+    {
+        if self.commands.is_empty() || framebuffer.width == 0 || framebuffer.height == 0 {
+            self.commands.clear();
+            return;
+        }
+        let screen = PixelRect::screen(framebuffer.width, framebuffer.height);
+
+        if threads <= 1 {
+            let commands = self.commands.iter().filter_map(|command| {
+                command.primitive.bounds(&screen).map(|bounds| (command, bounds))
+            });
+            draw_commands(framebuffer, &screen, commands, &shade);
+        } else {
+            self.execute_tiled(framebuffer, &screen, threads, &shade);
+        }
+        self.commands.clear();
+    }
+
+    fn execute_tiled<F>(&self, framebuffer: &mut Framebuffer, screen: &PixelRect, threads: usize, shade: &F)
+    where
+        F: Fn(&S, &Fragment) -> Option<Color> + Sync,
+    {
+        let tiles_x = framebuffer.width.div_ceil(TILE_SIZE);
+        let tiles_y = framebuffer.height.div_ceil(TILE_SIZE);
+        let tiles: Vec<PixelRect> = (0..tiles_y)
+            .flat_map(|ty| (0..tiles_x).map(move |tx| (tx, ty)))
+            .map(|(tx, ty)| PixelRect {
+                min_x: tx * TILE_SIZE,
+                min_y: ty * TILE_SIZE,
+                max_x: ((tx + 1) * TILE_SIZE).min(framebuffer.width) - 1,
+                max_y: ((ty + 1) * TILE_SIZE).min(framebuffer.height) - 1,
+            })
+            .collect();
+
+        // Binning: índices de comandos por tile, en orden de envío
+        let mut bins: Vec<Vec<(usize, PixelRect)>> = vec![Vec::new(); tiles.len()];
+        for (index, command) in self.commands.iter().enumerate() {
+            let Some(bounds) = command.primitive.bounds(screen) else {
+                continue;
+            };
+            for ty in bounds.min_y / TILE_SIZE..=bounds.max_y / TILE_SIZE {
+                for tx in bounds.min_x / TILE_SIZE..=bounds.max_x / TILE_SIZE {
+                    let tile = (ty * tiles_x + tx) as usize;
+                    if let Some(clipped) = bounds.intersect(&tiles[tile]) {
+                        bins[tile].push((index, clipped));
+                    }
+                }
+            }
+        }
+
+        // Cada hilo toma el siguiente tile libre hasta que no quedan
+        let next_tile = AtomicUsize::new(0);
+        let source: &Framebuffer = framebuffer;
+        let rendered: Vec<(PixelRect, Framebuffer)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.min(tiles.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let tile = next_tile.fetch_add(1, Ordering::Relaxed);
+                            if tile >= tiles.len() {
+                                break;
+                            }
+                            if bins[tile].is_empty() {
+                                continue;
+                            }
+                            let rect = tiles[tile];
+                            let mut target = source.region(rect.min_x, rect.min_y, rect.width(), rect.height());
+                            let commands = bins[tile].iter().map(|&(index, bounds)| (&self.commands[index], bounds));
+                            draw_commands(&mut target, &rect, commands, shade);
+                            done.push((rect, target));
+                        }
+                        done
+                    })
+                })
+                .collect();
+            workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+        });
+
+        for (rect, tile) in &rendered {
+            framebuffer.write_region(rect.min_x, rect.min_y, tile);
+        }
+    }
+}
+
+impl<S: Sync> Default for DrawList<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Dibuja comandos en `target`, que cubre la región `origin` de la pantalla.
+/// Cada comando viene con los píxeles que le tocan dentro de esa región.
+fn draw_commands<'a, S: 'a, F>(
+    target: &mut Framebuffer,
+    origin: &PixelRect,
+    commands: impl Iterator<Item = (&'a DrawCommand<S>, PixelRect)>,
+    shade: &F,
+) where
+    F: Fn(&S, &Fragment) -> Option<Color>,
+{
+    let depth_write = target.depth_write_enabled;
+    for (command, bounds) in commands {
+        target.depth_write_enabled = command.depth_write;
+        // Cada fragmento se sombrea y se escribe apenas sale del rasterizer
+        let mut output = |fragment: &Fragment| {
+            if let Some(color) = shade(&command.shading, fragment) {
+                let x = fragment.screen_x as u32 - origin.min_x;
+                let y = fragment.screen_y as u32 - origin.min_y;
+                match command.blend {
+                    BlendMode::Opaque => target.set_pixel_with_depth(x, y, fragment.depth, color),
+                    BlendMode::Alpha => target.blend_pixel_with_depth(x, y, fragment.depth, color),
+                };
+            }
+        };
+
+        match &command.primitive {
+            Primitive::Triangle(triangle) => {
+                rasterize_triangle(triangle, bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y, output);
+            }
+            Primitive::Rect { depth, .. } => {
+                for y in bounds.min_y..=bounds.max_y {
+                    for x in bounds.min_x..=bounds.max_x {
+                        output(&Fragment {
+                            screen_x: x as i32,
+                            screen_y: y as i32,
+                            depth: *depth,
+                            varyings: Varyings::default(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    target.depth_write_enabled = depth_write;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vertex_shader::VertexShaderOutput;
+
+    fn vertex(x: f32, y: f32, depth: f32, color: Color) -> VertexShaderOutput {
+        VertexShaderOutput {
+            clip_position: Vector4::new(0.0, 0.0, 0.0, 1.0),
+            screen_position: Vector3::new(x, y, depth),
+            inv_w: 1.0,
+            varyings: Varyings { color, ..Varyings::default() },
+        }
+    }
+
+    /// Triángulos superpuestos de varios tamaños, opacos y translúcidos
+    fn scene() -> DrawList<()> {
+        let mut list = DrawList::new();
+        for i in 0..40 {
+            let f = i as f32;
+            let color = Color::new((i * 37 % 256) as u8, (i * 91 % 256) as u8, (i * 53 % 256) as u8, 100 + i as u8);
+            let (x, y) = ((f * 17.3) % 180.0 - 20.0, (f * 11.7) % 120.0 - 10.0);
+            let size = 10.0 + (f * 7.1) % 90.0;
+            list.push(DrawCommand {
+                primitive: Primitive::Triangle(Triangle::new(
+                    vertex(x, y, 0.5 - f * 0.01, color),
+                    vertex(x + size, y + size * 0.3, 0.4, color),
+                    vertex(x + size * 0.2, y + size, 0.6, color),
+                )),
+                shading: (),
+                blend: if i % 3 == 0 { BlendMode::Alpha } else { BlendMode::Opaque },
+                depth_write: i % 3 != 0,
+            });
+        }
+        list.push(DrawCommand {
+            primitive: Primitive::Rect { rect: PixelRect { min_x: 50, min_y: 20, max_x: 150, max_y: 90 }, depth: 0.45 },
+            shading: (),
+            blend: BlendMode::Alpha,
+            depth_write: false,
+        });
+        list
+    }
+
+    fn render(threads: usize) -> Framebuffer {
+        // Tamaño que no es múltiplo de TILE_SIZE: hay tiles incompletos
+        let mut framebuffer = Framebuffer::new(TILE_SIZE * 2 + 37, TILE_SIZE + 13);
+        framebuffer.clear();
+        scene().execute(&mut framebuffer, threads, |_, fragment| {
+            if fragment.screen_x % 5 == 0 {
+                return None; // Descartar algunos fragmentos
+            }
+            let color = fragment.varyings.color;
+            Some(if color.a == 0 { Color::new(255, 255, 255, 128) } else { color })
+        });
+        framebuffer
+    }
+
+    #[test]
+    fn test_tiled_matches_single_threaded() {
+        let reference = render(1);
+        assert!(reference.pixels.iter().any(|&pixel| pixel != Color::BLACK));
+        for threads in [2, 3, 8] {
+            let tiled = render(threads);
+            assert_eq!(tiled.pixels, reference.pixels, "{} threads", threads);
+            assert_eq!(tiled.depth, reference.depth, "{} threads", threads);
+        }
+    }
+
+    #[test]
+    fn test_bounds_are_clipped_to_screen() {
+        let screen = PixelRect { min_x: 0, min_y: 0, max_x: 99, max_y: 49 };
+        let triangle = Triangle::new(
+            vertex(-20.0, -5.0, 0.0, Color::WHITE),
+            vertex(150.0, 10.0, 0.0, Color::WHITE),
+            vertex(30.5, 20.2, 0.0, Color::WHITE),
+        );
+        let bounds = Primitive::Triangle(triangle).bounds(&screen).unwrap();
+        assert_eq!(bounds, PixelRect { min_x: 0, min_y: 0, max_x: 99, max_y: 21 });
+
+        let offscreen = Triangle::new(
+            vertex(-20.0, -5.0, 0.0, Color::WHITE),
+            vertex(-10.0, -5.0, 0.0, Color::WHITE),
+            vertex(-15.0, -1.0, 0.0, Color::WHITE),
+        );
+        assert!(Primitive::Triangle(offscreen).bounds(&screen).is_none());
+    }
+}
@@ -0,0 +1,209 @@
+//! Varyings - Atributos que van del vertex shader al fragment shader
+//!
+//! El vertex shader calcula los atributos en cada vértice y el rasterizer los
+//! interpola en cada píxel del triángulo. En pantalla no varía linealmente un
+//! atributo `a` sino `a / w` (y `1 / w`): interpolar directamente con las
+//! coordenadas baricéntricas de pantalla deforma todo lo que cambia a lo
+//! largo de un triángulo grande. Por eso el rasterizer pesa cada vértice por
+//! su `1 / w` (ver `perspective_weights`).
+//!
+//! Todos los atributos se declaran en `varyings!`. Para agregar uno basta con
+//! sumarlo ahí y darle valor en el vertex shader: el clipping y el rasterizer
+//! lo interpolan sin más cambios.
+
+use raylib::prelude::*;
+
+/// Un atributo que se puede interpolar
+pub trait Varying: Copy {
+    /// Suma ponderada de los valores en los tres vértices
+    fn weighted_sum(values: [Self; 3], weights: [f32; 3]) -> Self;
+
+    /// Punto `t` entre dos valores (0 = `a`, 1 = `b`)
+    fn lerp(a: Self, b: Self, t: f32) -> Self;
+}
+
+impl Varying for f32 {
+    fn weighted_sum(values: [Self; 3], weights: [f32; 3]) -> Self {
+        values[0] * weights[0] + values[1] * weights[1] + values[2] * weights[2]
+    }
+
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a * (1.0 - t) + b * t
+    }
+}
+
+impl Varying for Vector2 {
+    fn weighted_sum(values: [Self; 3], weights: [f32; 3]) -> Self {
+        values[0] * weights[0] + values[1] * weights[1] + values[2] * weights[2]
+    }
+
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a * (1.0 - t) + b * t
+    }
+}
+
+impl Varying for Vector3 {
+    fn weighted_sum(values: [Self; 3], weights: [f32; 3]) -> Self {
+        values[0] * weights[0] + values[1] * weights[1] + values[2] * weights[2]
+    }
+
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a * (1.0 - t) + b * t
+    }
+}
+
+impl Varying for Vector4 {
+    fn weighted_sum(values: [Self; 3], weights: [f32; 3]) -> Self {
+        let component = |get: fn(&Vector4) -> f32| f32::weighted_sum(values.map(|v| get(&v)), weights);
+        Vector4::new(component(|v| v.x), component(|v| v.y), component(|v| v.z), component(|v| v.w))
+    }
+
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        let component = |get: fn(&Vector4) -> f32| f32::lerp(get(&a), get(&b), t);
+        Vector4::new(component(|v| v.x), component(|v| v.y), component(|v| v.z), component(|v| v.w))
+    }
+}
+
+impl Varying for Color {
+    /// Los canales se interpolan en punto flotante y se redondean
+    fn weighted_sum(values: [Self; 3], weights: [f32; 3]) -> Self {
+        let channel = |get: fn(&Color) -> u8| {
+            f32::weighted_sum(values.map(|c| get(&c) as f32), weights).round().clamp(0.0, 255.0) as u8
+        };
+        Color::new(channel(|c| c.r), channel(|c| c.g), channel(|c| c.b), channel(|c| c.a))
+    }
+
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        let channel = |get: fn(&Color) -> u8| f32::lerp(get(&a) as f32, get(&b) as f32, t).round().clamp(0.0, 255.0) as u8;
+        Color::new(channel(|c| c.r), channel(|c| c.g), channel(|c| c.b), channel(|c| c.a))
+    }
+}
+
+/// Declara la estructura de varyings y su interpolación campo por campo
+macro_rules! varyings {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $($(#[$field_meta:meta])* pub $field:ident: $ty:ty,)*
+        }
+    ) => {
+        $(#[$meta])*
+        pub struct $name {
+            $($(#[$field_meta])* pub $field: $ty,)*
+        }
+
+        impl $name {
+            /// Suma ponderada de los varyings de tres vértices
+            pub fn weighted_sum(values: [&Self; 3], weights: [f32; 3]) -> Self {
+                $name {
+                    $($field: Varying::weighted_sum(values.map(|v| v.$field), weights),)*
+                }
+            }
+
+            /// Punto `t` del segmento `a`→`b`, campo por campo
+            pub fn lerp(a: &Self, b: &Self, t: f32) -> Self {
+                $name {
+                    $($field: Varying::lerp(a.$field, b.$field, t),)*
+                }
+            }
+        }
+    };
+}
+
+varyings! {
+    /// Atributos interpolados de un vértice o de un fragmento
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub struct Varyings {
+        /// Posición en world space (para sombras e iluminación por punto)
+        pub world_position: Vector3,
+        /// Posición en object space (para texturas procedurales fijas a la superficie)
+        pub object_position: Vector3,
+        /// Normal en world space (interpolada puede no estar normalizada)
+        pub normal: Vector3,
+        /// Color del vértice
+        pub color: Color,
+        /// Coordenadas de textura
+        pub uv: Vector2,
+    }
+}
+
+impl Varyings {
+    /// Valor en un píxel con corrección de perspectiva, a partir de las
+    /// coordenadas baricéntricas en pantalla y el 1 / w de cada vértice
+    pub fn interpolate(values: [&Self; 3], barycentric: [f32; 3], inv_w: [f32; 3]) -> Self {
+        Self::weighted_sum(values, perspective_weights(barycentric, inv_w))
+    }
+}
+
+/// Convierte coordenadas baricéntricas de pantalla en los pesos que
+/// interpolan correctamente en perspectiva: cada vértice pesa `b_i / w_i`,
+/// normalizado para que los pesos sumen 1.
+pub fn perspective_weights(barycentric: [f32; 3], inv_w: [f32; 3]) -> [f32; 3] {
+    let weights = [0, 1, 2].map(|i| barycentric[i] * inv_w[i]);
+    let total = weights[0] + weights[1] + weights[2];
+    if total.abs() < f32::EPSILON {
+        return barycentric;
+    }
+    weights.map(|weight| weight / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varyings(uv: Vector2, color: Color) -> Varyings {
+        Varyings {
+            world_position: Vector3::new(uv.x, uv.y, 0.0),
+            object_position: Vector3::zero(),
+            normal: Vector3::new(0.0, 0.0, 1.0),
+            color,
+            uv,
+        }
+    }
+
+    #[test]
+    fn test_color_interpolation() {
+        let (white, black) = (varyings(Vector2::zero(), Color::WHITE), varyings(Vector2::zero(), Color::BLACK));
+
+        // 50% blanco, 50% negro = gris
+        let result = Varyings::weighted_sum([&white, &black, &black], [0.5, 0.25, 0.25]);
+        assert_eq!(result.color, Color::new(128, 128, 128, 255));
+    }
+
+    #[test]
+    fn test_interpolation_is_perspective_correct() {
+        // Mismo w: igual que la interpolación lineal
+        let (a, b) = (varyings(Vector2::new(0.0, 0.0), Color::WHITE), varyings(Vector2::new(1.0, 0.0), Color::WHITE));
+        let result = Varyings::interpolate([&a, &b, &b], [0.5, 0.25, 0.25], [1.0; 3]);
+        assert!((result.uv.x - 0.5).abs() < 1e-6);
+
+        // El vértice lejano (w = 3) ocupa menos pantalla: a mitad de camino en
+        // pantalla solo se ha recorrido un cuarto del triángulo, para todos
+        // los atributos por igual
+        let result = Varyings::interpolate([&a, &b, &b], [0.5, 0.5, 0.0], [1.0, 1.0 / 3.0, 1.0 / 3.0]);
+        assert!((result.uv.x - 0.25).abs() < 1e-6);
+        assert!((result.world_position.x - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_perspective_weights_sum_to_one() {
+        let weights = perspective_weights([0.2, 0.3, 0.5], [1.0, 0.1, 0.01]);
+        assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+        // Los vértices más cercanos (1/w mayor) pesan más
+        assert!(weights[0] > 0.2 && weights[2] < 0.5);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let (a, b) = (varyings(Vector2::new(0.0, 0.0), Color::BLACK), varyings(Vector2::new(1.0, 2.0), Color::WHITE));
+        let middle = Varyings::lerp(&a, &b, 0.5);
+        assert_eq!(middle.uv, Vector2::new(0.5, 1.0));
+        assert_eq!(middle.color, Color::new(128, 128, 128, 255));
+        assert_eq!(Varyings::lerp(&a, &b, 0.0), a);
+        assert_eq!(Varyings::lerp(&a, &b, 1.0), b);
+
+        // Posiciones en clip space (las usa el clipping)
+        let w = <Vector4 as Varying>::lerp(Vector4::new(0.0, 0.0, 0.0, 1.0), Vector4::new(4.0, 0.0, 0.0, 3.0), 0.25);
+        assert_eq!(w, Vector4::new(1.0, 0.0, 0.0, 1.5));
+    }
+}
@@ -4,27 +4,36 @@
 //! Aplica las 4 matrices de transformación en orden:
 //! 1. Model Matrix (espacio modelo → mundo)
 //! 2. View Matrix (espacio mundo → cámara)
-//! 3. Projection Matrix (espacio cámara → clip/perspectiva)
-//! 4. Viewport Matrix (espacio clip → pantalla)
+//! 3. Projection Matrix (espacio cámara → clip space homogéneo)
+//! 4. Viewport Matrix (NDC → pantalla, después de la división por W)
+//!
+//! La posición en clip space se conserva para que los triángulos que cruzan
+//! el near o el far plane se puedan recortar (ver `clipping`) antes de la
+//! división por W. Para un vértice detrás de la cámara (w ≤ 0) la posición
+//! en pantalla no tiene sentido; el clipping lo reemplaza.
 
 use raylib::prelude::*;
-use crate::matrix::Matrix;
+use crate::matrix::{multiply_matrix_vector4, Matrix};
+use crate::obj_loader::Vertex;
+use crate::varyings::Varyings;
 
 /// Representa un vértice transformado por el vertex shader
 #[derive(Clone, Copy, Debug)]
 pub struct VertexShaderOutput {
+    /// Posición homogénea en clip space, antes de dividir por W
+    pub clip_position: Vector4,
     /// Posición final en screen space (después de viewport transform)
     pub screen_position: Vector3,
-    /// Normal transformada al world space (para iluminación)
-    pub world_normal: Vector3,
-    /// Color del vértice
-    pub color: Color,
+    /// 1 / w en clip space: el rasterizer lo usa para interpolar los
+    /// varyings con corrección de perspectiva
+    pub inv_w: f32,
+    /// Atributos que se interpolan hasta el fragment shader
+    pub varyings: Varyings,
 }
 
 /// Vertex Shader - Transforma un vértice usando las 4 matrices
 pub fn vertex_shader(
-    vertex_position: Vector3,
-    vertex_normal: Vector3,
+    vertex: &Vertex,
     vertex_color: Color,
     model_matrix: &Matrix,
     view_matrix: &Matrix,
@@ -34,39 +43,54 @@ pub fn vertex_shader(
     
     // PASO 1: Model Space → World Space
     // Aplicar escala y traslación del objeto
-    let world_position = model_matrix.transform_point(vertex_position);
+    let world_position = model_matrix.transform_point(vertex.position);
     
     // Transformar normal (solo rotación, no traslación)
-    let world_normal = transform_normal(vertex_normal, model_matrix);
+    let world_normal = transform_normal(vertex.normal, model_matrix);
     
     // PASO 2: World Space → View Space (Camera Space)
     // Transformar al espacio de la cámara
     let view_position = view_matrix.transform_point(world_position);
     
-    // PASO 3: View Space → Clip Space (Perspective)
-    // Aplicar proyección perspectiva
-    let clip_position = projection_matrix.transform_point(view_position);
-    
-    // PASO 4: Perspective Divide (Clip Space → NDC)
-    // Dividir por W para obtener coordenadas normalizadas [-1, 1]
-    let w = clip_position.z.max(0.001); // Evitar división por 0
-    let ndc_position = Vector3::new(
-        clip_position.x / w,
-        clip_position.y / w,
-        clip_position.z / w,
+    // PASO 3: View Space → Clip Space
+    let clip_position = multiply_matrix_vector4(
+        projection_matrix,
+        &Vector4::new(view_position.x, view_position.y, view_position.z, 1.0),
     );
-    
-    // PASO 5: NDC → Screen Space
-    // Transformar a coordenadas de pantalla [0, width] x [0, height]
-    let screen_position = viewport_matrix.transform_point(ndc_position);
+
+    // PASO 4 y 5: Clip Space → NDC → Screen Space
+    let (screen_position, inv_w) = clip_to_screen(clip_position, viewport_matrix);
     
     VertexShaderOutput {
+        clip_position,
         screen_position,
-        world_normal,
-        color: vertex_color,
+        inv_w,
+        varyings: Varyings {
+            world_position,
+            object_position: vertex.position,
+            normal: world_normal,
+            color: vertex_color,
+            uv: vertex.uv,
+        },
     }
 }
 
+/// División por W y viewport: de clip space a coordenadas de pantalla.
+///
+/// El resultado de la división está en coordenadas normalizadas [-1, 1]; la
+/// Z en NDC es la profundidad que usa el z-buffer (menor = más cerca). La
+/// viewport lleva X e Y a [0, width] x [0, height]. También devuelve 1 / w,
+/// que el rasterizer usa para interpolar con corrección de perspectiva.
+pub fn clip_to_screen(clip_position: Vector4, viewport_matrix: &Matrix) -> (Vector3, f32) {
+    let inv_w = if clip_position.w != 0.0 { 1.0 / clip_position.w } else { 1.0 };
+    let ndc_position = Vector3::new(
+        clip_position.x * inv_w,
+        clip_position.y * inv_w,
+        clip_position.z * inv_w,
+    );
+    (viewport_matrix.transform_point(ndc_position), inv_w)
+}
+
 /// Transforma una normal usando solo la rotación de la matriz modelo
 /// (Las normales no se trasladan, solo rotan)
 fn transform_normal(normal: Vector3, model_matrix: &Matrix) -> Vector3 {
@@ -102,8 +126,7 @@ mod tests {
     fn test_vertex_shader_identity_matrices() {
         let identity = Matrix::identity();
         let result = vertex_shader(
-            Vector3::new(1.0, 1.0, 1.0),
-            Vector3::new(0.0, 1.0, 0.0),
+            &Vertex::new(Vector3::new(1.0, 1.0, 1.0), Vector3::new(0.0, 1.0, 0.0), Vector2::new(0.25, 0.75)),
             Color::WHITE,
             &identity,
             &identity,
@@ -114,5 +137,7 @@ mod tests {
         // Con matrices identidad, la posición debería mantenerse
         assert_eq!(result.screen_position.x, 1.0);
         assert_eq!(result.screen_position.y, 1.0);
+        assert_eq!(result.inv_w, 1.0);
+        assert_eq!(result.varyings.uv, Vector2::new(0.25, 0.75));
     }
 }